@@ -0,0 +1,711 @@
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use parser::{
+    EnumerationType, File, FileHash, LayoutItem, Member, Namespace, StructType, Type, TypeDef,
+    TypeKind, TypeModifierKind, TypeOffset, UnionType,
+};
+
+use crate::filter;
+use crate::{Options, Result};
+
+/// Print C declarations for the types that match the filter options.
+///
+/// The types that these depend on are declared first. Types that are only
+/// referenced by pointers are forward declared, which allows cyclic types.
+/// Anonymous types are defined inline.
+///
+/// Struct members are placed at their debuginfo offsets using explicit padding
+/// members, and the size of each struct, union and enumeration is checked with
+/// a `_Static_assert`. Variadic function types are declared with `...`, and
+/// function types without a prototype are declared with `()`. Structs with members that aren't naturally aligned are
+/// declared with `__attribute__((packed))`, assuming that the alignment of a
+/// scalar type is its size.
+///
+/// C++ tags and typedef names are qualified with their namespace, and characters
+/// that aren't valid in C identifiers, such as in template arguments, are replaced.
+pub fn c_header(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let mut header = Header::new(file, &hash);
+    writeln!(w, "/* Generated by ddbug from {}. */", file.path())?;
+    for unit in filter::filter_units(file, options) {
+        for ty in filter::filter_types(unit, &hash, options, false) {
+            header.require(w, ty, true)?;
+        }
+    }
+    Ok(())
+}
+
+struct Header<'a, 'input> {
+    hash: &'a FileHash<'input>,
+    // The definitions of named structs, unions and enumerations, for use in
+    // place of declarations.
+    definitions: HashMap<String, TypeOffset>,
+    // The types that are being defined.
+    visiting: HashSet<TypeOffset>,
+    declared: HashSet<String>,
+    defined: HashSet<String>,
+}
+
+impl<'a, 'input> Header<'a, 'input> {
+    fn new(file: &File<'input>, hash: &'a FileHash<'input>) -> Self {
+        Header {
+            hash,
+            definitions: definitions(file),
+            visiting: HashSet::new(),
+            declared: HashSet::new(),
+            defined: HashSet::new(),
+        }
+    }
+
+    /// Print the declarations that are needed before `ty` can be used.
+    ///
+    /// If `complete` is false, then `ty` is only used by a pointer.
+    fn require(&mut self, w: &mut dyn Write, ty: &Type<'input>, complete: bool) -> Result<()> {
+        let hash = self.hash;
+        match *ty.kind() {
+            TypeKind::Void
+            | TypeKind::Base(..)
+            | TypeKind::Unspecified(..)
+            | TypeKind::PointerToMember(..) => {}
+            TypeKind::Def(ref val) => self.require_type_def(w, ty.offset(), val, complete)?,
+            TypeKind::Struct(ref val) => match struct_tag(val) {
+                Some(tag) => self.require_tag(w, ty, tag, val.is_declaration(), complete)?,
+                None => self.require_anon(w, ty)?,
+            },
+            TypeKind::Union(ref val) => match union_tag(val) {
+                Some(tag) => self.require_tag(w, ty, tag, val.is_declaration(), complete)?,
+                None => self.require_anon(w, ty)?,
+            },
+            TypeKind::Enumeration(ref val) => {
+                // Enumerations can't be forward declared.
+                if let Some(tag) = enumeration_tag(val) {
+                    self.require_tag(w, ty, tag, val.is_declaration(), true)?;
+                }
+            }
+            TypeKind::Array(ref val) => {
+                if let Some(ty) = val.element_type(hash) {
+                    self.require(w, &ty, true)?;
+                }
+            }
+            TypeKind::Function(ref val) => {
+                if let Some(ty) = val.return_type(hash) {
+                    self.require(w, &ty, false)?;
+                }
+                for parameter in val.parameters() {
+                    if let Some(ty) = parameter.ty(hash) {
+                        self.require(w, &ty, false)?;
+                    }
+                }
+            }
+            TypeKind::Modifier(ref val) => {
+                if let Some(target) = val.ty(hash) {
+                    match val.kind() {
+                        TypeModifierKind::Pointer
+                        | TypeModifierKind::Reference
+                        | TypeModifierKind::RvalueReference => self.require(w, &target, false)?,
+                        _ => self.require(w, &target, complete)?,
+                    }
+                }
+            }
+            TypeKind::Subrange(ref val) => {
+                if let Some(ty) = val.ty(hash) {
+                    self.require(w, &ty, complete)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn require_type_def(
+        &mut self,
+        w: &mut dyn Write,
+        offset: TypeOffset,
+        val: &TypeDef<'input>,
+        complete: bool,
+    ) -> Result<()> {
+        let hash = self.hash;
+        let target = match val.ty(hash) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        let name = type_def_name(val);
+        let tag = format!("typedef {}", name);
+        if !self.defined.contains(&tag) && self.visiting.insert(offset) {
+            self.require(w, &target, false)?;
+            self.visiting.remove(&offset);
+            if self.defined.insert(tag) {
+                let declaration = self.declaration(&target, &name, 0);
+                writeln!(w)?;
+                writeln!(w, "typedef {};", declaration)?;
+            }
+        }
+        if complete {
+            self.require(w, &target, true)?;
+        }
+        Ok(())
+    }
+
+    fn require_tag(
+        &mut self,
+        w: &mut dyn Write,
+        ty: &Type<'input>,
+        tag: String,
+        declaration: bool,
+        complete: bool,
+    ) -> Result<()> {
+        let hash = self.hash;
+        if self.defined.contains(&tag) {
+            return Ok(());
+        }
+        if complete {
+            if !declaration {
+                if self.define(w, ty, &tag)? {
+                    return Ok(());
+                }
+            } else if let Some(definition) = self
+                .definitions
+                .get(&tag)
+                .and_then(|offset| Type::from_offset(hash, *offset))
+            {
+                if self.define(w, &definition, &tag)? {
+                    return Ok(());
+                }
+            }
+        }
+        if !self.declared.contains(&tag) {
+            match *ty.kind() {
+                TypeKind::Struct(..) | TypeKind::Union(..) => {
+                    writeln!(w)?;
+                    writeln!(w, "{};", tag)?;
+                    self.declared.insert(tag);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Anonymous types are defined inline, so require their members instead.
+    fn require_anon(&mut self, w: &mut dyn Write, ty: &Type<'input>) -> Result<()> {
+        if self.visiting.insert(ty.offset()) {
+            self.require_members(w, ty)?;
+            self.visiting.remove(&ty.offset());
+        }
+        Ok(())
+    }
+
+    fn require_members(&mut self, w: &mut dyn Write, ty: &Type<'input>) -> Result<()> {
+        let hash = self.hash;
+        if let TypeKind::Struct(ref val) = *ty.kind() {
+            for inherit in val.inherits() {
+                if let Some(ty) = inherit.ty(hash) {
+                    self.require(w, &ty, true)?;
+                }
+            }
+        }
+        for member in ty.members() {
+            if let Some(ty) = member.ty(hash) {
+                self.require(w, &ty, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the definition of a named type after the types it depends on.
+    ///
+    /// Returns false if the type is already being defined.
+    fn define(&mut self, w: &mut dyn Write, ty: &Type<'input>, tag: &str) -> Result<bool> {
+        if !self.visiting.insert(ty.offset()) {
+            return Ok(false);
+        }
+        self.require_members(w, ty)?;
+        self.visiting.remove(&ty.offset());
+        if !self.defined.insert(tag.to_string()) {
+            return Ok(true);
+        }
+        let (body, byte_size) = match *ty.kind() {
+            TypeKind::Struct(ref val) => (self.struct_body(val, 0), val.byte_size()),
+            TypeKind::Union(ref val) => (self.union_body(val, 0), val.byte_size()),
+            TypeKind::Enumeration(ref val) => {
+                (self.enumeration_body(val, 0), val.byte_size(self.hash))
+            }
+            _ => return Ok(true),
+        };
+        writeln!(w)?;
+        writeln!(w, "{} {};", tag, body)?;
+        if let Some(byte_size) = byte_size {
+            writeln!(
+                w,
+                "_Static_assert(sizeof({}) == {}, \"{}\");",
+                tag, byte_size, tag
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Return the declaration of `name` with the given type.
+    ///
+    /// `name` may be empty for abstract declarators.
+    fn declaration(&self, ty: &Type<'input>, name: &str, indent: usize) -> String {
+        let hash = self.hash;
+        match *ty.kind() {
+            TypeKind::Void => join("void", name),
+            TypeKind::Base(ref val) => join(val.name().unwrap_or("<anon-base-type>"), name),
+            TypeKind::Def(ref val) => join(&type_def_name(val), name),
+            TypeKind::Struct(ref val) => match struct_tag(val) {
+                Some(tag) => join(&tag, name),
+                None => join(&format!("struct {}", self.struct_body(val, indent)), name),
+            },
+            TypeKind::Union(ref val) => match union_tag(val) {
+                Some(tag) => join(&tag, name),
+                None => join(&format!("union {}", self.union_body(val, indent)), name),
+            },
+            TypeKind::Enumeration(ref val) => match enumeration_tag(val) {
+                Some(tag) => join(&tag, name),
+                None => join(
+                    &format!("enum {}", self.enumeration_body(val, indent)),
+                    name,
+                ),
+            },
+            TypeKind::Array(ref val) => {
+                let name = match val.count(hash) {
+                    Some(count) => format!("{}[{}]", name, count),
+                    None => format!("{}[]", name),
+                };
+                match val.element_type(hash) {
+                    Some(ty) => self.declaration(&ty, &name, indent),
+                    None => join("<invalid>", &name),
+                }
+            }
+            TypeKind::Function(ref val) => {
+                let mut parameters: Vec<_> = val
+                    .parameters()
+                    .iter()
+                    .map(|parameter| match parameter.ty(hash) {
+                        Some(ty) => self.declaration(&ty, parameter.name().unwrap_or(""), indent),
+                        None => "<invalid>".to_string(),
+                    })
+                    .collect();
+                // Declarations without a prototype are written as `()`, which
+                // isn't the same as `(void)`. C requires a named parameter before
+                // `...`, so variadic functions without one are written this way too.
+                if !parameters.is_empty() {
+                    if val.is_variadic() {
+                        parameters.push("...".to_string());
+                    }
+                } else if val.is_prototyped() && !val.is_variadic() {
+                    parameters.push("void".to_string());
+                }
+                let name = format!("{}({})", name, parameters.join(", "));
+                match val.return_type(hash) {
+                    Some(ty) => self.declaration(&ty, &name, indent),
+                    None => join("<invalid>", &name),
+                }
+            }
+            TypeKind::Unspecified(..) => join("void", name),
+            TypeKind::PointerToMember(ref val) => {
+                // C has no pointers to members, so only preserve the size.
+                let name = format!(
+                    "{}[{}]",
+                    parenthesize(name),
+                    val.byte_size(hash).unwrap_or(0)
+                );
+                join("unsigned char", &name)
+            }
+            TypeKind::Modifier(ref val) => {
+                let target = match val.ty(hash) {
+                    Some(target) => target,
+                    None => return join("<invalid>", name),
+                };
+                let qualifier = match val.kind() {
+                    TypeModifierKind::Pointer
+                    | TypeModifierKind::Reference
+                    | TypeModifierKind::RvalueReference => {
+                        let name = format!("*{}", name);
+                        let name = if self.is_array_or_function(&target) {
+                            format!("({})", name)
+                        } else {
+                            name
+                        };
+                        return self.declaration(&target, &name, indent);
+                    }
+                    TypeModifierKind::Const => "const",
+                    TypeModifierKind::Volatile => "volatile",
+                    TypeModifierKind::Restrict => "restrict",
+                    TypeModifierKind::Atomic => "_Atomic",
+                    TypeModifierKind::Packed
+                    | TypeModifierKind::Shared
+                    | TypeModifierKind::Other => {
+                        return self.declaration(&target, name, indent);
+                    }
+                };
+                if self.is_pointer(&target) {
+                    self.declaration(&target, &join(qualifier, name), indent)
+                } else {
+                    format!("{} {}", qualifier, self.declaration(&target, name, indent))
+                }
+            }
+            TypeKind::Subrange(ref val) => match val.ty(hash) {
+                Some(ty) => self.declaration(&ty, name, indent),
+                None => join("<invalid>", name),
+            },
+        }
+    }
+
+    fn struct_body(&self, val: &StructType<'input>, indent: usize) -> String {
+        let hash = self.hash;
+        let mut body = String::from("{\n");
+        let mut padding_index = 0;
+        for layout in val.layout(hash) {
+            match layout.item {
+                LayoutItem::Padding => {
+                    if let Some(bit_size) = layout.bit_size.get() {
+                        self.padding(
+                            &mut body,
+                            layout.bit_offset,
+                            bit_size,
+                            indent + 1,
+                            &mut padding_index,
+                        );
+                    }
+                }
+                LayoutItem::Member(member) => {
+                    let declaration = self.member(member, indent + 1);
+                    line(&mut body, indent + 1, &declaration);
+                }
+                LayoutItem::Inherit(inherit) => {
+                    let name = format!("__base{}", layout.bit_offset / 8);
+                    let declaration = match inherit.ty(hash) {
+                        Some(ty) => self.declaration(&ty, &name, indent + 1),
+                        None => join("<invalid>", &name),
+                    };
+                    line(&mut body, indent + 1, &declaration);
+                }
+                LayoutItem::VariantPart(..) => {
+                    // C has no variant parts, so only preserve the size.
+                    if let Some(bit_size) = layout.bit_size.get() {
+                        self.padding(
+                            &mut body,
+                            layout.bit_offset,
+                            bit_size,
+                            indent + 1,
+                            &mut padding_index,
+                        );
+                    }
+                }
+            }
+        }
+        indent_to(&mut body, indent);
+        body.push('}');
+        if is_packed(val, self.hash) {
+            body.push_str(" __attribute__((packed))");
+        }
+        body
+    }
+
+    fn union_body(&self, val: &UnionType<'input>, indent: usize) -> String {
+        let hash = self.hash;
+        let mut body = String::from("{\n");
+        let mut bit_size = 0;
+        for member in val.members() {
+            let declaration = self.member(member, indent + 1);
+            line(&mut body, indent + 1, &declaration);
+            bit_size = cmp::max(bit_size, member.bit_size(hash).unwrap_or(0));
+        }
+        if let Some(byte_size) = val.byte_size() {
+            if bit_size < byte_size * 8 {
+                line(
+                    &mut body,
+                    indent + 1,
+                    &format!("char __padding0[{}]", byte_size),
+                );
+            }
+        }
+        indent_to(&mut body, indent);
+        body.push('}');
+        body
+    }
+
+    fn enumeration_body(&self, val: &EnumerationType<'input>, indent: usize) -> String {
+        let mut body = String::from("{\n");
+        for enumerator in val.enumerators(self.hash) {
+            indent_to(&mut body, indent + 1);
+            body.push_str(enumerator.name().unwrap_or("<anon>"));
+            if let Some(value) = enumerator.value() {
+                body.push_str(&format!(" = {}", value));
+            }
+            body.push_str(",\n");
+        }
+        indent_to(&mut body, indent);
+        body.push('}');
+        body
+    }
+
+    fn member(&self, member: &Member<'input>, indent: usize) -> String {
+        let hash = self.hash;
+        let ty = match member.ty(hash) {
+            Some(ty) => ty,
+            None => return join("<invalid>", member.name().unwrap_or("")),
+        };
+        let declaration = self.declaration(&ty, member.name().unwrap_or(""), indent);
+        let bit_size = member.bit_size(hash);
+        let type_bit_size = ty.byte_size(hash).map(|v| v * 8);
+        match bit_size {
+            Some(bit_size) if member.bit_offset() % 8 != 0 || Some(bit_size) != type_bit_size => {
+                format!("{} : {}", declaration, bit_size)
+            }
+            _ => declaration,
+        }
+    }
+
+    /// Add explicit padding members for the given bit range.
+    ///
+    /// Partial bytes use unnamed bitfields.
+    fn padding(
+        &self,
+        body: &mut String,
+        bit_offset: u64,
+        bit_size: u64,
+        indent: usize,
+        index: &mut usize,
+    ) {
+        let end = bit_offset + bit_size;
+        let byte_start = cmp::min((bit_offset + 7) & !7, end);
+        let byte_end = cmp::max(end & !7, byte_start);
+        if byte_start > bit_offset {
+            line(
+                body,
+                indent,
+                &format!("unsigned int : {}", byte_start - bit_offset),
+            );
+        }
+        if byte_end > byte_start {
+            line(
+                body,
+                indent,
+                &format!("char __padding{}[{}]", index, (byte_end - byte_start) / 8),
+            );
+            *index += 1;
+        }
+        if end > byte_end {
+            line(body, indent, &format!("unsigned int : {}", end - byte_end));
+        }
+    }
+
+    /// Return true if the type is a pointer, ignoring other modifiers.
+    fn is_pointer(&self, ty: &Type<'input>) -> bool {
+        match *ty.kind() {
+            TypeKind::Modifier(ref val) => match val.kind() {
+                TypeModifierKind::Pointer
+                | TypeModifierKind::Reference
+                | TypeModifierKind::RvalueReference => true,
+                _ => match val.ty(self.hash) {
+                    Some(ty) => self.is_pointer(&ty),
+                    None => false,
+                },
+            },
+            _ => false,
+        }
+    }
+
+    /// Return true if the type is an array or function, ignoring qualifiers.
+    fn is_array_or_function(&self, ty: &Type<'input>) -> bool {
+        match *ty.kind() {
+            TypeKind::Array(..) | TypeKind::Function(..) => true,
+            TypeKind::Modifier(ref val) => match val.kind() {
+                TypeModifierKind::Pointer
+                | TypeModifierKind::Reference
+                | TypeModifierKind::RvalueReference => false,
+                _ => match val.ty(self.hash) {
+                    Some(ty) => self.is_array_or_function(&ty),
+                    None => false,
+                },
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Return the definitions of named structs, unions and enumerations, indexed by tag.
+pub(crate) fn definitions(file: &File) -> HashMap<String, TypeOffset> {
+    let mut definitions = HashMap::new();
+    for unit in file.units() {
+        for ty in unit.types() {
+            let tag = match *ty.kind() {
+                TypeKind::Struct(ref val) if !val.is_declaration() => struct_tag(val),
+                TypeKind::Union(ref val) if !val.is_declaration() => union_tag(val),
+                TypeKind::Enumeration(ref val) if !val.is_declaration() => enumeration_tag(val),
+                _ => None,
+            };
+            if let Some(tag) = tag {
+                definitions.entry(tag).or_insert_with(|| ty.offset());
+            }
+        }
+    }
+    definitions
+}
+
+pub(crate) fn struct_tag(val: &StructType) -> Option<String> {
+    val.name()
+        .map(|name| format!("struct {}", qualified_ident(val.namespace(), name)))
+}
+
+pub(crate) fn union_tag(val: &UnionType) -> Option<String> {
+    val.name()
+        .map(|name| format!("union {}", qualified_ident(val.namespace(), name)))
+}
+
+pub(crate) fn enumeration_tag(val: &EnumerationType) -> Option<String> {
+    val.name()
+        .map(|name| format!("enum {}", qualified_ident(val.namespace(), name)))
+}
+
+fn type_def_name(val: &TypeDef) -> String {
+    match val.name() {
+        Some(name) => qualified_ident(val.namespace(), name),
+        None => "<anon-typedef>".to_string(),
+    }
+}
+
+/// Convert a possibly qualified C++ name to a valid C identifier.
+pub(crate) fn qualified_ident(namespace: Option<&Namespace>, name: &str) -> String {
+    let mut ident: String = Namespace::qualified_name(namespace, name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Return true if any member or base of the struct isn't naturally aligned,
+/// or the size of the struct isn't a multiple of its natural alignment.
+pub(crate) fn is_packed(val: &StructType, hash: &FileHash) -> bool {
+    let mut align = 1;
+    for layout in val.layout(hash) {
+        let ty = match layout.item {
+            LayoutItem::Member(member) => match member.ty(hash) {
+                // Bitfields don't need to be aligned.
+                Some(ty) if member.bit_size(hash) == ty.byte_size(hash).map(|v| v * 8) => Some(ty),
+                _ => None,
+            },
+            LayoutItem::Inherit(inherit) => inherit.ty(hash),
+            LayoutItem::Padding | LayoutItem::VariantPart(..) => None,
+        };
+        if let Some(ty) = ty {
+            let member_alignment = alignment(&ty, hash);
+            if layout.bit_offset % (member_alignment * 8) != 0 {
+                return true;
+            }
+            align = cmp::max(align, member_alignment);
+        }
+    }
+    match val.byte_size() {
+        Some(byte_size) => byte_size % align != 0,
+        None => false,
+    }
+}
+
+/// Return the natural alignment of a type in bytes.
+pub(crate) fn alignment(ty: &Type, hash: &FileHash) -> u64 {
+    match *ty.kind() {
+        TypeKind::Struct(ref val) => {
+            if is_packed(val, hash) {
+                return 1;
+            }
+            let mut align = 1;
+            for inherit in val.inherits() {
+                if let Some(ty) = inherit.ty(hash) {
+                    align = cmp::max(align, alignment(&ty, hash));
+                }
+            }
+            for member in val.members() {
+                if let Some(ty) = member.ty(hash) {
+                    align = cmp::max(align, alignment(&ty, hash));
+                }
+            }
+            align
+        }
+        TypeKind::Union(ref val) => val
+            .members()
+            .iter()
+            .filter_map(|member| member.ty(hash))
+            .map(|ty| alignment(&ty, hash))
+            .max()
+            .unwrap_or(1),
+        TypeKind::Array(ref val) => match val.element_type(hash) {
+            Some(ty) => alignment(&ty, hash),
+            None => 1,
+        },
+        TypeKind::Def(ref val) => match val.ty(hash) {
+            Some(ty) => alignment(&ty, hash),
+            None => 1,
+        },
+        TypeKind::Subrange(ref val) => match val.ty(hash) {
+            Some(ty) => alignment(&ty, hash),
+            None => 1,
+        },
+        TypeKind::Modifier(ref val) => match val.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => scalar_alignment(ty.byte_size(hash)),
+            _ => match val.ty(hash) {
+                Some(ty) => alignment(&ty, hash),
+                None => 1,
+            },
+        },
+        TypeKind::Base(..)
+        | TypeKind::Enumeration(..)
+        | TypeKind::PointerToMember(..)
+        | TypeKind::Void
+        | TypeKind::Function(..)
+        | TypeKind::Unspecified(..) => scalar_alignment(ty.byte_size(hash)),
+    }
+}
+
+/// Return the alignment of a scalar type of the given size.
+///
+/// This is the largest power of two that divides the size, up to 16.
+fn scalar_alignment(byte_size: Option<u64>) -> u64 {
+    match byte_size {
+        Some(byte_size) if byte_size != 0 => cmp::min(byte_size & byte_size.wrapping_neg(), 16),
+        _ => 1,
+    }
+}
+
+fn join(base: &str, name: &str) -> String {
+    if name.is_empty() {
+        base.to_string()
+    } else {
+        format!("{} {}", base, name)
+    }
+}
+
+fn parenthesize(name: &str) -> String {
+    if name.starts_with('*') {
+        format!("({})", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn indent_to(body: &mut String, indent: usize) {
+    for _ in 0..indent {
+        body.push('\t');
+    }
+}
+
+fn line(body: &mut String, indent: usize, text: &str) {
+    indent_to(body, indent);
+    body.push_str(text);
+    body.push_str(";\n");
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::ptr;
+
+use parser::{File, FileHash, Function, InlinedFunction};
+
+use crate::code::Code;
+use crate::filter;
+use crate::symbolize::function_name;
+use crate::{Options, Result};
+
+/// A key that identifies a function.
+///
+/// Functions from the symbol table don't have a debuginfo offset, so this
+/// uses the address of the `Function` within the file instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct FunctionKey(usize);
+
+impl FunctionKey {
+    pub(crate) fn new(function: &Function) -> Self {
+        FunctionKey(function as *const Function as usize)
+    }
+}
+
+/// A call from one function to another.
+#[derive(Debug, Clone, Copy)]
+pub struct CallEdge<'input> {
+    caller: &'input Function<'input>,
+    callee: &'input Function<'input>,
+    inlined: bool,
+}
+
+impl<'input> CallEdge<'input> {
+    /// The function containing the call.
+    #[inline]
+    pub fn caller(&self) -> &'input Function<'input> {
+        self.caller
+    }
+
+    /// The function that is called.
+    #[inline]
+    pub fn callee(&self) -> &'input Function<'input> {
+        self.callee
+    }
+
+    /// Return true if the callee was inlined into the caller.
+    #[inline]
+    pub fn is_inlined(&self) -> bool {
+        self.inlined
+    }
+}
+
+/// The calls between all functions in a file.
+///
+/// Calls are found by disassembling the code of each function, and from the
+/// inlined functions in the debuginfo.
+#[derive(Debug, Default)]
+pub struct CallGraph<'input> {
+    edges: Vec<CallEdge<'input>>,
+    calls: HashMap<FunctionKey, Vec<usize>>,
+    callers: HashMap<FunctionKey, Vec<usize>>,
+    // Functions containing calls with a target that isn't known statically.
+    indirect: HashSet<FunctionKey>,
+    // Functions containing direct calls to an address that isn't a known
+    // function, such as an imported function.
+    unknown: HashSet<FunctionKey>,
+    // The names of the imported functions called by each function.
+    imports: HashMap<FunctionKey, Vec<&'input str>>,
+    // Functions containing jumps to another function.
+    tail: HashSet<FunctionKey>,
+}
+
+impl<'input> CallGraph<'input> {
+    /// Build the call graph for the file of the given `FileHash`.
+    pub fn new(hash: &'input FileHash<'input>) -> Self {
+        let code = Code::new(hash.file);
+        CallGraph::with_code(hash, code.as_ref())
+    }
+
+    pub(crate) fn with_code(hash: &'input FileHash<'input>, code: Option<&Code<'input>>) -> Self {
+        let mut edges = Vec::new();
+        let mut indirect = HashSet::new();
+        let mut unknown = HashSet::new();
+        let mut imports = HashMap::new();
+        let mut tail = HashSet::new();
+        for unit in hash.file.units() {
+            for function in unit.functions() {
+                let range = match function.range() {
+                    Some(range) => range,
+                    None => continue,
+                };
+                if let Some(code) = code {
+                    let calls = code.call_list(range);
+                    if !calls.indirect.is_empty() {
+                        indirect.insert(FunctionKey::new(function));
+                    }
+                    for call in calls.direct {
+                        if let Some(callee) = hash.functions_by_address.get(&call.to) {
+                            edges.push(CallEdge {
+                                caller: function,
+                                callee: *callee,
+                                inlined: false,
+                            });
+                        } else {
+                            unknown.insert(FunctionKey::new(function));
+                            if let Some(import) = call.import {
+                                let names = imports
+                                    .entry(FunctionKey::new(function))
+                                    .or_insert_with(Vec::new);
+                                if !names.contains(&import) {
+                                    names.push(import);
+                                }
+                            }
+                        }
+                    }
+                    for call in calls.tail {
+                        let callee = hash.functions_by_address.get(&call.to);
+                        if call.import.is_some()
+                            || callee.map_or(false, |callee| !ptr::eq(*callee, function))
+                        {
+                            tail.insert(FunctionKey::new(function));
+                        }
+                    }
+                }
+                let details = function.details(hash);
+                add_inlined_calls(&mut edges, hash, function, details.inlined_functions());
+            }
+        }
+
+        let mut seen = HashSet::new();
+        edges.retain(|x| {
+            seen.insert((
+                FunctionKey::new(x.caller),
+                FunctionKey::new(x.callee),
+                x.inlined,
+            ))
+        });
+
+        let mut calls = HashMap::new();
+        let mut callers = HashMap::new();
+        for (index, edge) in edges.iter().enumerate() {
+            calls
+                .entry(FunctionKey::new(edge.caller))
+                .or_insert_with(Vec::new)
+                .push(index);
+            callers
+                .entry(FunctionKey::new(edge.callee))
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+        CallGraph {
+            edges,
+            calls,
+            callers,
+            indirect,
+            unknown,
+            imports,
+            tail,
+        }
+    }
+
+    /// All calls in the file.
+    #[inline]
+    pub fn edges(&self) -> &[CallEdge<'input>] {
+        &self.edges
+    }
+
+    /// The calls made by the given function.
+    pub fn calls<'a>(
+        &'a self,
+        function: &Function,
+    ) -> impl Iterator<Item = &'a CallEdge<'input>> + 'a {
+        self.lookup(&self.calls, FunctionKey::new(function))
+    }
+
+    /// The calls to the given function.
+    pub fn callers<'a>(
+        &'a self,
+        function: &Function,
+    ) -> impl Iterator<Item = &'a CallEdge<'input>> + 'a {
+        self.lookup(&self.callers, FunctionKey::new(function))
+    }
+
+    /// Return true if the given function contains calls with a target that
+    /// can't be determined statically, such as calls through function pointers.
+    pub fn has_indirect_calls(&self, function: &Function) -> bool {
+        self.indirect.contains(&FunctionKey::new(function))
+    }
+
+    /// Return true if the given function contains direct calls to an address
+    /// that isn't a known function, such as a PLT stub for an imported function.
+    pub fn has_unknown_calls(&self, function: &Function) -> bool {
+        self.unknown.contains(&FunctionKey::new(function))
+    }
+
+    /// Return true if the given function contains jumps to another function,
+    /// such as tail calls. These calls are not included in the graph.
+    pub fn has_tail_calls(&self, function: &Function) -> bool {
+        self.tail.contains(&FunctionKey::new(function))
+    }
+
+    /// The names of the imported functions that are called by the given
+    /// function, such as through PLT stubs.
+    pub fn imports(&self, function: &Function) -> &[&'input str] {
+        self.imports
+            .get(&FunctionKey::new(function))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        map: &'a HashMap<FunctionKey, Vec<usize>>,
+        key: FunctionKey,
+    ) -> impl Iterator<Item = &'a CallEdge<'input>> + 'a {
+        map.get(&key)
+            .into_iter()
+            .flatten()
+            .map(move |&index| &self.edges[index])
+    }
+
+    /// The shortest path of calls from the given function to any function
+    /// for which `target` returns true.
+    ///
+    /// The path is empty if `target` returns true for the given function.
+    pub fn shortest_path<F>(&self, function: &Function, target: F) -> Option<Vec<&CallEdge<'input>>>
+    where
+        F: Fn(&Function) -> bool,
+    {
+        if target(function) {
+            return Some(Vec::new());
+        }
+        // The edge used to first reach each function.
+        let mut prev: HashMap<FunctionKey, Option<&CallEdge>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        prev.insert(FunctionKey::new(function), None);
+        queue.push_back(FunctionKey::new(function));
+        while let Some(key) = queue.pop_front() {
+            for edge in self.lookup(&self.calls, key) {
+                let callee = FunctionKey::new(edge.callee());
+                if prev.contains_key(&callee) {
+                    continue;
+                }
+                prev.insert(callee, Some(edge));
+                if target(edge.callee()) {
+                    let mut path = vec![edge];
+                    let mut key = FunctionKey::new(edge.caller());
+                    while let Some(Some(edge)) = prev.get(&key) {
+                        path.push(*edge);
+                        key = FunctionKey::new(edge.caller());
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                queue.push_back(callee);
+            }
+        }
+        None
+    }
+
+    /// All functions from which a function for which `target` returns true
+    /// can be reached.
+    pub fn reaching<F>(&self, target: F) -> Vec<&'input Function<'input>>
+    where
+        F: Fn(&Function) -> bool,
+    {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        for edge in &self.edges {
+            if target(edge.callee()) && seen.insert(FunctionKey::new(edge.caller())) {
+                queue.push_back(edge.caller());
+            }
+        }
+        let mut functions = Vec::new();
+        while let Some(function) = queue.pop_front() {
+            functions.push(function);
+            for edge in self.callers(function) {
+                if seen.insert(FunctionKey::new(edge.caller())) {
+                    queue.push_back(edge.caller());
+                }
+            }
+        }
+        functions
+    }
+}
+
+fn add_inlined_calls<'input>(
+    edges: &mut Vec<CallEdge<'input>>,
+    hash: &'input FileHash<'input>,
+    caller: &'input Function<'input>,
+    inlined_functions: &[InlinedFunction<'input>],
+) {
+    for inlined_function in inlined_functions {
+        let callee = inlined_function.abstract_origin(hash);
+        if let Some(callee) = callee {
+            edges.push(CallEdge {
+                caller,
+                callee,
+                inlined: true,
+            });
+        }
+        // Calls within an inlined function are made by its abstract origin.
+        add_inlined_calls(
+            edges,
+            hash,
+            callee.unwrap_or(caller),
+            inlined_function.inlined_functions(),
+        );
+    }
+}
+
+/// Write the call graph in Graphviz DOT format.
+///
+/// The graph starts at the functions that match the filter options, and
+/// follows their calls up to `options.call_graph_depth` levels deep.
+/// Inlined calls are drawn with dashed edges.
+pub fn call_graph(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let graph = CallGraph::new(&hash);
+
+    let mut nodes = HashMap::new();
+    let mut queue = VecDeque::new();
+    for unit in filter::filter_units(file, options) {
+        for function in filter::filter_functions(unit, options) {
+            let key = FunctionKey::new(function);
+            if !nodes.contains_key(&key) {
+                nodes.insert(key, nodes.len());
+                queue.push_back((function, 0));
+            }
+        }
+    }
+
+    writeln!(w, "digraph calls {{")?;
+    writeln!(w, "    node [shape=box];")?;
+    let mut edges = Vec::new();
+    while let Some((function, depth)) = queue.pop_front() {
+        write!(w, "    f{} [label=", nodes[&FunctionKey::new(function)])?;
+        write_label(w, &function_name(function))?;
+        writeln!(w, "];")?;
+        if depth >= options.call_graph_depth {
+            continue;
+        }
+        for edge in graph.calls(function) {
+            let callee = edge.callee();
+            let key = FunctionKey::new(callee);
+            if !nodes.contains_key(&key) {
+                nodes.insert(key, nodes.len());
+                queue.push_back((callee, depth + 1));
+            }
+            edges.push(edge);
+        }
+    }
+    for edge in edges {
+        write!(
+            w,
+            "    f{} -> f{}",
+            nodes[&FunctionKey::new(edge.caller())],
+            nodes[&FunctionKey::new(edge.callee())]
+        )?;
+        if edge.is_inlined() {
+            write!(w, " [style=dashed]")?;
+        }
+        writeln!(w, ";")?;
+    }
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+fn write_label(w: &mut dyn Write, s: &str) -> Result<()> {
+    write!(w, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            c => write!(w, "{}", c)?,
+        }
+    }
+    write!(w, "\"")?;
+    Ok(())
+}
@@ -1,17 +1,49 @@
-use capstone::arch::x86::X86OperandType;
+use capstone::arch::arm::{ArmInsn, ArmOperandType};
+use capstone::arch::arm64::{Arm64Insn, Arm64OperandType};
+use capstone::arch::ppc::{PpcInsn, PpcOperand};
+use capstone::arch::x86::{X86Insn, X86OperandType};
 use capstone::arch::ArchOperand;
-use capstone::{self, Arch, Capstone, Insn, InsnDetail, InsnGroupType, Mode};
+use capstone::{self, Arch, Capstone, Endian, Insn, InsnDetail, InsnGroupType, Mode, RegId};
+use std::cmp;
+use std::collections::HashMap;
 use std::convert::TryInto;
 
 use crate::print::{self, PrintState, ValuePrinter};
 use crate::Result;
-use parser::{Address, Architecture, File, FunctionDetails, Range, Register};
+use parser::{
+    Address, Architecture, File, FileHash, FunctionDetails, MappingKind, Range, Register,
+    Relocation, RelocationKind, SymbolKind,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Isa {
+    X86,
+    Arm,
+    Arm64,
+    Ppc,
+    RiscV,
+}
 
 #[derive(Debug)]
 pub(crate) struct Code<'code> {
-    arch: Arch,
-    mode: Mode,
+    isa: Isa,
+    address_size: u64,
+    big_endian: bool,
+    // Address ranges of ARM functions that use the Thumb instruction set.
+    thumb: Vec<Range>,
     regions: Vec<Region<'code>>,
+    // The relocations for the code, sorted by address.
+    relocations: Vec<Relocation<'code>>,
+    // The names of sections, used for relocations that refer to a section.
+    sections: Vec<(Range, String)>,
+    // The address of the GOT, which is used as a base register by i386 PIC.
+    got_base: Option<u64>,
+    // The imported symbols for GOT entries, by entry address.
+    got: HashMap<u64, Import<'code>>,
+    // The imported symbols for PLT stubs, by stub address.
+    stubs: HashMap<u64, Import<'code>>,
+    // The calls recorded in a snapshot, sorted by address.
+    recorded_calls: Vec<parser::Call<'code>>,
 }
 
 #[derive(Debug)]
@@ -20,41 +52,315 @@ struct Region<'code> {
     code: &'code [u8],
 }
 
+/// A symbol that is resolved by the dynamic linker.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Import<'code> {
+    pub name: Option<&'code str>,
+    // The address of the symbol if it is defined in this file.
+    pub address: Option<u64>,
+}
+
+/// The calls within a range of code.
+#[derive(Debug, Default)]
+pub(crate) struct CallList<'code> {
+    pub direct: Vec<Call<'code>>,
+    // The addresses of calls with a target that isn't known statically.
+    pub indirect: Vec<u64>,
+    // Jumps to a target outside of the range, which may be tail calls.
+    pub tail: Vec<Call<'code>>,
+}
+
 #[derive(Debug)]
-pub(crate) struct Call {
+pub(crate) struct Call<'code> {
     pub from: u64,
+    // For calls through a PLT stub or GOT entry, this is the address of the
+    // imported symbol if it is defined in this file, or else the address of
+    // the stub or entry.
     pub to: u64,
+    pub import: Option<&'code str>,
+}
+
+/// A reference to a data address by an instruction.
+#[derive(Debug)]
+pub(crate) struct DataRef {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// A memory operand that is addressed relative to a register.
+#[derive(Debug)]
+pub(crate) struct RegisterOffset {
+    pub address: u64,
+    pub register: Register,
+    pub offset: i64,
+    pub write: bool,
+}
+
+/// The targets of an indirect jump through a table.
+#[derive(Debug)]
+pub(crate) struct JumpTable {
+    pub address: u64,
+    pub targets: Vec<u64>,
 }
 
 impl<'code> Code<'code> {
     pub(crate) fn new(file: &File<'code>) -> Option<Self> {
-        let (arch, mode) = match file.machine() {
-            Architecture::I386 => (Arch::X86, Mode::Mode32),
-            Architecture::X86_64 => (Arch::X86, Mode::Mode64),
+        let (isa, address_size) = match file.machine() {
+            Architecture::I386 => (Isa::X86, 4),
+            Architecture::X86_64 => (Isa::X86, 8),
+            Architecture::Arm(_) => (Isa::Arm, 4),
+            Architecture::Aarch64(_) => (Isa::Arm64, 8),
+            Architecture::Powerpc => (Isa::Ppc, 4),
+            Architecture::Powerpc64 | Architecture::Powerpc64le => (Isa::Ppc, 8),
+            Architecture::Riscv32 => (Isa::RiscV, 4),
+            Architecture::Riscv64 => (Isa::RiscV, 8),
             _ => return None,
         };
+        let mut thumb = Vec::new();
+        if isa == Isa::Arm {
+            // The symbol address of a Thumb function has the low bit set.
+            for symbol in file.symbols() {
+                let address = symbol.address().begin;
+                if symbol.kind() == SymbolKind::Function && address & 1 != 0 {
+                    thumb.push(Range {
+                        begin: address & !1,
+                        end: (address & !1) + symbol.size(),
+                    });
+                }
+            }
+            // Mapping symbols also cover code that isn't in a function symbol.
+            // Each sequence ends at the next mapping symbol in the same section,
+            // or the end of its section.
+            let mapping_symbols = file.mapping_symbols();
+            for (i, symbol) in mapping_symbols.iter().enumerate() {
+                if symbol.kind() != MappingKind::Thumb {
+                    continue;
+                }
+                let begin = symbol.address();
+                let section_end = symbol.section_range().end;
+                let end = match mapping_symbols.get(i + 1) {
+                    Some(next) if next.section_index() == symbol.section_index() => {
+                        cmp::min(next.address(), section_end)
+                    }
+                    _ => section_end,
+                };
+                if begin < end {
+                    thumb.push(Range { begin, end });
+                }
+            }
+        }
         let mut regions = Vec::new();
-        // TODO: handle object files (no segments)
-        // TODO: handle relocations
         for segment in file.segments() {
             regions.push(Region {
                 address: segment.address,
                 code: segment.bytes,
             });
         }
-        Some(Code {
-            arch,
-            mode,
+
+        let mut got = HashMap::new();
+        for relocation in file.dynamic_relocations() {
+            let address = if relocation.symbol().is_some() && relocation.target() == 0 {
+                None
+            } else {
+                Some(relocation.value())
+            };
+            got.insert(
+                relocation.address(),
+                Import {
+                    name: relocation.symbol(),
+                    address,
+                },
+            );
+        }
+        let mut got_base = None;
+        let mut plt = Vec::new();
+        let mut sections = Vec::new();
+        for section in file.sections() {
+            let range = match section.address() {
+                Some(range) => range,
+                None => continue,
+            };
+            if let Some(name) = section.name() {
+                sections.push((range, name.to_string()));
+            }
+            match section.name() {
+                Some(".got.plt") => got_base = Some(range.begin),
+                Some(".got") => {
+                    got_base.get_or_insert(range.begin);
+                }
+                // TODO: Mach-O lazy symbol pointers need the indirect symbol table.
+                Some(".plt") | Some(".plt.sec") | Some(".plt.got") | Some("__stubs") => {
+                    plt.push(range)
+                }
+                _ => {}
+            }
+        }
+
+        let mut recorded_calls = file.calls().to_vec();
+        recorded_calls.sort_by(|a, b| a.from.cmp(&b.from));
+
+        let mut code = Code {
+            isa,
+            address_size,
+            big_endian: !file.is_little_endian(),
+            thumb,
             regions,
-        })
+            relocations: file.relocations().to_vec(),
+            sections,
+            got_base,
+            got,
+            stubs: HashMap::new(),
+            recorded_calls,
+        };
+        code.stubs = plt_stubs(&code, &plt);
+        Some(code)
     }
 
-    pub(crate) fn calls(&self, range: Range) -> Vec<Call> {
-        calls(self, range).unwrap_or(Vec::new())
+    pub(crate) fn calls(&self, range: Range) -> Vec<Call<'code>> {
+        self.call_list(range).direct
     }
 
-    pub(crate) fn disassembler<'a>(&'a self) -> Option<Disassembler<'a>> {
-        Disassembler::new(self, self.arch, self.mode)
+    pub(crate) fn call_list(&self, range: Range) -> CallList<'code> {
+        if !self.recorded_calls.is_empty() {
+            return self.recorded_call_list(range);
+        }
+        calls(self, range).unwrap_or_default()
+    }
+
+    // Snapshots don't contain any code, so use the calls that were recorded instead.
+    fn recorded_call_list(&self, range: Range) -> CallList<'code> {
+        let calls = &self.recorded_calls;
+        let mut index = match calls.binary_search_by(|call| call.from.cmp(&range.begin)) {
+            Ok(index) | Err(index) => index,
+        };
+        while index > 0 && calls[index - 1].from >= range.begin {
+            index -= 1;
+        }
+        let mut list = CallList::default();
+        for call in &calls[index..] {
+            if call.from >= range.end {
+                break;
+            }
+            list.direct.push(Call {
+                from: call.from,
+                to: call.to,
+                import: call.import,
+            });
+        }
+        list
+    }
+
+    /// The imported symbol that is called by the PLT stub at the given address.
+    pub(crate) fn stub(&self, address: u64) -> Option<&Import<'code>> {
+        self.stubs.get(&address)
+    }
+
+    /// The imported symbol for the GOT entry at the given address.
+    pub(crate) fn got_entry(&self, address: u64) -> Option<&Import<'code>> {
+        self.got.get(&address)
+    }
+
+    // The relocation for a value within the given instruction.
+    fn relocation(&self, insn: &Insn) -> Option<&Relocation<'code>> {
+        let begin = insn.address();
+        let end = begin + insn.bytes().len() as u64;
+        let index = match self
+            .relocations
+            .binary_search_by_key(&begin, |relocation| relocation.address())
+        {
+            Ok(index) | Err(index) => index,
+        };
+        self.relocations
+            .get(index)
+            .filter(|relocation| relocation.address() < end)
+    }
+
+    fn call(&self, from: u64, to: u64) -> Call<'code> {
+        match self.stubs.get(&to) {
+            Some(import) => Call {
+                from,
+                to: import.address.unwrap_or(to),
+                import: import.name,
+            },
+            None => Call {
+                from,
+                to,
+                import: None,
+            },
+        }
+    }
+
+    /// The addresses referenced by the instructions within a range of code.
+    ///
+    /// This includes immediate operands, and both the address and the loaded
+    /// value of instruction pointer relative memory operands. Immediate operands
+    /// are included even if they are constants rather than addresses.
+    pub(crate) fn references(&self, range: Range) -> Vec<u64> {
+        references(self, range).unwrap_or_default()
+    }
+
+    /// The data addresses referenced by the instructions within a range of code.
+    ///
+    /// This includes immediate operands, absolute and instruction pointer
+    /// relative memory operands, relocated operands, and AArch64 `adrp` pairs.
+    pub(crate) fn data_refs(&self, range: Range) -> Vec<DataRef> {
+        data_refs(self, range).unwrap_or_default()
+    }
+
+    /// The memory operands that are relative to a register within a range of code.
+    pub(crate) fn register_offsets(&self, range: Range) -> Vec<RegisterOffset> {
+        register_offsets(self, range).unwrap_or_default()
+    }
+
+    /// The pointer sized values at aligned addresses within a range of data.
+    pub(crate) fn pointers(&self, range: Range) -> Vec<u64> {
+        let size = self.address_size;
+        let mut pointers = Vec::new();
+        let mut address = (range.begin + size - 1) / size * size;
+        while address + size <= range.end {
+            if let Some(value) = self.read_mem(address, size) {
+                pointers.push(value);
+            }
+            address += size;
+        }
+        pointers
+    }
+
+    pub(crate) fn disassembler<'a>(&'a self, range: Range) -> Option<Disassembler<'a>> {
+        Disassembler::new(self, range)
+    }
+
+    fn mode(&self, range: Range) -> Option<(Arch, Mode)> {
+        let mode = match self.isa {
+            Isa::X86 if self.address_size == 4 => (Arch::X86, Mode::Mode32),
+            Isa::X86 => (Arch::X86, Mode::Mode64),
+            Isa::Arm if self.is_thumb(range) => (Arch::ARM, Mode::Thumb),
+            Isa::Arm => (Arch::ARM, Mode::Arm),
+            Isa::Arm64 => (Arch::ARM64, Mode::Arm),
+            Isa::Ppc if self.address_size == 4 => (Arch::PPC, Mode::Mode32),
+            Isa::Ppc => (Arch::PPC, Mode::Mode64),
+            // Capstone doesn't support RISC-V.
+            Isa::RiscV => return None,
+        };
+        Some(mode)
+    }
+
+    fn capstone(&self, range: Range) -> Option<(Capstone, Mode)> {
+        let (arch, mode) = self.mode(range)?;
+        let endian = if self.big_endian {
+            Some(Endian::Big)
+        } else {
+            None
+        };
+        let mut cs = Capstone::new_raw(arch, mode, capstone::NO_EXTRA_MODE, endian).ok()?;
+        cs.set_detail(true).ok()?;
+        Some((cs, mode))
+    }
+
+    fn is_thumb(&self, range: Range) -> bool {
+        self.thumb
+            .iter()
+            .any(|thumb| range.begin >= thumb.begin && range.begin < thumb.end)
     }
 
     fn range(&self, range: Range) -> Option<&'code [u8]> {
@@ -75,98 +381,1022 @@ impl<'code> Code<'code> {
             begin: address,
             end: address + size,
         })?;
-        match size {
-            4 => Some(u32::from_le_bytes(range.try_into().unwrap()) as u64),
-            8 => Some(u64::from_le_bytes(range.try_into().unwrap())),
+        match (size, self.big_endian) {
+            (4, false) => Some(u32::from_le_bytes(range.try_into().unwrap()) as u64),
+            (4, true) => Some(u32::from_be_bytes(range.try_into().unwrap()) as u64),
+            (8, false) => Some(u64::from_le_bytes(range.try_into().unwrap())),
+            (8, true) => Some(u64::from_be_bytes(range.try_into().unwrap())),
             _ => None,
         }
     }
 }
 
-fn calls(code: &Code, range: Range) -> Option<Vec<Call>> {
+fn calls<'code>(code: &Code<'code>, range: Range) -> Option<CallList<'code>> {
+    let bytes = code.range(range)?;
+    if code.isa == Isa::RiscV {
+        return Some(calls_riscv(code, bytes, range.begin));
+    }
+    let (cs, mode) = code.capstone(range)?;
+    let insns = cs.disasm_all(bytes, range.begin).ok()?;
+    let got = got_setup(code, &cs, mode, &insns);
+    let mut calls = CallList::default();
+    for insn in insns.iter() {
+        match call(code, &cs, mode, &insn, got) {
+            Some(Some(call)) => calls.direct.push(call),
+            Some(None) => calls.indirect.push(insn.address()),
+            None => {
+                if let Some(call) = tail_call(code, &cs, &insn, range) {
+                    calls.tail.push(call);
+                }
+            }
+        }
+    }
+    Some(calls)
+}
+
+fn references(code: &Code, range: Range) -> Option<Vec<u64>> {
     let bytes = code.range(range)?;
-    let mut cs = Capstone::new_raw(code.arch, code.mode, capstone::NO_EXTRA_MODE, None).ok()?;
-    cs.set_detail(true).ok()?;
+    if code.isa == Isa::RiscV {
+        // TODO: handle `auipc; addi` address loads
+        let calls = calls_riscv(code, bytes, range.begin);
+        return Some(calls.direct.iter().map(|call| call.to).collect());
+    }
+    let (cs, mode) = code.capstone(range)?;
     let insns = cs.disasm_all(bytes, range.begin).ok()?;
-    Some(insns.iter().filter_map(|x| call(code, &cs, &x)).collect())
+    let mut references = Vec::new();
+    for insn in insns.iter() {
+        let detail = match cs.insn_detail(&insn) {
+            Ok(detail) => detail,
+            Err(_) => continue,
+        };
+        for op in detail.arch_detail().operands() {
+            if let Some(imm) = is_imm(&op) {
+                references.push(imm as u64);
+            } else if let Some((_offset, address, size)) = is_ip_offset(&insn, mode, &op) {
+                references.push(address);
+                if let Some(value) = code.read_mem(address, size) {
+                    references.push(value);
+                }
+            }
+        }
+    }
+    Some(references)
 }
 
-fn call(code: &Code, cs: &Capstone, insn: &Insn) -> Option<Call> {
-    match code.arch {
-        Arch::X86 => call_x86(code, cs, insn),
-        _ => None,
+fn data_refs(code: &Code, range: Range) -> Option<Vec<DataRef>> {
+    let bytes = code.range(range)?;
+    if code.isa == Isa::RiscV {
+        // TODO: handle `auipc; addi` address loads
+        return Some(Vec::new());
+    }
+    let (cs, mode) = code.capstone(range)?;
+    let insns = cs.disasm_all(bytes, range.begin).ok()?;
+    let got = got_setup(code, &cs, mode, &insns);
+    let mut refs = Vec::new();
+    let mut page = None;
+    for insn in insns.iter() {
+        let detail = match cs.insn_detail(&insn) {
+            Ok(detail) => detail,
+            Err(_) => continue,
+        };
+        if is_call(code.isa, &insn, &detail) || is_jump(code.isa, &insn, &detail) {
+            page = None;
+            continue;
+        }
+        let from = insn.address();
+        let ops = detail.arch_detail().operands();
+        if let Some(relocation) = code.relocation(&insn) {
+            if relocated_operand(&insn, relocation, &ops).is_some() {
+                let to = relocation_target(code, relocation, &insn);
+                refs.push(DataRef { from, to });
+                continue;
+            }
+        }
+        if code.isa == Isa::Arm64 {
+            let id = insn.id().0;
+            if id == Arm64Insn::ARM64_INS_ADRP {
+                if let (Some(reg), Some(imm)) =
+                    (ops.get(0).and_then(arm64_reg), ops.get(1).and_then(is_imm))
+                {
+                    page = Some((reg, imm));
+                }
+                continue;
+            }
+            // `add xd, xn, #:lo12:symbol`
+            if id == Arm64Insn::ARM64_INS_ADD {
+                if let (Some((reg, page)), Some(base), Some(imm)) = (
+                    page,
+                    ops.get(1).and_then(arm64_reg),
+                    ops.get(2).and_then(is_imm),
+                ) {
+                    if base == reg {
+                        refs.push(DataRef {
+                            from,
+                            to: page.wrapping_add(imm),
+                        });
+                        continue;
+                    }
+                }
+            }
+        }
+        for op in &ops {
+            if let Some(to) = data_address(code, &insn, mode, op, page, got) {
+                refs.push(DataRef { from, to });
+                if code.isa == Isa::Arm {
+                    // ARM loads addresses from a literal pool.
+                    if let Some(to) = code.read_mem(to, 4) {
+                        refs.push(DataRef { from, to });
+                    }
+                }
+            } else if let Some(to) = is_imm(op) {
+                // Other immediates are more likely to be constants than addresses.
+                if is_load_address(code.isa, &insn) {
+                    refs.push(DataRef { from, to });
+                }
+            }
+        }
+    }
+    Some(refs)
+}
+
+fn register_offsets(code: &Code, range: Range) -> Option<Vec<RegisterOffset>> {
+    let bytes = code.range(range)?;
+    let (cs, _mode) = code.capstone(range)?;
+    let insns = cs.disasm_all(bytes, range.begin).ok()?;
+    let mut operands = Vec::new();
+    for insn in insns.iter() {
+        let detail = match cs.insn_detail(&insn) {
+            Ok(detail) => detail,
+            Err(_) => continue,
+        };
+        // `lea` only computes the address, and doesn't access memory.
+        if code.isa == Isa::X86 && insn.id().0 == X86Insn::X86_INS_LEA {
+            continue;
+        }
+        for (index, op) in detail.arch_detail().operands().into_iter().enumerate() {
+            if let Some((register, offset)) = is_reg_offset(&op) {
+                operands.push(RegisterOffset {
+                    address: insn.address(),
+                    register,
+                    offset,
+                    write: is_memory_write(code.isa, &insn, index),
+                });
+            }
+        }
     }
+    Some(operands)
 }
 
-fn call_x86(code: &Code, cs: &Capstone, insn: &Insn) -> Option<Call> {
+// Returns `None` if the instruction is not a call, or `Some(None)` if the
+// target of the call can't be determined.
+//
+// `got` is the address from which EBX holds the address of the GOT on i386.
+fn call<'code>(
+    code: &Code<'code>,
+    cs: &Capstone,
+    mode: Mode,
+    insn: &Insn,
+    got: Option<u64>,
+) -> Option<Option<Call<'code>>> {
     let detail = cs.insn_detail(insn).ok()?;
-    if !is_call(&detail) {
+    if !is_call(code.isa, insn, &detail) {
         return None;
     }
+    if let Some(relocation) = code.relocation(insn) {
+        // The target of a call to an undefined symbol is 0, so use its name.
+        let import = if relocation.target() == 0 {
+            relocation.symbol()
+        } else {
+            None
+        };
+        return Some(Some(Call {
+            from: insn.address(),
+            to: relocation_target(code, relocation, insn),
+            import,
+        }));
+    }
     let arch_detail = detail.arch_detail();
     for op in arch_detail.operands() {
         if let Some(imm) = is_imm(&op) {
-            return Some(Call {
-                from: insn.address(),
-                to: imm as u64,
-            });
-        } else if let Some((_offset, address, size)) = is_ip_offset(insn, &op) {
-            if let Some(value) = code.read_mem(address, size) {
-                return Some(Call {
+            return Some(Some(code.call(insn.address(), imm as u64)));
+        }
+        if let Some(address) = got_address(code, insn, mode, &op, None, got) {
+            if let Some(import) = code.got.get(&address) {
+                return Some(Some(Call {
                     from: insn.address(),
-                    to: value,
-                });
+                    to: import.address.unwrap_or(address),
+                    import: import.name,
+                }));
+            }
+        }
+        if let Some((_offset, address, size)) = is_ip_offset(insn, mode, &op) {
+            if let Some(value) = code.read_mem(address, size) {
+                return Some(Some(code.call(insn.address(), value)));
+            }
+        }
+    }
+    Some(None)
+}
+
+// Returns the target of a jump to an immediate address outside of `range`.
+//
+// These are usually tail calls, but may also be jumps to other parts of the
+// same function, so the caller must check that the target is a function.
+fn tail_call<'code>(
+    code: &Code<'code>,
+    cs: &Capstone,
+    insn: &Insn,
+    range: Range,
+) -> Option<Call<'code>> {
+    let detail = cs.insn_detail(insn).ok()?;
+    if !is_jump(code.isa, insn, &detail) {
+        return None;
+    }
+    let call = if let Some(relocation) = code.relocation(insn) {
+        let import = if relocation.target() == 0 {
+            relocation.symbol()
+        } else {
+            None
+        };
+        Call {
+            from: insn.address(),
+            to: relocation_target(code, relocation, insn),
+            import,
+        }
+    } else {
+        let to = detail.arch_detail().operands().iter().find_map(is_imm)?;
+        code.call(insn.address(), to)
+    };
+    if range.contains(call.to) && call.import.is_none() {
+        return None;
+    }
+    Some(call)
+}
+
+// The address that is referred to by a relocated instruction operand.
+fn relocation_target(code: &Code, relocation: &Relocation, insn: &Insn) -> u64 {
+    match relocation.kind() {
+        // x86 operands are relative to the end of the instruction, and the addend
+        // compensates for the distance from the relocation to the end.
+        RelocationKind::Relative if code.isa == Isa::X86 => {
+            let end = insn.address() + insn.bytes().len() as u64;
+            relocation
+                .value()
+                .wrapping_add(end.wrapping_sub(relocation.address()))
+        }
+        RelocationKind::Absolute | RelocationKind::Relative => relocation.value(),
+    }
+}
+
+// Return the index of the operand that is relocated.
+//
+// Immediates are encoded after memory displacements, so this is an immediate
+// if the relocation is at the end of the instruction.
+fn relocated_operand(insn: &Insn, relocation: &Relocation, ops: &[ArchOperand]) -> Option<usize> {
+    let end = insn.address() + insn.bytes().len() as u64;
+    if relocation.address() + u64::from(relocation.size() / 8) == end {
+        if let Some(index) = ops.iter().position(|op| is_imm(op).is_some()) {
+            return Some(index);
+        }
+    }
+    ops.iter().position(is_mem)
+}
+
+// Print a relocated operand as `symbol+addend`.
+//
+// Memory operands keep their size and registers, but the displacement is replaced.
+fn print_relocated_operand(
+    cs: &Capstone,
+    code: &Code,
+    insn: &Insn,
+    relocation: &Relocation,
+    target: u64,
+    op: &ArchOperand,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    let (prefix, registers) = match mem_registers(cs, insn, op) {
+        Some(mem) => mem,
+        None => return print_relocation(code, relocation, target, w, hash),
+    };
+    write!(w, "{}[", prefix)?;
+    for register in registers {
+        write!(w, "{} + ", register)?;
+    }
+    print_relocation(code, relocation, target, w, hash)?;
+    write!(w, "]")?;
+    Ok(())
+}
+
+// Return the prefix and the base and index registers of a memory operand.
+//
+// The instruction pointer is omitted because the displacement is printed
+// as the target of the relocation.
+fn mem_registers(cs: &Capstone, insn: &Insn, op: &ArchOperand) -> Option<(String, Vec<String>)> {
+    let reg_name = |reg: RegId| {
+        if reg.0 == 0 {
+            None
+        } else {
+            cs.reg_name(reg)
+        }
+    };
+    let mut prefix = String::new();
+    let mut registers = Vec::new();
+    match op {
+        ArchOperand::X86Operand(op) => {
+            let mem = match op.op_type {
+                X86OperandType::Mem(mem) => mem,
+                _ => return None,
+            };
+            // `lea` doesn't access memory, so it has no size.
+            if insn.id().0 != X86Insn::X86_INS_LEA {
+                if let Some(size) = x86_size_name(op.size) {
+                    prefix.push_str(size);
+                    prefix.push_str(" ptr ");
+                }
+            }
+            if let Some(segment) = reg_name(mem.segment()) {
+                prefix.push_str(&segment);
+                prefix.push(':');
+            }
+            if let Some(base) = reg_name(mem.base()).filter(|reg| reg != "rip" && reg != "eip") {
+                registers.push(base);
+            }
+            if let Some(index) = reg_name(mem.index()) {
+                if mem.scale() > 1 {
+                    registers.push(format!("{}*{}", index, mem.scale()));
+                } else {
+                    registers.push(index);
+                }
+            }
+        }
+        ArchOperand::ArmOperand(op) => {
+            let mem = match op.op_type {
+                ArmOperandType::Mem(mem) => mem,
+                _ => return None,
+            };
+            if let Some(base) = reg_name(mem.base()).filter(|reg| reg != "pc") {
+                registers.push(base);
+            }
+            if let Some(index) = reg_name(mem.index()) {
+                registers.push(index);
+            }
+        }
+        ArchOperand::Arm64Operand(op) => {
+            let mem = match op.op_type {
+                Arm64OperandType::Mem(mem) => mem,
+                _ => return None,
+            };
+            if let Some(base) = reg_name(mem.base()) {
+                registers.push(base);
+            }
+            if let Some(index) = reg_name(mem.index()) {
+                registers.push(index);
+            }
+        }
+        ArchOperand::PpcOperand(PpcOperand::Mem(mem)) => {
+            if let Some(base) = reg_name(mem.base()) {
+                registers.push(base);
+            }
+        }
+        _ => return None,
+    }
+    Some((prefix, registers))
+}
+
+// The name that capstone uses for the size of an x86 memory operand.
+fn x86_size_name(size: u8) -> Option<&'static str> {
+    match size {
+        1 => Some("byte"),
+        2 => Some("word"),
+        4 => Some("dword"),
+        6 => Some("fword"),
+        8 => Some("qword"),
+        10 => Some("xword"),
+        16 => Some("xmmword"),
+        32 => Some("ymmword"),
+        64 => Some("zmmword"),
+        _ => None,
+    }
+}
+
+fn print_relocation(
+    code: &Code,
+    relocation: &Relocation,
+    target: u64,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    let base = if let Some(symbol) = relocation.symbol() {
+        write!(w, "{}", symbol)?;
+        relocation.target()
+    } else if let Some(function) = hash.functions_by_address.get(&target) {
+        return print::function::print_ref(function, w);
+    } else if let Some((variable, offset)) = hash.find_variable(target) {
+        return print::variable::print_member_ref(variable, offset, w, hash);
+    } else if let Some((range, name)) = code
+        .sections
+        .iter()
+        .find(|(range, _)| range.begin == relocation.target())
+    {
+        write!(w, "{}", name)?;
+        range.begin
+    } else {
+        write!(w, "0x{:x}", target)?;
+        return Ok(());
+    };
+    let offset = target.wrapping_sub(base) as i64;
+    if offset < 0 {
+        write!(w, "-0x{:x}", -offset)?;
+    } else if offset > 0 {
+        write!(w, "+0x{:x}", offset)?;
+    }
+    Ok(())
+}
+
+// Return the address of the GOT entry that is accessed by a memory operand.
+//
+// `page` is the register and value of a preceding AArch64 `adrp`, and
+// `got` is the address from which EBX holds the address of the GOT on i386.
+fn got_address(
+    code: &Code,
+    insn: &Insn,
+    mode: Mode,
+    op: &ArchOperand,
+    page: Option<(u16, u64)>,
+    got: Option<u64>,
+) -> Option<u64> {
+    if let Some((_offset, address, _size)) = is_ip_offset(insn, mode, op) {
+        return Some(address);
+    }
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Mem(op) = op.op_type {
+                use capstone::arch::x86::X86Reg;
+                if op.index().0 as u32 != X86Reg::X86_REG_INVALID {
+                    return None;
+                }
+                let base = op.base().0 as u32;
+                if base == X86Reg::X86_REG_INVALID {
+                    return Some(op.disp() as u64);
+                } else if base == X86Reg::X86_REG_EBX && is_got_base(insn, mode, got) {
+                    return Some(code.got_base?.wrapping_add(op.disp() as u64));
+                }
+            }
+        }
+        ArchOperand::Arm64Operand(op) => {
+            if let Arm64OperandType::Mem(op) = op.op_type {
+                let (reg, page) = page?;
+                if op.base().0 == reg {
+                    return Some(page.wrapping_add(i64::from(op.disp()) as u64));
+                }
+            }
+        }
+        _ => {}
+    }
+    None
+}
+
+// Return the data address that is accessed by a memory operand.
+//
+// This handles absolute and instruction pointer relative operands, i386
+// operands relative to the GOT after the address given by `got`, and AArch64
+// operands relative to the page of a preceding `adrp`, which is given by `page`.
+fn data_address(
+    code: &Code,
+    insn: &Insn,
+    mode: Mode,
+    op: &ArchOperand,
+    page: Option<(u16, u64)>,
+    got: Option<u64>,
+) -> Option<u64> {
+    if let Some((_offset, address, _size)) = is_ip_offset(insn, mode, op) {
+        return Some(address);
+    }
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Mem(op) = op.op_type {
+                use capstone::arch::x86::X86Reg;
+                let base = op.base().0 as u32;
+                if base == X86Reg::X86_REG_INVALID {
+                    return Some(op.disp() as u64);
+                } else if base == X86Reg::X86_REG_EBX && is_got_base(insn, mode, got) {
+                    return Some(code.got_base?.wrapping_add(op.disp() as u64));
+                }
+            }
+        }
+        ArchOperand::Arm64Operand(_) => return got_address(code, insn, mode, op, page, got),
+        _ => {}
+    }
+    None
+}
+
+// Return true if EBX holds the address of the GOT for an i386 instruction.
+fn is_got_base(insn: &Insn, mode: Mode, got: Option<u64>) -> bool {
+    match got {
+        Some(got) => mode == Mode::Mode32 && insn.address() >= got,
+        None => false,
+    }
+}
+
+// Return the address from which EBX holds the address of the GOT in i386
+// position independent code.
+//
+// This recognises `call __x86.get_pc_thunk.bx` or `call 1f; 1: pop ebx`,
+// followed by `add ebx, offset`.
+fn got_setup(
+    code: &Code,
+    cs: &Capstone,
+    mode: Mode,
+    insns: &capstone::Instructions,
+) -> Option<u64> {
+    use capstone::arch::x86::X86Reg;
+    // `mov ebx, [esp]; ret`
+    const THUNK: [u8; 4] = [0x8b, 0x1c, 0x24, 0xc3];
+
+    if code.isa != Isa::X86 || mode != Mode::Mode32 {
+        return None;
+    }
+    let is_ebx = |op: Option<&ArchOperand>| {
+        op.and_then(x86_reg)
+            .map_or(false, |reg| u32::from(reg) == X86Reg::X86_REG_EBX)
+    };
+    // Whether EBX holds the address of the next instruction.
+    let mut pc = false;
+    // Whether the previous instruction called the next instruction.
+    let mut next = false;
+    for insn in insns.iter() {
+        let detail = match cs.insn_detail(&insn) {
+            Ok(detail) => detail,
+            Err(_) => continue,
+        };
+        let ops = detail.arch_detail().operands();
+        let end = insn.address() + insn.bytes().len() as u64;
+        let call_next = next;
+        next = false;
+        match insn.id().0 {
+            X86Insn::X86_INS_CALL => {
+                pc = false;
+                if let Some(Some(call)) = call(code, cs, mode, &insn, None) {
+                    if call.to == end {
+                        next = true;
+                    } else if call
+                        .import
+                        .map_or(false, |name| name == "__x86.get_pc_thunk.bx")
+                        || code.range(Range {
+                            begin: call.to,
+                            end: call.to + THUNK.len() as u64,
+                        }) == Some(&THUNK[..])
+                    {
+                        pc = true;
+                    }
+                }
+            }
+            X86Insn::X86_INS_POP => {
+                pc = call_next && is_ebx(ops.get(0));
+            }
+            X86Insn::X86_INS_ADD if pc => {
+                if is_ebx(ops.get(0)) && ops.get(1).and_then(is_imm).is_some() {
+                    return Some(end);
+                }
+                pc = false;
             }
+            _ => pc = false,
         }
     }
     None
 }
 
+// Return true if the instruction loads an address that is given by an immediate.
+fn is_load_address(isa: Isa, insn: &Insn) -> bool {
+    let id = insn.id().0;
+    match isa {
+        // Non-PIC code uses absolute addresses as immediates.
+        Isa::X86 => match id {
+            X86Insn::X86_INS_MOV | X86Insn::X86_INS_PUSH => true,
+            _ => false,
+        },
+        Isa::Arm64 => id == Arm64Insn::ARM64_INS_ADR,
+        // TODO: PowerPC `lis; addi` pairs
+        Isa::Arm | Isa::Ppc | Isa::RiscV => false,
+    }
+}
+
+// Return true if the instruction writes to its memory operand at `index`.
+//
+// Capstone doesn't tell us how operands are accessed, so this is an estimate.
+fn is_memory_write(isa: Isa, insn: &Insn, index: usize) -> bool {
+    match isa {
+        // Most instructions write to their first operand.
+        Isa::X86 => {
+            index == 0
+                && match insn.id().0 {
+                    X86Insn::X86_INS_CMP
+                    | X86Insn::X86_INS_TEST
+                    | X86Insn::X86_INS_BT
+                    | X86Insn::X86_INS_PUSH
+                    | X86Insn::X86_INS_CALL
+                    | X86Insn::X86_INS_JMP
+                    | X86Insn::X86_INS_NOP
+                    | X86Insn::X86_INS_MUL
+                    | X86Insn::X86_INS_IMUL
+                    | X86Insn::X86_INS_DIV
+                    | X86Insn::X86_INS_IDIV
+                    | X86Insn::X86_INS_FLD
+                    | X86Insn::X86_INS_FILD
+                    | X86Insn::X86_INS_UCOMISS
+                    | X86Insn::X86_INS_UCOMISD
+                    | X86Insn::X86_INS_COMISS
+                    | X86Insn::X86_INS_COMISD
+                    | X86Insn::X86_INS_PREFETCHT0
+                    | X86Insn::X86_INS_PREFETCHT1
+                    | X86Insn::X86_INS_PREFETCHT2
+                    | X86Insn::X86_INS_PREFETCHNTA => false,
+                    _ => true,
+                }
+        }
+        // Stores, and atomic read-modify-write instructions.
+        Isa::Arm | Isa::Arm64 | Isa::Ppc => {
+            let mnemonic = insn.mnemonic().unwrap_or("");
+            ["st", "swp", "cas", "ldadd", "ldclr", "ldeor", "ldset"]
+                .iter()
+                .any(|prefix| mnemonic.starts_with(prefix))
+        }
+        Isa::RiscV => false,
+    }
+}
+
+// Find the imported symbol that is called by each PLT stub.
+//
+// Each stub is a sequence of instructions ending in an indirect jump through
+// a GOT entry that has a dynamic relocation for the symbol.
+// TODO: ARM, PowerPC, RISC-V
+fn plt_stubs<'code>(code: &Code<'code>, plt: &[Range]) -> HashMap<u64, Import<'code>> {
+    let mut stubs = HashMap::new();
+    for &range in plt {
+        let bytes = match code.range(range) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        let (cs, mode) = match code.capstone(range) {
+            Some(cs) => cs,
+            None => continue,
+        };
+        let insns = match cs.disasm_all(bytes, range.begin) {
+            Ok(insns) => insns,
+            Err(_) => continue,
+        };
+        let mut start = None;
+        let mut entry = None;
+        let mut page = None;
+        for insn in insns.iter() {
+            let detail = match cs.insn_detail(&insn) {
+                Ok(detail) => detail,
+                Err(_) => continue,
+            };
+            if start.is_none() {
+                // Skip padding between stubs.
+                match insn.mnemonic() {
+                    Some(mnemonic) if mnemonic.starts_with("nop") || mnemonic == "int3" => continue,
+                    _ => start = Some(insn.address()),
+                }
+            }
+            let ops = detail.arch_detail().operands();
+            for op in &ops {
+                // The caller of an i386 PIC stub sets EBX to the address of the GOT.
+                if let Some(address) = got_address(code, &insn, mode, op, page, Some(range.begin)) {
+                    entry = Some(address);
+                }
+            }
+            if code.isa == Isa::Arm64 && insn.id().0 == Arm64Insn::ARM64_INS_ADRP {
+                if let (Some(reg), Some(imm)) =
+                    (ops.get(0).and_then(arm64_reg), ops.get(1).and_then(is_imm))
+                {
+                    page = Some((reg, imm));
+                }
+            }
+            if is_jump(code.isa, &insn, &detail) {
+                if let (Some(start), Some(import)) =
+                    (start, entry.and_then(|entry| code.got.get(&entry)))
+                {
+                    stubs.insert(start, *import);
+                }
+                start = None;
+                entry = None;
+                page = None;
+            }
+        }
+    }
+    stubs
+}
+
+fn arm64_reg(op: &ArchOperand) -> Option<u16> {
+    if let ArchOperand::Arm64Operand(op) = op {
+        if let Arm64OperandType::Reg(reg) = op.op_type {
+            return Some(reg.0);
+        }
+    }
+    None
+}
+
+// Find the jump tables used by indirect jumps within a range of code.
+//
+// This recognises `jmp [table + index * size]`, and the position independent
+// sequence within a basic block of:
+//
+// ```
+// lea base, [rip + table]
+// movsxd offset, dword ptr [base + index * 4]
+// add offset, base
+// jmp offset
+// ```
+//
+// where the table contains 32-bit offsets relative to itself.
+// Tables end at the first target that is outside of the range.
+// TODO: other architectures
+fn jump_tables(
+    code: &Code,
+    cs: &Capstone,
+    mode: Mode,
+    insns: &capstone::Instructions,
+    range: Range,
+) -> HashMap<u64, JumpTable> {
+    use capstone::arch::x86::X86Reg;
+
+    let mut tables = HashMap::new();
+    if code.isa != Isa::X86 {
+        return tables;
+    }
+    // The register and table address of each step in the sequence.
+    let mut base = None;
+    let mut offset = None;
+    let mut target = None;
+    for insn in insns.iter() {
+        let detail = match cs.insn_detail(&insn) {
+            Ok(detail) => detail,
+            Err(_) => continue,
+        };
+        let ops = detail.arch_detail().operands();
+        let dest = ops.get(0).and_then(x86_reg);
+        match insn.id().0 {
+            X86Insn::X86_INS_LEA => {
+                if let Some(reg) = dest {
+                    let address = ops
+                        .get(1)
+                        .and_then(|op| is_ip_offset(&insn, mode, op))
+                        .map(|(_offset, address, _size)| address);
+                    if let Some(address) = address {
+                        base = Some((reg, address));
+                        offset = None;
+                        target = None;
+                    } else if base.map(|x| x.0) == Some(reg) {
+                        base = None;
+                    }
+                }
+                continue;
+            }
+            X86Insn::X86_INS_MOVSXD => {
+                if let (Some((base_reg, table)), Some(reg), Some(op)) = (base, dest, ops.get(1)) {
+                    if let ArchOperand::X86Operand(ref op) = *op {
+                        if let X86OperandType::Mem(mem) = op.op_type {
+                            if mem.base().0 == base_reg
+                                && mem.index().0 as u32 != X86Reg::X86_REG_INVALID
+                                && mem.scale() == 4
+                                && mem.disp() == 0
+                            {
+                                offset = Some((reg, table));
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+            X86Insn::X86_INS_ADD => {
+                if let (Some((base_reg, _)), Some((offset_reg, table)), Some(a), Some(b)) =
+                    (base, offset, dest, ops.get(1).and_then(x86_reg))
+                {
+                    if (a == offset_reg && b == base_reg) || (a == base_reg && b == offset_reg) {
+                        target = Some((a, table));
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let jump = is_jump(code.isa, &insn, &detail);
+        let ret = detail
+            .groups()
+            .any(|group| group.0 as u32 == InsnGroupType::CS_GRP_RET);
+        if !jump && !ret && !is_call(code.isa, &insn, &detail) {
+            continue;
+        }
+
+        let table = if jump && ops.len() == 1 {
+            match ops[0] {
+                ArchOperand::X86Operand(ref op) => match op.op_type {
+                    X86OperandType::Mem(op) => {
+                        if op.base().0 as u32 == X86Reg::X86_REG_INVALID
+                            && op.index().0 as u32 != X86Reg::X86_REG_INVALID
+                            && op.scale() as u64 == code.address_size
+                        {
+                            Some((op.disp() as u64, false))
+                        } else {
+                            None
+                        }
+                    }
+                    X86OperandType::Reg(reg) => match target {
+                        Some((target_reg, table)) if target_reg == reg.0 => Some((table, true)),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            }
+        } else {
+            None
+        };
+        if let Some((address, relative)) = table {
+            let targets = jump_table_targets(code, address, relative, range);
+            if !targets.is_empty() {
+                tables.insert(insn.address(), JumpTable { address, targets });
+            }
+        }
+        // The basic block ends here.
+        base = None;
+        offset = None;
+        target = None;
+    }
+    tables
+}
+
+// Return the register of an x86 register operand.
+fn x86_reg(op: &ArchOperand) -> Option<u16> {
+    if let ArchOperand::X86Operand(op) = op {
+        if let X86OperandType::Reg(reg) = op.op_type {
+            return Some(reg.0);
+        }
+    }
+    None
+}
+
+fn jump_table_targets(code: &Code, address: u64, relative: bool, range: Range) -> Vec<u64> {
+    // Limit the size in case the end of the table can't be determined.
+    const MAX_TARGETS: usize = 4096;
+    let size = if relative { 4 } else { code.address_size };
+    let mut targets = Vec::new();
+    while targets.len() < MAX_TARGETS {
+        let entry = address.wrapping_add(targets.len() as u64 * size);
+        let value = match code.read_mem(entry, size) {
+            Some(value) => value,
+            None => break,
+        };
+        let target = if relative {
+            address.wrapping_add(value as u32 as i32 as i64 as u64)
+        } else {
+            value
+        };
+        if !range.contains(target) {
+            break;
+        }
+        targets.push(target);
+    }
+    targets
+}
+
+// Capstone doesn't support RISC-V, so decode the call instructions directly.
+// This handles `jal ra, offset` and `auipc ra, hi; jalr ra, lo(ra)`.
+fn calls_riscv<'code>(code: &Code<'code>, bytes: &[u8], address: u64) -> CallList<'code> {
+    const OPCODE_AUIPC: u32 = 0x17;
+    const OPCODE_JAL: u32 = 0x6f;
+    const OPCODE_JALR: u32 = 0x67;
+    const REG_RA: u32 = 1;
+
+    let mask = if code.address_size == 4 {
+        0xffff_ffff
+    } else {
+        !0
+    };
+    let mut calls = CallList::default();
+    let mut auipc = None;
+    let mut offset = 0;
+    while offset + 2 <= bytes.len() {
+        let from = address + offset as u64;
+        let half = u16::from_le_bytes(bytes[offset..][..2].try_into().unwrap());
+        if half & 0x3 != 0x3 {
+            // Compressed instruction.
+            auipc = None;
+            offset += 2;
+            continue;
+        }
+        if offset + 4 > bytes.len() {
+            break;
+        }
+        let insn = u32::from_le_bytes(bytes[offset..][..4].try_into().unwrap());
+        offset += 4;
+
+        let opcode = insn & 0x7f;
+        let rd = (insn >> 7) & 0x1f;
+        let rs1 = (insn >> 15) & 0x1f;
+        match opcode {
+            OPCODE_AUIPC => {
+                let imm = (insn & 0xffff_f000) as i32 as i64;
+                auipc = Some((rd, from.wrapping_add(imm as u64)));
+                continue;
+            }
+            OPCODE_JAL if rd == REG_RA => {
+                let imm = (((insn as i32) >> 11) as u32 & 0xfff0_0000)
+                    | (insn & 0x000f_f000)
+                    | ((insn >> 9) & 0x800)
+                    | ((insn >> 20) & 0x7fe);
+                calls
+                    .direct
+                    .push(code.call(from, from.wrapping_add(imm as i32 as i64 as u64) & mask));
+            }
+            OPCODE_JALR if rd == REG_RA => match auipc {
+                Some((reg, base)) if reg == rs1 => {
+                    let imm = ((insn as i32) >> 20) as i64;
+                    calls
+                        .direct
+                        .push(code.call(from, base.wrapping_add(imm as u64) & mask));
+                }
+                _ => calls.indirect.push(from),
+            },
+            _ => {}
+        }
+        auipc = None;
+    }
+    calls
+}
+
 pub(crate) struct Disassembler<'a> {
     code: &'a Code<'a>,
     cs: capstone::Capstone,
+    mode: Mode,
 }
 
 impl<'a> Disassembler<'a> {
-    pub(crate) fn new(code: &'a Code<'a>, arch: Arch, mode: Mode) -> Option<Disassembler<'a>> {
-        let mut cs = Capstone::new_raw(arch, mode, capstone::NO_EXTRA_MODE, None).ok()?;
-        cs.set_detail(true).ok()?;
-        Some(Disassembler { code, cs })
+    pub(crate) fn new(code: &'a Code<'a>, range: Range) -> Option<Disassembler<'a>> {
+        let (cs, mode) = code.capstone(range)?;
+        Some(Disassembler { code, cs, mode })
     }
 
     pub(crate) fn instructions(&'a self, range: Range) -> Option<Instructions<'a>> {
-        self.code
+        let instructions = self
+            .code
             .range(range)
-            .and_then(|code| self.cs.disasm_all(code, range.begin).ok())
-            .map(|instructions| Instructions { instructions })
+            .and_then(|code| self.cs.disasm_all(code, range.begin).ok())?;
+        let jump_tables = jump_tables(self.code, &self.cs, self.mode, &instructions, range);
+        let got = got_setup(self.code, &self.cs, self.mode, &instructions);
+        Some(Instructions {
+            instructions,
+            jump_tables,
+            got,
+        })
     }
 }
 
 pub(crate) struct Instructions<'a> {
     instructions: capstone::Instructions<'a>,
+    jump_tables: HashMap<u64, JumpTable>,
+    // The address from which EBX holds the address of the GOT on i386.
+    got: Option<u64>,
 }
 
 impl<'a> Instructions<'a> {
     pub(crate) fn iter(&'a self) -> InstructionIterator<'a> {
         let instructions = self.instructions.iter();
-        InstructionIterator { instructions }
+        InstructionIterator {
+            instructions,
+            jump_tables: &self.jump_tables,
+            got: self.got,
+        }
     }
 }
 
 pub(crate) struct InstructionIterator<'a> {
     instructions: capstone::InstructionIterator<'a>,
+    jump_tables: &'a HashMap<u64, JumpTable>,
+    got: Option<u64>,
 }
 
 impl<'a> Iterator for InstructionIterator<'a> {
     type Item = Instruction<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.instructions.next().map(|insn| Instruction { insn })
+        let jump_tables = self.jump_tables;
+        let got = self.got;
+        self.instructions.next().map(|insn| Instruction {
+            jump_table: jump_tables.get(&insn.address()),
+            got,
+            insn,
+        })
     }
 }
 
 pub(crate) struct Instruction<'a> {
     insn: capstone::Insn<'a>,
+    jump_table: Option<&'a JumpTable>,
+    got: Option<u64>,
 }
 
 impl<'a> Instruction<'a> {
@@ -195,13 +1425,17 @@ impl<'a> Instruction<'a> {
             Err(_) => return Ok(()),
         };
         let arch_detail = detail.arch_detail();
+        let operands = arch_detail.operands();
+        let relocation = d.code.relocation(&self.insn);
+        let relocated =
+            relocation.and_then(|relocation| relocated_operand(&self.insn, relocation, &operands));
 
-        state.line(|w, _hash| {
+        state.line(|w, hash| {
             write!(w, "{:3x}:  ", self.insn.address() - range.begin)?;
             if let Some(mnemonic) = self.insn.mnemonic() {
                 write!(w, "{:6}", mnemonic)?;
                 if let Some(op_str) = self.insn.op_str().filter(|s| !s.is_empty()) {
-                    let mut ops = arch_detail.operands().into_iter();
+                    let mut ops = operands.iter().enumerate();
                     let mut first = true;
                     for op_str in op_str.split(", ") {
                         if first {
@@ -210,9 +1444,18 @@ impl<'a> Instruction<'a> {
                         } else {
                             write!(w, ", ")?;
                         }
-                        if let Some(op) = ops.next() {
-                            if let Some(imm) = is_imm(&op) {
-                                if is_jump(&detail) && range.contains(imm) {
+                        if let Some((index, op)) = ops.next() {
+                            if let (Some(relocation), Some(relocated)) = (relocation, relocated) {
+                                if index == relocated {
+                                    let target = relocation_target(d.code, relocation, &self.insn);
+                                    print_relocated_operand(
+                                        &d.cs, d.code, &self.insn, relocation, target, op, w, hash,
+                                    )?;
+                                    continue;
+                                }
+                            }
+                            if let Some(imm) = is_imm(op) {
+                                if is_jump(d.code.isa, &self.insn, &detail) && range.contains(imm) {
                                     write!(w, "+{:x}", imm - range.begin)?;
                                     continue;
                                 }
@@ -232,7 +1475,12 @@ impl<'a> Instruction<'a> {
         })?;
 
         let mut first = true;
-        for op in arch_detail.operands() {
+        for (index, op) in operands.iter().enumerate() {
+            if Some(index) == relocated {
+                // This was printed as a symbol above.
+                first = false;
+                continue;
+            }
             let address = if first {
                 // HACK: assume only first operand is modified, so calculate it after the instruction
                 first = false;
@@ -240,15 +1488,13 @@ impl<'a> Instruction<'a> {
             } else {
                 self.insn.address()
             };
-            if let Some(imm) = is_imm(&op) {
-                if is_jump(&detail) && range.contains(imm) {
+            if let Some(imm) = is_imm(op) {
+                if is_jump(d.code.isa, &self.insn, &detail) && range.contains(imm) {
                     continue;
                 }
-                // TODO: handle relocations
                 if imm == 0 {
                     continue;
                 }
-                // TODO: lookup variables too
                 if let Some(function) = state.hash().functions_by_address.get(&imm) {
                     state.line(|w, _hash| {
                         pad_address(w)?;
@@ -256,9 +1502,23 @@ impl<'a> Instruction<'a> {
                         write!(w, "0x{:x} = ", imm)?;
                         print::function::print_ref(function, w)
                     })?;
+                } else if let Some(import) = d.code.stub(imm) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "0x{:x} = ", imm)?;
+                        print_import(import, w, hash)
+                    })?;
+                } else if let Some((variable, offset)) = state.hash().find_variable(imm) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "0x{:x} = ", imm)?;
+                        print::variable::print_member_ref(variable, offset, w, hash)
+                    })?;
                 }
             }
-            if let Some(reg) = is_reg(&op) {
+            if let Some(reg) = is_reg(op) {
                 for parameter in f.parameters() {
                     for (range, register) in parameter.registers() {
                         if reg == register && range.contains(address) {
@@ -286,7 +1546,7 @@ impl<'a> Instruction<'a> {
                     }
                 }
             }
-            if let Some((reg, ofs)) = is_reg_offset(&op) {
+            if let Some((reg, ofs)) = is_reg_offset(op) {
                 for parameter in f.parameters() {
                     let size = parameter.byte_size(state.hash()).unwrap_or(0) as i64;
                     for (range, register, offset) in parameter.register_offsets() {
@@ -338,8 +1598,22 @@ impl<'a> Instruction<'a> {
                     }
                 }
             }
-            if let Some((offset, address, size)) = is_ip_offset(&self.insn, &op) {
-                if let Some(value) = d.code.read_mem(address, size) {
+            if let Some((offset, address, size)) = is_ip_offset(&self.insn, d.mode, op) {
+                if let Some(import) = d.code.got_entry(address) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "[ip + 0x{:x}] = ", offset)?;
+                        print_import(import, w, hash)
+                    })?;
+                } else if let Some((variable, var_offset)) = state.hash().find_variable(address) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "[ip + 0x{:x}] = ", offset)?;
+                        print::variable::print_member_ref(variable, var_offset, w, hash)
+                    })?;
+                } else if let Some(value) = d.code.read_mem(address, size) {
                     state.line(|w, hash| {
                         pad_address(w)?;
                         pad_mnemonic(w)?;
@@ -348,83 +1622,293 @@ impl<'a> Instruction<'a> {
                         if let Some(function) = hash.functions_by_address.get(&value) {
                             write!(w, " = ")?;
                             print::function::print_ref(function, w)?;
+                        } else if let Some((variable, offset)) = hash.find_variable(value) {
+                            write!(w, " = ")?;
+                            print::variable::print_member_ref(variable, offset, w, hash)?;
                         }
                         Ok(())
                     })?;
                 }
+            } else if let Some(address) =
+                data_address(d.code, &self.insn, d.mode, op, None, self.got)
+            {
+                if let Some(import) = d.code.got_entry(address) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "[0x{:x}] = ", address)?;
+                        print_import(import, w, hash)
+                    })?;
+                } else if let Some((variable, offset)) = state.hash().find_variable(address) {
+                    state.line(|w, hash| {
+                        pad_address(w)?;
+                        pad_mnemonic(w)?;
+                        write!(w, "[0x{:x}] = ", address)?;
+                        print::variable::print_member_ref(variable, offset, w, hash)
+                    })?;
+                }
             }
-            // TODO: keep track of pointer types, and lookup X86OperandType::Mem offsets
+            // TODO: keep track of pointer types, and lookup memory operand offsets
         }
 
+        if let Some(table) = self.jump_table {
+            state.line(|w, _hash| {
+                pad_address(w)?;
+                pad_mnemonic(w)?;
+                write!(w, "jump table 0x{:x}:", table.address)?;
+                for target in &table.targets {
+                    write!(w, " +{:x}", target - range.begin)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+fn print_import(import: &Import, w: &mut dyn ValuePrinter, hash: &FileHash) -> Result<()> {
+    let function = import
+        .address
+        .and_then(|address| hash.functions_by_address.get(&address));
+    if let Some(function) = function {
+        print::function::print_ref(function, w)
+    } else {
+        write!(w, "{}", import.name.unwrap_or("<unknown>"))?;
         Ok(())
     }
 }
 
-fn is_call(detail: &InsnDetail) -> bool {
-    detail
+fn is_call(isa: Isa, insn: &Insn, detail: &InsnDetail) -> bool {
+    if detail
         .groups()
         .any(|group| group.0 as u32 == InsnGroupType::CS_GRP_CALL)
+    {
+        return true;
+    }
+    // Capstone doesn't set the call group for all branch and link instructions.
+    let id = insn.id().0;
+    match isa {
+        Isa::Arm => match id {
+            ArmInsn::ARM_INS_BL | ArmInsn::ARM_INS_BLX => true,
+            _ => false,
+        },
+        Isa::Arm64 => match id {
+            Arm64Insn::ARM64_INS_BL | Arm64Insn::ARM64_INS_BLR => true,
+            _ => false,
+        },
+        Isa::Ppc => match id {
+            PpcInsn::PPC_INS_BL | PpcInsn::PPC_INS_BLA => true,
+            _ => false,
+        },
+        Isa::X86 | Isa::RiscV => false,
+    }
 }
 
-fn is_jump(detail: &InsnDetail) -> bool {
-    detail
+fn is_jump(isa: Isa, insn: &Insn, detail: &InsnDetail) -> bool {
+    if detail
         .groups()
         .any(|group| group.0 as u32 == InsnGroupType::CS_GRP_JUMP)
+    {
+        return true;
+    }
+    let id = insn.id().0;
+    match isa {
+        Isa::Arm => match id {
+            ArmInsn::ARM_INS_B
+            | ArmInsn::ARM_INS_BX
+            | ArmInsn::ARM_INS_CBZ
+            | ArmInsn::ARM_INS_CBNZ => true,
+            _ => false,
+        },
+        Isa::Arm64 => match id {
+            Arm64Insn::ARM64_INS_B
+            | Arm64Insn::ARM64_INS_BR
+            | Arm64Insn::ARM64_INS_CBZ
+            | Arm64Insn::ARM64_INS_CBNZ
+            | Arm64Insn::ARM64_INS_TBZ
+            | Arm64Insn::ARM64_INS_TBNZ => true,
+            _ => false,
+        },
+        Isa::Ppc => match id {
+            PpcInsn::PPC_INS_B | PpcInsn::PPC_INS_BA | PpcInsn::PPC_INS_BC => true,
+            _ => false,
+        },
+        Isa::X86 | Isa::RiscV => false,
+    }
 }
 
 fn is_imm(op: &ArchOperand) -> Option<u64> {
-    if let ArchOperand::X86Operand(op) = op {
-        if let X86OperandType::Imm(imm) = op.op_type {
-            return Some(imm as u64);
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Imm(imm) = op.op_type {
+                return Some(imm as u64);
+            }
         }
+        ArchOperand::ArmOperand(op) => {
+            if let ArmOperandType::Imm(imm) = op.op_type {
+                return Some(imm as u32 as u64);
+            }
+        }
+        ArchOperand::Arm64Operand(op) => {
+            if let Arm64OperandType::Imm(imm) = op.op_type {
+                return Some(imm as u64);
+            }
+        }
+        ArchOperand::PpcOperand(PpcOperand::Imm(imm)) => {
+            return Some(*imm as u64);
+        }
+        _ => {}
     }
     None
 }
 
+fn is_mem(op: &ArchOperand) -> bool {
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Mem(_) = op.op_type {
+                return true;
+            }
+        }
+        ArchOperand::ArmOperand(op) => {
+            if let ArmOperandType::Mem(_) = op.op_type {
+                return true;
+            }
+        }
+        ArchOperand::Arm64Operand(op) => {
+            if let Arm64OperandType::Mem(_) = op.op_type {
+                return true;
+            }
+        }
+        ArchOperand::PpcOperand(PpcOperand::Mem(_)) => {
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
 fn is_reg(op: &ArchOperand) -> Option<Register> {
-    if let ArchOperand::X86Operand(op) = op {
-        if let X86OperandType::Reg(reg) = op.op_type {
-            return convert_reg(reg);
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Reg(reg) = op.op_type {
+                return convert_reg_x86(reg);
+            }
+            if let X86OperandType::Mem(op) = op.op_type {
+                return convert_reg_x86(op.base());
+                // TODO: op.index()?
+            }
         }
-        if let X86OperandType::Mem(op) = op.op_type {
-            return convert_reg(op.base());
-            // TODO: op.index()?
+        ArchOperand::ArmOperand(op) => {
+            if let ArmOperandType::Reg(reg) = op.op_type {
+                return convert_reg_arm(reg);
+            }
+            if let ArmOperandType::Mem(op) = op.op_type {
+                return convert_reg_arm(op.base());
+            }
         }
+        ArchOperand::Arm64Operand(op) => {
+            if let Arm64OperandType::Reg(reg) = op.op_type {
+                return convert_reg_arm64(reg);
+            }
+            if let Arm64OperandType::Mem(op) = op.op_type {
+                return convert_reg_arm64(op.base());
+            }
+        }
+        ArchOperand::PpcOperand(PpcOperand::Reg(reg)) => {
+            return convert_reg_ppc(*reg);
+        }
+        ArchOperand::PpcOperand(PpcOperand::Mem(op)) => {
+            return convert_reg_ppc(op.base());
+        }
+        _ => {}
     }
     None
 }
 
+// Operands with an index register don't have a fixed offset, so they are ignored.
 fn is_reg_offset(op: &ArchOperand) -> Option<(Register, i64)> {
-    if let ArchOperand::X86Operand(op) = op {
-        if let X86OperandType::Mem(op) = op.op_type {
-            return convert_reg(op.base()).map(|reg| (reg, op.disp()));
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Mem(op) = op.op_type {
+                use capstone::arch::x86::X86Reg;
+                if op.index().0 as u32 != X86Reg::X86_REG_INVALID {
+                    return None;
+                }
+                return convert_reg_x86(op.base()).map(|reg| (reg, op.disp()));
+            }
+        }
+        ArchOperand::ArmOperand(op) => {
+            if let ArmOperandType::Mem(op) = op.op_type {
+                use capstone::arch::arm::ArmReg;
+                if op.index().0 as u32 != ArmReg::ARM_REG_INVALID {
+                    return None;
+                }
+                return convert_reg_arm(op.base()).map(|reg| (reg, i64::from(op.disp())));
+            }
+        }
+        ArchOperand::Arm64Operand(op) => {
+            if let Arm64OperandType::Mem(op) = op.op_type {
+                use capstone::arch::arm64::Arm64Reg;
+                if op.index().0 as u32 != Arm64Reg::ARM64_REG_INVALID {
+                    return None;
+                }
+                return convert_reg_arm64(op.base()).map(|reg| (reg, i64::from(op.disp())));
+            }
+        }
+        ArchOperand::PpcOperand(PpcOperand::Mem(op)) => {
+            return convert_reg_ppc(op.base()).map(|reg| (reg, i64::from(op.disp())));
         }
+        _ => {}
     }
     None
 }
 
 // Option<(offset, address, size)>
-fn is_ip_offset(insn: &Insn, op: &ArchOperand) -> Option<(i64, u64, u64)> {
-    if let ArchOperand::X86Operand(op) = op {
-        if let X86OperandType::Mem(op) = op.op_type {
-            use capstone::arch::x86::X86Reg;
-            let reg = op.base().0 as u32;
-            let size = if reg == X86Reg::X86_REG_RIP {
-                8
-            } else if reg == X86Reg::X86_REG_EIP {
-                4
-            } else {
-                return None;
-            };
-            let offset = op.disp();
-            let address = (insn.address() + insn.bytes().len() as u64).wrapping_add(offset as u64);
-            return Some((offset, address, size));
+fn is_ip_offset(insn: &Insn, mode: Mode, op: &ArchOperand) -> Option<(i64, u64, u64)> {
+    match op {
+        ArchOperand::X86Operand(op) => {
+            if let X86OperandType::Mem(op) = op.op_type {
+                use capstone::arch::x86::X86Reg;
+                let reg = op.base().0 as u32;
+                let size = if reg == X86Reg::X86_REG_RIP {
+                    8
+                } else if reg == X86Reg::X86_REG_EIP {
+                    4
+                } else {
+                    return None;
+                };
+                let offset = op.disp();
+                let address =
+                    (insn.address() + insn.bytes().len() as u64).wrapping_add(offset as u64);
+                return Some((offset, address, size));
+            }
+        }
+        ArchOperand::ArmOperand(op) => {
+            if let ArmOperandType::Mem(op) = op.op_type {
+                use capstone::arch::arm::ArmReg;
+                if op.base().0 as u32 != ArmReg::ARM_REG_PC {
+                    return None;
+                }
+                // The PC reads as the address of the current instruction plus 8 in ARM
+                // mode, or plus 4 in Thumb mode, and is word aligned for loads.
+                let pc = if mode == Mode::Thumb {
+                    insn.address() + 4
+                } else {
+                    insn.address() + 8
+                };
+                let offset = i64::from(op.disp());
+                let address = (pc & !3).wrapping_add(offset as u64);
+                return Some((offset, address, 4));
+            }
         }
+        // AArch64 and PowerPC PC relative operands are decoded as immediates.
+        _ => {}
     }
     None
 }
 
-fn convert_reg(reg: capstone::RegId) -> Option<Register> {
+fn convert_reg_x86(reg: capstone::RegId) -> Option<Register> {
     use capstone::arch::x86::X86Reg::*;
     // FIXME: mapping from capstone to dwarf registers should live elsewhere
     // FIXME: keep track of register width?
@@ -474,3 +1958,59 @@ fn convert_reg(reg: capstone::RegId) -> Option<Register> {
         }
     };
 }
+
+fn convert_reg_arm(reg: capstone::RegId) -> Option<Register> {
+    use capstone::arch::arm::ArmReg::*;
+    let reg = reg.0 as u32;
+    match reg {
+        ARM_REG_R0..=ARM_REG_R12 => Some(Register((reg - ARM_REG_R0) as u16)),
+        ARM_REG_SP => Some(Register(13)),
+        ARM_REG_LR => Some(Register(14)),
+        ARM_REG_PC => Some(Register(15)),
+        ARM_REG_S0..=ARM_REG_S31 => Some(Register(64 + (reg - ARM_REG_S0) as u16)),
+        ARM_REG_D0..=ARM_REG_D31 => Some(Register(256 + (reg - ARM_REG_D0) as u16)),
+        ARM_REG_INVALID => None,
+        _ => {
+            debug!("Unsupported ARM register {}", reg);
+            None
+        }
+    }
+}
+
+fn convert_reg_arm64(reg: capstone::RegId) -> Option<Register> {
+    use capstone::arch::arm64::Arm64Reg::*;
+    let reg = reg.0 as u32;
+    match reg {
+        ARM64_REG_X0..=ARM64_REG_X28 => Some(Register((reg - ARM64_REG_X0) as u16)),
+        ARM64_REG_W0..=ARM64_REG_W30 => Some(Register((reg - ARM64_REG_W0) as u16)),
+        ARM64_REG_X29 => Some(Register(29)),
+        ARM64_REG_X30 => Some(Register(30)),
+        ARM64_REG_SP | ARM64_REG_WSP => Some(Register(31)),
+        ARM64_REG_V0..=ARM64_REG_V31 => Some(Register(64 + (reg - ARM64_REG_V0) as u16)),
+        ARM64_REG_Q0..=ARM64_REG_Q31 => Some(Register(64 + (reg - ARM64_REG_Q0) as u16)),
+        ARM64_REG_D0..=ARM64_REG_D31 => Some(Register(64 + (reg - ARM64_REG_D0) as u16)),
+        ARM64_REG_S0..=ARM64_REG_S31 => Some(Register(64 + (reg - ARM64_REG_S0) as u16)),
+        // There's never variables/parameters in the zero registers.
+        ARM64_REG_INVALID | ARM64_REG_XZR | ARM64_REG_WZR => None,
+        _ => {
+            debug!("Unsupported AArch64 register {}", reg);
+            None
+        }
+    }
+}
+
+fn convert_reg_ppc(reg: capstone::RegId) -> Option<Register> {
+    use capstone::arch::ppc::PpcReg::*;
+    let reg = reg.0 as u32;
+    match reg {
+        PPC_REG_R0..=PPC_REG_R31 => Some(Register((reg - PPC_REG_R0) as u16)),
+        PPC_REG_F0..=PPC_REG_F31 => Some(Register(32 + (reg - PPC_REG_F0) as u16)),
+        PPC_REG_LR => Some(Register(65)),
+        PPC_REG_CTR => Some(Register(66)),
+        PPC_REG_INVALID => None,
+        _ => {
+            debug!("Unsupported PowerPC register {}", reg);
+            None
+        }
+    }
+}
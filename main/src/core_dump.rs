@@ -0,0 +1,886 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use parser::{
+    Architecture, CfiDirective, File, FileHash, Function, Location, Piece, Range, Register, Type,
+};
+
+use crate::filter;
+use crate::print::{self, TextValuePrinter, ValuePrinter};
+use crate::symbolize::{function_name, source_string};
+use crate::{Options, Result};
+
+const ET_CORE: u64 = 4;
+const EM_X86_64: u64 = 62;
+const EM_AARCH64: u64 = 183;
+const PT_LOAD: u64 = 1;
+const PT_NOTE: u64 = 4;
+const NT_PRSTATUS: u64 = 1;
+const NT_AUXV: u64 = 6;
+const AT_NULL: u64 = 0;
+const AT_ENTRY: u64 = 9;
+
+// The offset of `pr_pid` and `pr_reg` within `struct elf_prstatus` for 64-bit targets.
+const PRSTATUS_PID: usize = 32;
+const PRSTATUS_REG: usize = 112;
+
+// Limit the number of frames, in case the unwinding doesn't terminate.
+const MAX_FRAMES: usize = 256;
+
+// The register layout of a machine.
+struct Arch {
+    // The index within `pr_reg` and the DWARF register number of each register.
+    registers: &'static [(usize, u16)],
+    // The index of the program counter within `pr_reg`.
+    pc: usize,
+    sp: Register,
+    return_address: Register,
+}
+
+const X86_64: Arch = Arch {
+    registers: &[
+        (0, 15), // r15
+        (1, 14), // r14
+        (2, 13), // r13
+        (3, 12), // r12
+        (4, 6),  // rbp
+        (5, 3),  // rbx
+        (6, 11), // r11
+        (7, 10), // r10
+        (8, 9),  // r9
+        (9, 8),  // r8
+        (10, 0), // rax
+        (11, 2), // rcx
+        (12, 1), // rdx
+        (13, 4), // rsi
+        (14, 5), // rdi
+        (19, 7), // rsp
+    ],
+    pc: 16,
+    sp: Register(7),
+    return_address: Register(16),
+};
+
+const AARCH64: Arch = Arch {
+    registers: &[
+        (0, 0),
+        (1, 1),
+        (2, 2),
+        (3, 3),
+        (4, 4),
+        (5, 5),
+        (6, 6),
+        (7, 7),
+        (8, 8),
+        (9, 9),
+        (10, 10),
+        (11, 11),
+        (12, 12),
+        (13, 13),
+        (14, 14),
+        (15, 15),
+        (16, 16),
+        (17, 17),
+        (18, 18),
+        (19, 19),
+        (20, 20),
+        (21, 21),
+        (22, 22),
+        (23, 23),
+        (24, 24),
+        (25, 25),
+        (26, 26),
+        (27, 27),
+        (28, 28),
+        (29, 29),
+        (30, 30),
+        (31, 31), // sp
+    ],
+    pc: 32,
+    sp: Register(31),
+    return_address: Register(30),
+};
+
+type Registers = BTreeMap<Register, u64>;
+
+// The contents of an ELF core file.
+struct Core<'data> {
+    machine: u64,
+    arch: &'static Arch,
+    big_endian: bool,
+    // The address and bytes of each `PT_LOAD` segment.
+    loads: Vec<(u64, &'data [u8])>,
+    threads: Vec<Thread>,
+    // The entry point from the auxiliary vector.
+    entry: Option<u64>,
+}
+
+struct Thread {
+    pid: u64,
+    pc: u64,
+    registers: Registers,
+}
+
+impl<'data> Core<'data> {
+    fn parse(data: &'data [u8]) -> Result<Self> {
+        if data.get(..4) != Some(&b"\x7fELF"[..]) {
+            return Err("not an ELF file".into());
+        }
+        if data.get(4) != Some(&2) {
+            return Err(
+                "unsupported core file class: only 64-bit x86-64 and AArch64 are supported".into(),
+            );
+        }
+        let big_endian = match data.get(5) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err("invalid ELF data encoding".into()),
+        };
+        let field = |offset: usize, size: usize| {
+            read(data, offset, size, big_endian).ok_or("truncated core file")
+        };
+        if field(16, 2)? != ET_CORE {
+            return Err("not an ELF core file".into());
+        }
+        let machine = field(18, 2)?;
+        let arch = match machine {
+            EM_X86_64 => &X86_64,
+            EM_AARCH64 => &AARCH64,
+            _ => {
+                return Err(format!(
+                    "unsupported core file machine {}: only x86-64 and AArch64 are supported",
+                    machine
+                )
+                .into())
+            }
+        };
+        let phoff = field(0x20, 8)? as usize;
+        let phentsize = field(0x36, 2)? as usize;
+        let phnum = field(0x38, 2)? as usize;
+
+        let mut core = Core {
+            machine,
+            arch,
+            big_endian,
+            loads: Vec::new(),
+            threads: Vec::new(),
+            entry: None,
+        };
+        for i in 0..phnum {
+            let ph = phoff + i * phentsize;
+            let p_type = field(ph, 4)?;
+            let offset = field(ph + 8, 8)? as usize;
+            let address = field(ph + 16, 8)?;
+            let size = field(ph + 32, 8)? as usize;
+            let bytes = data
+                .get(offset..offset.saturating_add(size))
+                .ok_or("truncated core file")?;
+            match p_type {
+                PT_LOAD => core.loads.push((address, bytes)),
+                PT_NOTE => core.parse_notes(bytes),
+                _ => {}
+            }
+        }
+        Ok(core)
+    }
+
+    fn parse_notes(&mut self, data: &[u8]) {
+        let big_endian = self.big_endian;
+        let align = |size: usize| (size + 3) & !3;
+        let mut offset = 0;
+        while offset + 12 <= data.len() {
+            let (namesz, descsz, n_type) = match (
+                read(data, offset, 4, big_endian),
+                read(data, offset + 4, 4, big_endian),
+                read(data, offset + 8, 4, big_endian),
+            ) {
+                (Some(namesz), Some(descsz), Some(n_type)) => {
+                    (namesz as usize, descsz as usize, n_type)
+                }
+                _ => return,
+            };
+            let begin = offset + 12 + align(namesz);
+            let desc = match data.get(begin..begin.saturating_add(descsz)) {
+                Some(desc) => desc,
+                None => return,
+            };
+            offset = begin + align(descsz);
+            match n_type {
+                NT_PRSTATUS => {
+                    if let Some(thread) = parse_prstatus(desc, self.arch, big_endian) {
+                        self.threads.push(thread);
+                    }
+                }
+                NT_AUXV => {
+                    for auxv in desc.chunks(16) {
+                        match (read(auxv, 0, 8, big_endian), read(auxv, 8, 8, big_endian)) {
+                            (Some(AT_NULL), _) | (None, _) => break,
+                            (Some(AT_ENTRY), Some(value)) => self.entry = Some(value),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse_prstatus(desc: &[u8], arch: &Arch, big_endian: bool) -> Option<Thread> {
+    let pid = read(desc, PRSTATUS_PID, 4, big_endian)?;
+    let reg = |index: usize| read(desc, PRSTATUS_REG + index * 8, 8, big_endian);
+    let pc = reg(arch.pc)?;
+    let mut registers = Registers::new();
+    for &(index, register) in arch.registers {
+        registers.insert(Register(register), reg(index)?);
+    }
+    Some(Thread { pid, pc, registers })
+}
+
+fn read(data: &[u8], offset: usize, size: usize, big_endian: bool) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(size)?)?;
+    let fold = |value, byte: &u8| (value << 8) | u64::from(*byte);
+    if big_endian {
+        Some(bytes.iter().fold(0, fold))
+    } else {
+        Some(bytes.iter().rev().fold(0, fold))
+    }
+}
+
+// The memory of the process, from the core file and the executable.
+struct Process<'a, 'input> {
+    core: &'a Core<'a>,
+    file: &'a File<'input>,
+    // The difference between addresses in the process and addresses in the executable.
+    bias: u64,
+}
+
+impl<'a, 'input> Process<'a, 'input> {
+    fn bytes(&self, address: u64, size: u64) -> Option<&'a [u8]> {
+        let end = address.checked_add(size)?;
+        for &(begin, bytes) in &self.core.loads {
+            if address >= begin && end <= begin + bytes.len() as u64 {
+                return Some(&bytes[(address - begin) as usize..][..size as usize]);
+            }
+        }
+        // Read-only segments are usually omitted from core files, so use the executable.
+        let address = address.wrapping_sub(self.bias);
+        for segment in self.file.segments() {
+            let begin = segment.address;
+            if address >= begin && address.checked_add(size)? <= begin + segment.bytes.len() as u64
+            {
+                return Some(&segment.bytes[(address - begin) as usize..][..size as usize]);
+            }
+        }
+        None
+    }
+
+    fn read(&self, address: u64) -> Option<u64> {
+        read(self.bytes(address, 8)?, 0, 8, self.core.big_endian)
+    }
+}
+
+/// Print the values of global variables and the stack frames of each thread
+/// in an ELF core file.
+///
+/// `file` is the executable that the core file was generated from, and `core` is the
+/// contents of the core file. Memory that is not in the core file is read from the
+/// executable instead. Only x86-64 and AArch64 core files are supported.
+///
+/// Stacks are unwound using the CFI of each function. Registers that aren't saved
+/// by the CFI are assumed to be unchanged by the call, so the values of variables
+/// in caller-saved registers may be stale for frames other than the innermost.
+pub fn core_dump(file: &File, core: &[u8], w: &mut dyn Write, options: &Options) -> Result<()> {
+    let core = Core::parse(core)?;
+    match (core.machine, file.machine()) {
+        (EM_X86_64, Architecture::X86_64) | (EM_AARCH64, Architecture::Aarch64(_)) => {}
+        _ => return Err("core file machine does not match the executable".into()),
+    }
+    let bias = match core.entry {
+        Some(entry) => entry.wrapping_sub(file.entry()),
+        None => 0,
+    };
+    let hash = FileHash::new(file);
+    let process = Process {
+        core: &core,
+        file,
+        bias,
+    };
+    let mut w = TextValuePrinter::new(w);
+    print_variables(&mut w, &hash, &process, options)?;
+    for thread in &core.threads {
+        writeln!(w)?;
+        writeln!(w, "thread {}", thread.pid)?;
+        print_thread(&mut w, &hash, &process, thread)?;
+    }
+    Ok(())
+}
+
+fn print_variables(
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+    process: &Process,
+    options: &Options,
+) -> Result<()> {
+    for unit in filter::filter_units(hash.file, options) {
+        for variable in filter::filter_variables(unit, options) {
+            let (address, size) = match (variable.address(), variable.byte_size(hash)) {
+                (Some(address), Some(size)) => (address.wrapping_add(process.bias), size),
+                _ => continue,
+            };
+            write!(w, "var ")?;
+            print::variable::print_ref(variable, w)?;
+            let bytes = process.bytes(address, size);
+            print_value(
+                w,
+                hash,
+                process,
+                variable.ty(hash),
+                bytes.map(Cow::Borrowed),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// The state of a stack frame.
+struct Frame {
+    // The address of the current instruction, or the return address for caller frames.
+    pc: u64,
+    registers: Registers,
+    cfa: Option<u64>,
+}
+
+fn print_thread(
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+    process: &Process,
+    thread: &Thread,
+) -> Result<()> {
+    let mut frame = Frame {
+        pc: thread.pc,
+        registers: thread.registers.clone(),
+        cfa: None,
+    };
+    for depth in 0..MAX_FRAMES {
+        // Return addresses may be after the end of the calling function,
+        // so look up the address of the call instruction instead.
+        let address = if depth == 0 { frame.pc } else { frame.pc - 1 };
+        let address = address.wrapping_sub(process.bias);
+        let function = hash.find_function(address).map(|(_, function)| function);
+        let caller = function.and_then(|function| unwind(hash, process, function, address, &frame));
+        frame.cfa = caller.as_ref().map(|caller| caller.0);
+
+        write!(w, "#{} 0x{:x} in ", depth, frame.pc)?;
+        match function {
+            Some(function) => write!(w, "{}", function_name(function))?,
+            None => write!(w, "??")?,
+        }
+        if let Some(location) = hash.file.find_location(hash, address) {
+            if let Some(source) = location
+                .source()
+                .and_then(|source| source_string(source, location.unit()))
+            {
+                write!(w, " at {}", source)?;
+            }
+        }
+        writeln!(w)?;
+        if let Some(function) = function {
+            print_frame(w, hash, process, function, address, &frame)?;
+        }
+
+        let (cfa, registers) = match caller {
+            Some(caller) => caller,
+            None => break,
+        };
+        let pc = match registers.get(&process.core.arch.return_address) {
+            Some(&pc) if pc != 0 => pc,
+            _ => break,
+        };
+        // The stack grows down, so the CFA must be above the stack pointer.
+        if frame.registers.get(&process.core.arch.sp) >= Some(&cfa) {
+            break;
+        }
+        frame = Frame {
+            pc,
+            registers,
+            cfa: None,
+        };
+    }
+    Ok(())
+}
+
+fn print_frame<'input>(
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash<'input>,
+    process: &Process,
+    function: &Function<'input>,
+    address: u64,
+    frame: &Frame,
+) -> Result<()> {
+    let details = function.details(hash);
+    for parameter in details.parameters() {
+        write!(w, "\t{}", parameter.name().unwrap_or("<anon>"))?;
+        let ty = parameter.ty(hash);
+        let bytes = ty.as_ref().and_then(|ty| {
+            let size = ty.byte_size(hash)?;
+            evaluate(
+                process,
+                function,
+                frame,
+                address,
+                parameter.locations(),
+                size,
+            )
+        });
+        print_value(w, hash, process, ty, bytes.map(Cow::Owned))?;
+    }
+    for variable in details.variables() {
+        write!(w, "\tvar {}", variable.name().unwrap_or("<anon>"))?;
+        let ty = variable.ty(hash);
+        let bytes = ty.as_ref().and_then(|ty| {
+            let size = ty.byte_size(hash)?;
+            evaluate(
+                process,
+                function,
+                frame,
+                address,
+                variable.locations(),
+                size,
+            )
+        });
+        print_value(w, hash, process, ty, bytes.map(Cow::Owned))?;
+    }
+    Ok(())
+}
+
+fn print_value(
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+    process: &Process,
+    ty: Option<Cow<Type>>,
+    bytes: Option<Cow<[u8]>>,
+) -> Result<()> {
+    write!(w, ": ")?;
+    print::types::print_ref(ty.clone(), w, hash)?;
+    write!(w, " = ")?;
+    match (ty, bytes) {
+        (Some(ty), Some(bytes)) => print::value::print_bytes(&bytes, process.bias, &ty, w, hash)?,
+        _ => write!(w, "<unavailable>")?,
+    }
+    writeln!(w)?;
+    Ok(())
+}
+
+// Find the CFA and the caller's registers for a frame.
+fn unwind<'input>(
+    hash: &FileHash<'input>,
+    process: &Process,
+    function: &Function<'input>,
+    address: u64,
+    frame: &Frame,
+) -> Option<(u64, Registers)> {
+    let rules = Rules::new(function, hash, address)?;
+    let (register, offset) = rules.cfa?;
+    let cfa = frame.registers.get(&register)?.wrapping_add(offset as u64);
+
+    let mut registers = frame.registers.clone();
+    for (&register, &rule) in &rules.registers {
+        let value = match rule {
+            Rule::Undefined => None,
+            Rule::SameValue => continue,
+            Rule::Offset(offset) => process.read(cfa.wrapping_add(offset as u64)),
+            Rule::ValOffset(offset) => Some(cfa.wrapping_add(offset as u64)),
+            Rule::Register(other) => frame.registers.get(&other).cloned(),
+        };
+        match value {
+            Some(value) => registers.insert(register, value),
+            None => registers.remove(&register),
+        };
+    }
+    if let Some(register) = rules.return_address {
+        let value = registers.get(&register).cloned();
+        match value {
+            Some(value) => registers.insert(process.core.arch.return_address, value),
+            None => registers.remove(&process.core.arch.return_address),
+        };
+    }
+    registers.insert(process.core.arch.sp, cfa);
+    Some((cfa, registers))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    Undefined,
+    SameValue,
+    Offset(i64),
+    ValOffset(i64),
+    Register(Register),
+}
+
+// The unwind rules at an address.
+#[derive(Debug, Clone, Default)]
+struct Rules {
+    cfa: Option<(Register, i64)>,
+    registers: BTreeMap<Register, Rule>,
+    return_address: Option<Register>,
+}
+
+impl Rules {
+    fn new<'input>(
+        function: &Function<'input>,
+        hash: &FileHash<'input>,
+        address: u64,
+    ) -> Option<Self> {
+        let cfi = function.cfi(hash);
+        if cfi.is_empty() {
+            return None;
+        }
+        let mut rules = Rules::default();
+        // The rules from the CIE, which are used by `.cfi_restore`.
+        let mut initial = None;
+        let mut stack = Vec::new();
+        for (cfi_address, directive) in cfi {
+            if let Some(cfi_address) = cfi_address.get() {
+                if cfi_address > address {
+                    break;
+                }
+                if initial.is_none() {
+                    initial = Some(rules.clone());
+                }
+            }
+            match directive {
+                CfiDirective::ReturnColumn(register) => rules.return_address = Some(register),
+                CfiDirective::DefCfa(register, offset) => rules.cfa = Some((register, offset)),
+                CfiDirective::DefCfaRegister(register) => {
+                    rules.cfa = Some((register, rules.cfa.map(|x| x.1).unwrap_or(0)));
+                }
+                CfiDirective::DefCfaOffset(offset) => {
+                    rules.cfa = rules.cfa.map(|x| (x.0, offset));
+                }
+                CfiDirective::Offset(register, offset) => {
+                    rules.registers.insert(register, Rule::Offset(offset));
+                }
+                CfiDirective::ValOffset(register, offset) => {
+                    rules.registers.insert(register, Rule::ValOffset(offset));
+                }
+                CfiDirective::Register(register, other) => {
+                    rules.registers.insert(register, Rule::Register(other));
+                }
+                CfiDirective::Restore(register) => {
+                    let rule = initial
+                        .as_ref()
+                        .and_then(|initial| initial.registers.get(&register).cloned());
+                    match rule {
+                        Some(rule) => rules.registers.insert(register, rule),
+                        None => rules.registers.remove(&register),
+                    };
+                }
+                CfiDirective::Undefined(register) => {
+                    rules.registers.insert(register, Rule::Undefined);
+                }
+                CfiDirective::SameValue(register) => {
+                    rules.registers.insert(register, Rule::SameValue);
+                }
+                CfiDirective::RememberState => stack.push(rules.clone()),
+                CfiDirective::RestoreState => {
+                    if let Some(state) = stack.pop() {
+                        rules = state;
+                    }
+                }
+                CfiDirective::StartProc
+                | CfiDirective::EndProc
+                | CfiDirective::Personality(_)
+                | CfiDirective::Lsda(_)
+                | CfiDirective::SignalFrame
+                | CfiDirective::Other => {}
+            }
+        }
+        Some(rules)
+    }
+}
+
+// Read the bytes of a value from the pieces of its location that are valid at an address.
+fn evaluate(
+    process: &Process,
+    function: &Function,
+    frame: &Frame,
+    address: u64,
+    locations: &[(Range, Piece)],
+    size: u64,
+) -> Option<Vec<u8>> {
+    let mut bytes = vec![0; size as usize];
+    let mut found = false;
+    for (range, piece) in locations {
+        if !range.contains(address) {
+            continue;
+        }
+        if piece.bit_offset % 8 != 0 {
+            return None;
+        }
+        let begin = piece.bit_offset / 8;
+        let end = match piece.bit_size.get() {
+            Some(bit_size) if bit_size % 8 == 0 => begin + bit_size / 8,
+            Some(_) => return None,
+            None => size,
+        };
+        let dest = bytes.get_mut(begin as usize..end as usize)?;
+        evaluate_piece(process, function, frame, piece, dest)?;
+        found = true;
+    }
+    if found {
+        Some(bytes)
+    } else {
+        None
+    }
+}
+
+fn evaluate_piece(
+    process: &Process,
+    function: &Function,
+    frame: &Frame,
+    piece: &Piece,
+    dest: &mut [u8],
+) -> Option<()> {
+    let register = |register: Register| frame.registers.get(&register).cloned();
+    if piece.is_value {
+        let value = match piece.location {
+            Location::Literal { value } => value,
+            Location::Register { register: r } => register(r)?,
+            Location::RegisterOffset {
+                register: r,
+                offset,
+            } => register(r)?.wrapping_add(offset as u64),
+            _ => return None,
+        };
+        return write_value(value, dest, process.core.big_endian);
+    }
+    let address = match piece.location {
+        Location::Register { register: r } => {
+            let value = register(r)?.checked_shr(piece.location_offset as u32)?;
+            return write_value(value, dest, process.core.big_endian);
+        }
+        Location::RegisterOffset {
+            register: r,
+            offset,
+        } => register(r)?.wrapping_add(offset as u64),
+        Location::FrameOffset { offset } => {
+            frame_base(function, frame)?.wrapping_add(offset as u64)
+        }
+        Location::CfaOffset { offset } => frame.cfa?.wrapping_add(offset as u64),
+        Location::Address { address } => address.get()?.wrapping_add(process.bias),
+        Location::Literal { value } => value,
+        Location::Empty | Location::TlsOffset { .. } | Location::Other => return None,
+    };
+    let address = address.wrapping_add(piece.location_offset / 8);
+    dest.copy_from_slice(process.bytes(address, dest.len() as u64)?);
+    Some(())
+}
+
+fn frame_base(function: &Function, frame: &Frame) -> Option<u64> {
+    match function.frame_base()? {
+        Location::Register { register } => frame.registers.get(&register).cloned(),
+        Location::RegisterOffset { register, offset } => frame
+            .registers
+            .get(&register)
+            .map(|value| value.wrapping_add(offset as u64)),
+        Location::CfaOffset { offset } => frame.cfa.map(|cfa| cfa.wrapping_add(offset as u64)),
+        _ => None,
+    }
+}
+
+fn write_value(value: u64, dest: &mut [u8], big_endian: bool) -> Option<()> {
+    let len = dest.len();
+    if len > 8 {
+        return None;
+    }
+    for (i, byte) in dest.iter_mut().enumerate() {
+        let shift = if big_endian { len - 1 - i } else { i } * 8;
+        *byte = (value >> shift) as u8;
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::Size;
+
+    // The size of `struct elf_prstatus` for x86-64.
+    const PRSTATUS_SIZE: usize = 336;
+
+    // Build a little endian x86-64 core file.
+    //
+    // `registers` are indices within `pr_reg` and their values, and `stack` is the
+    // address and contents of a single `PT_LOAD` segment.
+    fn core(pid: u32, registers: &[(usize, u64)], entry: u64, stack: (u64, &[u8])) -> Vec<u8> {
+        fn note(data: &mut Vec<u8>, n_type: u32, desc: &[u8]) {
+            data.extend_from_slice(&5u32.to_le_bytes());
+            data.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+            data.extend_from_slice(&n_type.to_le_bytes());
+            data.extend_from_slice(b"CORE\0\0\0\0");
+            data.extend_from_slice(desc);
+        }
+
+        let mut prstatus = vec![0; PRSTATUS_SIZE];
+        prstatus[PRSTATUS_PID..][..4].copy_from_slice(&pid.to_le_bytes());
+        for &(index, value) in registers {
+            prstatus[PRSTATUS_REG + index * 8..][..8].copy_from_slice(&value.to_le_bytes());
+        }
+        let mut auxv = Vec::new();
+        for &value in &[AT_ENTRY, entry, AT_NULL, 0] {
+            auxv.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut notes = Vec::new();
+        note(&mut notes, NT_PRSTATUS as u32, &prstatus);
+        note(&mut notes, NT_AUXV as u32, &auxv);
+
+        let phoff = 64;
+        let notes_offset = phoff + 2 * 56;
+        let stack_offset = notes_offset + notes.len();
+        let mut data = vec![0; notes_offset];
+        data[..4].copy_from_slice(b"\x7fELF");
+        data[4] = 2;
+        data[5] = 1;
+        data[16..18].copy_from_slice(&(ET_CORE as u16).to_le_bytes());
+        data[18..20].copy_from_slice(&(EM_X86_64 as u16).to_le_bytes());
+        data[0x20..0x28].copy_from_slice(&(phoff as u64).to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&2u16.to_le_bytes());
+        let headers = [
+            (PT_NOTE as u32, notes_offset, 0, notes.len()),
+            (PT_LOAD as u32, stack_offset, stack.0, stack.1.len()),
+        ];
+        for (i, &(p_type, offset, address, size)) in headers.iter().enumerate() {
+            let ph = &mut data[phoff + i * 56..][..56];
+            ph[..4].copy_from_slice(&p_type.to_le_bytes());
+            ph[8..16].copy_from_slice(&(offset as u64).to_le_bytes());
+            ph[16..24].copy_from_slice(&address.to_le_bytes());
+            ph[32..40].copy_from_slice(&(size as u64).to_le_bytes());
+        }
+        data.extend_from_slice(&notes);
+        data.extend_from_slice(stack.1);
+        data
+    }
+
+    #[test]
+    fn parse() {
+        let data = core(
+            1234,
+            &[(4, 0x10), (16, 0x20), (19, 0x30)],
+            0x40,
+            (0x1000, &[1]),
+        );
+        let core = Core::parse(&data).unwrap();
+        assert_eq!(core.machine, EM_X86_64);
+        assert_eq!(core.entry, Some(0x40));
+        assert_eq!(core.loads, vec![(0x1000, &[1][..])]);
+        assert_eq!(core.threads.len(), 1);
+        let thread = &core.threads[0];
+        assert_eq!(thread.pid, 1234);
+        assert_eq!(thread.pc, 0x20);
+        assert_eq!(thread.registers.get(&Register(6)), Some(&0x10));
+        assert_eq!(thread.registers.get(&Register(7)), Some(&0x30));
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut data = core(1, &[], 0, (0, &[]));
+        data[18..20].copy_from_slice(&3u16.to_le_bytes());
+        let e = Core::parse(&data).err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "unsupported core file machine 3: only x86-64 and AArch64 are supported"
+        );
+
+        data[4] = 1;
+        let e = Core::parse(&data).err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "unsupported core file class: only 64-bit x86-64 and AArch64 are supported"
+        );
+    }
+
+    #[test]
+    fn backtrace() {
+        // Load the executable at a different address, to check the bias.
+        const BIAS: u64 = 0x10000;
+        const STACK: u64 = 0x7ffe_0000;
+        // Stopped in `function_equal` after `push rbp; mov rbp, rsp`, which was
+        // called from `main` with the same frame setup.
+        const PC: u64 = 0x4004e5;
+        const RETURN: u64 = 0x4004dc;
+
+        let mut stack = vec![0; 0x40];
+        let mut write = |offset: usize, value: u64| {
+            stack[offset..][..8].copy_from_slice(&value.to_le_bytes());
+        };
+        // The frame of `function_equal`: saved rbp and return address.
+        write(0x0, STACK + 0x20);
+        write(0x8, RETURN + BIAS);
+        // The frame of `main`: a null return address ends the backtrace.
+        write(0x20, 0);
+        write(0x28, 0);
+        let registers = [(4, STACK), (16, PC + BIAS), (19, STACK)];
+
+        File::parse("tests/bin/diff1", |file| {
+            let data = core(42, &registers, file.entry() + BIAS, (STACK, &stack[..]));
+            let mut output = Vec::new();
+            let options = Options::default();
+            core_dump(file, &data, &mut output, &options)?;
+            let output = String::from_utf8(output).unwrap();
+            let frames: Vec<_> = output
+                .lines()
+                .skip_while(|line| *line != "thread 42")
+                .filter(|line| line.starts_with('#'))
+                .collect();
+            assert_eq!(frames.len(), 2, "{}", output);
+            assert!(frames[0].starts_with("#0 0x4104e5 in function_equal"));
+            assert!(frames[1].starts_with("#1 0x4104dc in main"));
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn register_piece() {
+        let data = core(1, &[], 0, (0, &[]));
+        let core = Core::parse(&data).unwrap();
+        File::parse("tests/bin/diff1", |file| {
+            let process = Process {
+                core: &core,
+                file,
+                bias: 0,
+            };
+            let function = file
+                .units()
+                .iter()
+                .flat_map(|unit| unit.functions())
+                .next()
+                .unwrap();
+            let mut registers = Registers::new();
+            registers.insert(Register(0), 0x1122_3344_5566_7788);
+            let frame = Frame {
+                pc: 0,
+                registers,
+                cfa: None,
+            };
+            // The second 16 bits of rax.
+            let piece = Piece {
+                bit_offset: 0,
+                bit_size: Size::new(16),
+                location: Location::Register {
+                    register: Register(0),
+                },
+                location_offset: 16,
+                is_value: false,
+            };
+            let mut dest = [0; 2];
+            evaluate_piece(&process, function, &frame, &piece, &mut dest).unwrap();
+            assert_eq!(dest, [0x66, 0x55]);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use parser::{
+    File, FileHash, LayoutItem, Member, Namespace, StructType, Type, TypeKind, TypeModifierKind,
+};
+
+use crate::filter;
+use crate::member_access::MemberAccesses;
+use crate::print::{self, TextValuePrinter};
+use crate::reorder::StructKey;
+use crate::symbolize::tagged_name;
+use crate::{Options, Result};
+
+/// Print the structs that match the filter options and have atomic members
+/// that share a cache line with other members.
+///
+/// A member is atomic if its type has the `_Atomic` modifier, is a Rust or C++
+/// atomic type (`core::sync::atomic::AtomicUsize`, `std::atomic<T>`), or is a
+/// struct or union containing an atomic member (such as a `Mutex`).
+///
+/// A cache line is reported if it contains multiple atomic members, or an
+/// atomic member and another member that is written by the code found by the
+/// member access analysis. All members in that line are printed, along with
+/// the number of reads and writes of each member.
+///
+/// Structs with multiple atomic members in one cache line are printed first.
+pub fn false_sharing(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let cacheline_size = if options.cacheline_size != 0 {
+        options.cacheline_size
+    } else {
+        64
+    };
+    let hash = FileHash::new(file);
+    let accesses = access_counts(&hash);
+
+    let mut seen = HashSet::new();
+    let mut reports = Vec::new();
+    for unit in filter::filter_units(file, options) {
+        for ty in filter::filter_types(unit, &hash, options, false) {
+            if let TypeKind::Struct(ref val) = *ty.kind() {
+                let key = StructKey::new(ty, val);
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+                let lines = shared_lines(val, &hash, cacheline_size, |member| {
+                    access_count(&accesses, &key, member).1 != 0
+                });
+                if !lines.is_empty() {
+                    let name = tagged_name("struct", val.namespace(), val.name());
+                    reports.push((name, key, val, lines));
+                }
+            }
+        }
+    }
+    reports.sort_by(|a, b| {
+        let max_a = a.3.iter().map(|line| line.atomics).max();
+        let max_b = b.3.iter().map(|line| line.atomics).max();
+        max_b
+            .cmp(&max_a)
+            .then_with(|| b.3.len().cmp(&a.3.len()))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    for (name, key, val, lines) in reports {
+        write!(w, "{}", name)?;
+        if let Some(size) = val.byte_size() {
+            write!(w, " [size {}]", size)?;
+        }
+        writeln!(w)?;
+        for line in lines {
+            writeln!(
+                w,
+                "\tcacheline {} [{} atomic, {} other]",
+                line.index,
+                line.atomics,
+                line.members.len() - line.atomics
+            )?;
+            for (member, atomic) in line.members {
+                let member_name = member.name().unwrap_or("<anon>");
+                write!(
+                    w,
+                    "\t\t{}[{}]\t{}: ",
+                    print::member::format_bit(member.bit_offset()),
+                    print::member::format_bit(member.bit_size(&hash).unwrap_or(0)),
+                    member_name
+                )?;
+                print::types::print_ref(member.ty(&hash), &mut TextValuePrinter::new(w), &hash)?;
+                if atomic {
+                    write!(w, " <atomic>")?;
+                }
+                let (reads, writes) = access_count(&accesses, &key, member);
+                writeln!(w, " [reads {}, writes {}]", reads, writes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A cache line containing an atomic member and at least one other member.
+struct Line<'input, 'item> {
+    index: u64,
+    atomics: usize,
+    members: Vec<(&'item Member<'input>, bool)>,
+}
+
+fn shared_lines<'input, 'item, F>(
+    ty: &'item StructType<'input>,
+    hash: &FileHash<'input>,
+    cacheline_size: u64,
+    is_written: F,
+) -> Vec<Line<'input, 'item>>
+where
+    F: Fn(&Member) -> bool,
+{
+    let mut lines: Vec<Line> = Vec::new();
+    for layout in ty.layout(hash) {
+        let member = match layout.item {
+            LayoutItem::Member(member) => member,
+            _ => continue,
+        };
+        let bit_size = match layout.bit_size.get() {
+            Some(bit_size) if bit_size != 0 => bit_size,
+            _ => continue,
+        };
+        let atomic = member.ty(hash).map_or(false, |ty| is_atomic(&ty, hash));
+        let first = layout.bit_offset / 8 / cacheline_size;
+        let last = (layout.bit_offset + bit_size - 1) / 8 / cacheline_size;
+        for index in first..=last {
+            if lines.last().map(|line| line.index) != Some(index) {
+                lines.push(Line {
+                    index,
+                    atomics: 0,
+                    members: Vec::new(),
+                });
+            }
+            let line = lines.last_mut().unwrap();
+            line.members.push((member, atomic));
+            if atomic {
+                line.atomics += 1;
+            }
+        }
+    }
+    lines.retain(|line| {
+        line.atomics > 1
+            || (line.atomics != 0
+                && line
+                    .members
+                    .iter()
+                    .any(|&(member, atomic)| !atomic && is_written(member)))
+    });
+    lines
+}
+
+/// Return true if the type is atomic, ignoring typedefs, qualifiers and arrays.
+fn is_atomic(ty: &Type, hash: &FileHash) -> bool {
+    let target: Option<Cow<Type>> = match *ty.kind() {
+        TypeKind::Modifier(ref val) => match val.kind() {
+            TypeModifierKind::Atomic => return true,
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => return false,
+            _ => val.ty(hash),
+        },
+        TypeKind::Def(ref val) => val.ty(hash),
+        TypeKind::Array(ref val) => val.element_type(hash),
+        TypeKind::Struct(ref val) => {
+            return is_atomic_name(val.namespace(), val.name())
+                || val
+                    .inherits()
+                    .iter()
+                    .any(|inherit| inherit.ty(hash).map_or(false, |ty| is_atomic(&ty, hash)))
+                || is_atomic_member(val.members(), hash);
+        }
+        TypeKind::Union(ref val) => {
+            return is_atomic_name(val.namespace(), val.name())
+                || is_atomic_member(val.members(), hash);
+        }
+        _ => return false,
+    };
+    target.map_or(false, |target| is_atomic(&target, hash))
+}
+
+fn is_atomic_member(members: &[Member], hash: &FileHash) -> bool {
+    members
+        .iter()
+        .any(|member| member.ty(hash).map_or(false, |ty| is_atomic(&ty, hash)))
+}
+
+fn is_atomic_name(namespace: Option<&Namespace>, name: Option<&str>) -> bool {
+    let name = match name {
+        Some(name) => Namespace::qualified_name(namespace, name),
+        None => return false,
+    };
+    // libc++ uses an inline namespace.
+    let name = name.replacen("std::__1::", "std::", 1);
+    // Rust: `AtomicUsize`, `AtomicPtr<T>`. C++: `atomic<T>`, `__atomic_base<T>`.
+    name.starts_with("core::sync::atomic::Atomic")
+        || name.starts_with("std::sync::atomic::Atomic")
+        || name.starts_with("std::atomic<")
+        || name.starts_with("std::__atomic_base<")
+}
+
+/// Count the reads and writes of each member, by struct and member name.
+fn access_counts(hash: &FileHash) -> HashMap<(StructKey, String), (usize, usize)> {
+    let mut counts = HashMap::new();
+    let accesses = MemberAccesses::new(hash);
+    for access in accesses.list() {
+        let member = match access.member() {
+            Some(member) => member,
+            None => continue,
+        };
+        if let TypeKind::Struct(ref val) = *access.ty().kind() {
+            let key = (
+                StructKey::new(access.ty(), val),
+                member.name().unwrap_or("<anon>").to_string(),
+            );
+            let count = counts.entry(key).or_insert((0, 0));
+            if access.is_write() {
+                count.1 += 1;
+            } else {
+                count.0 += 1;
+            }
+        }
+    }
+    counts
+}
+
+fn access_count(
+    accesses: &HashMap<(StructKey, String), (usize, usize)>,
+    key: &StructKey,
+    member: &Member,
+) -> (usize, usize) {
+    let member_name = member.name().unwrap_or("<anon>").to_string();
+    accesses
+        .get(&(key.clone(), member_name))
+        .cloned()
+        .unwrap_or((0, 0))
+}
@@ -0,0 +1,137 @@
+use std::io::Write;
+
+use crate::Result;
+
+/// Write a string as a JSON string literal.
+pub(crate) fn write_str(w: &mut dyn Write, s: &str) -> Result<()> {
+    write!(w, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            '\n' => write!(w, "\\n")?,
+            '\r' => write!(w, "\\r")?,
+            '\t' => write!(w, "\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{}", c)?,
+        }
+    }
+    write!(w, "\"")?;
+    Ok(())
+}
+
+/// Write an optional string as a JSON string literal or `null`.
+pub(crate) fn write_opt_str(w: &mut dyn Write, s: Option<&str>) -> Result<()> {
+    match s {
+        Some(s) => write_str(w, s),
+        None => {
+            write!(w, "null")?;
+            Ok(())
+        }
+    }
+}
+
+/// A JSON value.
+///
+/// Object members are kept in insertion order so that output is stable.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+impl Value {
+    /// An address as a hexadecimal string.
+    ///
+    /// JSON numbers may lose precision above 2^53, which includes kernel addresses.
+    pub(crate) fn address(address: Option<u64>) -> Value {
+        match address {
+            Some(address) => Value::String(format!("0x{:x}", address)),
+            None => Value::Null,
+        }
+    }
+
+    /// Write the value in compact form.
+    pub(crate) fn write(&self, w: &mut dyn Write) -> Result<()> {
+        match self {
+            Value::Null => write!(w, "null")?,
+            Value::Bool(value) => write!(w, "{}", value)?,
+            Value::Unsigned(value) => write!(w, "{}", value)?,
+            Value::Signed(value) => write!(w, "{}", value)?,
+            Value::String(value) => write_str(w, value)?,
+            Value::Array(values) => {
+                write!(w, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        write!(w, ",")?;
+                    }
+                    value.write(w)?;
+                }
+                write!(w, "]")?;
+            }
+            Value::Object(members) => {
+                write!(w, "{{")?;
+                for (i, (name, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        write!(w, ",")?;
+                    }
+                    write_str(w, name)?;
+                    write!(w, ":")?;
+                    value.write(w)?;
+                }
+                write!(w, "}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Value {
+        Value::Unsigned(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        Value::Signed(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::String(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Value {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Value {
+        Value::Array(values)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        match value {
+            Some(value) => value.into(),
+            None => Value::Null,
+        }
+    }
+}
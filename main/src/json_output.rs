@@ -0,0 +1,574 @@
+use std::borrow::Cow;
+use std::io::Write;
+
+use parser::{
+    BaseTypeEncoding, File, FileHash, Function, InlinedFunction, Layout, LayoutItem, LocalVariable,
+    Namespace, Parameter, Source, Type, TypeKind, Unit, Variable,
+};
+
+use crate::filter;
+use crate::json::Value;
+use crate::print::{self, MergeResult, TextValuePrinter};
+use crate::snapshot;
+use crate::symbolize::source_string;
+use crate::{Options, Result};
+
+/// The version of the JSON schema.
+///
+/// This is incremented for any change that is not backwards compatible.
+const VERSION: u64 = 1;
+
+/// Print the file summary, units, types, functions and variables as JSON.
+///
+/// The filter and category options are used to select the entries to print.
+/// The schema is documented in the README.
+pub fn print_json(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let mut root = vec![("version", Value::from(VERSION))];
+    if options.category_file {
+        let mut summary = vec![("path", Value::from(file.path()))];
+        summary.extend(file_summary(&hash, file));
+        root.push(("file", Value::Object(summary)));
+    }
+    let mut units = Vec::new();
+    for unit in filter::filter_units(file, options) {
+        units.push(unit_value(&hash, unit, options)?);
+    }
+    root.push(("units", Value::Array(units)));
+    Value::Object(root).write(w)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+/// Print the differences between two files as JSON.
+///
+/// Each difference is a record with a status of `added`, `deleted` or `modified`.
+/// Entries are matched using the same rules as the text diff, and an entry is
+/// modified if any of its JSON fields differ. Use the ignore options to omit
+/// fields such as addresses that are expected to differ.
+pub fn diff_json(file_a: &File, file_b: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    snapshot::check_diff_options(file_a, file_b, options)?;
+    let hash_a = FileHash::new(file_a);
+    let hash_b = FileHash::new(file_b);
+    let mut changes = Vec::new();
+    if options.category_file {
+        let summary_a = Value::Object(file_summary(&hash_a, file_a));
+        let summary_b = Value::Object(file_summary(&hash_b, file_b));
+        if summary_a != summary_b {
+            changes.push(change(
+                "modified",
+                "file",
+                None,
+                Value::Null,
+                Some(summary_a),
+                Some(summary_b),
+            ));
+        }
+    }
+    for unit in print::file::merged_units(&hash_a, file_a, &hash_b, file_b, options) {
+        match unit {
+            MergeResult::Both(unit_a, unit_b) => {
+                diff_unit(&mut changes, &hash_a, unit_a, &hash_b, unit_b, options)?;
+            }
+            MergeResult::Left(unit_a) => {
+                if !options.ignore_deleted {
+                    let value = unit_value(&hash_a, unit_a, options)?;
+                    changes.push(change(
+                        "deleted",
+                        "unit",
+                        None,
+                        unit_name(unit_a),
+                        Some(value),
+                        None,
+                    ));
+                }
+            }
+            MergeResult::Right(unit_b) => {
+                if !options.ignore_added {
+                    let value = unit_value(&hash_b, unit_b, options)?;
+                    changes.push(change(
+                        "added",
+                        "unit",
+                        None,
+                        unit_name(unit_b),
+                        None,
+                        Some(value),
+                    ));
+                }
+            }
+        }
+    }
+    let root = vec![
+        ("version", Value::from(VERSION)),
+        ("old", Value::Object(vec![("path", file_a.path().into())])),
+        ("new", Value::Object(vec![("path", file_b.path().into())])),
+        ("changes", Value::Array(changes)),
+    ];
+    Value::Object(root).write(w)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+fn diff_unit(
+    changes: &mut Vec<Value>,
+    hash_a: &FileHash,
+    unit_a: &Unit,
+    hash_b: &FileHash,
+    unit_b: &Unit,
+    options: &Options,
+) -> Result<()> {
+    let mut add = |kind, name: Value, a: Option<Value>, b: Option<Value>| {
+        let status = match (&a, &b) {
+            (Some(a), Some(b)) if a == b => return,
+            (Some(_), Some(_)) => "modified",
+            (Some(_), None) if !options.ignore_deleted => "deleted",
+            (None, Some(_)) if !options.ignore_added => "added",
+            _ => return,
+        };
+        changes.push(change(status, kind, Some(unit_name(unit_b)), name, a, b));
+    };
+
+    if options.category_unit {
+        let summary_a = Value::Object(unit_summary(hash_a, unit_a));
+        let summary_b = Value::Object(unit_summary(hash_b, unit_b));
+        add("unit", unit_name(unit_b), Some(summary_a), Some(summary_b));
+    }
+    if options.category_type {
+        for ty in print::unit::merged_types(hash_a, unit_a, hash_b, unit_b, options) {
+            let (a, b) = match ty {
+                MergeResult::Both(a, b) => (Some(a), Some(b)),
+                MergeResult::Left(a) => (Some(a), None),
+                MergeResult::Right(b) => (None, Some(b)),
+            };
+            let name = type_name(a.or(b).unwrap());
+            let a = match a {
+                Some(a) => Some(type_value(hash_a, unit_a, a)?),
+                None => None,
+            };
+            let b = match b {
+                Some(b) => Some(type_value(hash_b, unit_b, b)?),
+                None => None,
+            };
+            add("type", name, a, b);
+        }
+    }
+    if options.category_function {
+        let (functions, inlined_functions) =
+            print::unit::merged_functions(hash_a, unit_a, hash_b, unit_b, options);
+        for function in functions.into_iter().chain(inlined_functions) {
+            let (a, b) = match function {
+                MergeResult::Both(a, b) => (Some(a), Some(b)),
+                MergeResult::Left(a) => (Some(a), None),
+                MergeResult::Right(b) => (None, Some(b)),
+            };
+            let function = a.or(b).unwrap();
+            let name = qualified_name(function.namespace(), function.name());
+            let a = match a {
+                Some(a) => Some(function_value(hash_a, unit_a, a, options)?),
+                None => None,
+            };
+            let b = match b {
+                Some(b) => Some(function_value(hash_b, unit_b, b, options)?),
+                None => None,
+            };
+            add("function", name, a, b);
+        }
+    }
+    if options.category_variable {
+        for variable in print::unit::merged_variables(hash_a, unit_a, hash_b, unit_b, options) {
+            let (a, b) = match variable {
+                MergeResult::Both(a, b) => (Some(a), Some(b)),
+                MergeResult::Left(a) => (Some(a), None),
+                MergeResult::Right(b) => (None, Some(b)),
+            };
+            let variable = a.or(b).unwrap();
+            let name = qualified_name(variable.namespace(), variable.name());
+            let a = match a {
+                Some(a) => Some(variable_value(hash_a, unit_a, a, options)?),
+                None => None,
+            };
+            let b = match b {
+                Some(b) => Some(variable_value(hash_b, unit_b, b, options)?),
+                None => None,
+            };
+            add("variable", name, a, b);
+        }
+    }
+    Ok(())
+}
+
+fn change(
+    status: &str,
+    kind: &str,
+    unit: Option<Value>,
+    name: Value,
+    old: Option<Value>,
+    new: Option<Value>,
+) -> Value {
+    let mut object = vec![("status", status.into()), ("kind", kind.into())];
+    if let Some(unit) = unit {
+        object.push(("unit", unit));
+    }
+    object.push(("name", name));
+    if let Some(old) = old {
+        object.push(("old", old));
+    }
+    if let Some(new) = new {
+        object.push(("new", new));
+    }
+    Value::Object(object)
+}
+
+fn file_summary(hash: &FileHash, file: &File) -> Vec<(&'static str, Value)> {
+    let size = file.ranges(hash).size();
+    let fn_size = file.function_size();
+    let var_size = file.variable_size(hash);
+    let sections = file
+        .sections()
+        .iter()
+        .map(|section| {
+            Value::Object(vec![
+                ("name", section.name().into()),
+                (
+                    "address",
+                    Value::address(section.address().map(|range| range.begin)),
+                ),
+                ("size", section.size().into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    vec![
+        ("size", size.into()),
+        ("fn_size", fn_size.into()),
+        ("var_size", var_size.into()),
+        ("other_size", (size - fn_size - var_size).into()),
+        ("sections", sections.into()),
+    ]
+}
+
+fn unit_name(unit: &Unit) -> Value {
+    unit.name().into()
+}
+
+fn unit_summary(hash: &FileHash, unit: &Unit) -> Vec<(&'static str, Value)> {
+    vec![
+        ("fn_size", unit.function_size().into()),
+        ("var_size", unit.variable_size(hash).into()),
+        ("unknown_size", unit.unknown_ranges(hash).size().into()),
+    ]
+}
+
+fn unit_value(hash: &FileHash, unit: &Unit, options: &Options) -> Result<Value> {
+    let mut object = vec![("name", unit_name(unit)), ("dir", unit.dir().into())];
+    if options.category_unit {
+        object.extend(unit_summary(hash, unit));
+    }
+    if options.category_type {
+        let mut types = Vec::new();
+        for ty in filter::filter_types(unit, hash, options, false) {
+            types.push(type_value(hash, unit, ty)?);
+        }
+        object.push(("types", types.into()));
+    }
+    if options.category_function {
+        let mut functions = Vec::new();
+        for function in filter::filter_functions(unit, options) {
+            functions.push(function_value(hash, unit, function, options)?);
+        }
+        object.push(("functions", functions.into()));
+    }
+    if options.category_variable {
+        let mut variables = Vec::new();
+        for variable in filter::filter_variables(unit, options) {
+            variables.push(variable_value(hash, unit, variable, options)?);
+        }
+        object.push(("variables", variables.into()));
+    }
+    Ok(Value::Object(object))
+}
+
+fn type_name(ty: &Type) -> Value {
+    match ty.kind() {
+        TypeKind::Base(val) => val.name().into(),
+        TypeKind::Def(val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Struct(val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Union(val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Enumeration(val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Unspecified(val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Void
+        | TypeKind::Array(..)
+        | TypeKind::Function(..)
+        | TypeKind::PointerToMember(..)
+        | TypeKind::Modifier(..)
+        | TypeKind::Subrange(..) => Value::Null,
+    }
+}
+
+fn type_value(hash: &FileHash, unit: &Unit, ty: &Type) -> Result<Value> {
+    let mut object = vec![("kind", Value::Null), ("name", type_name(ty))];
+    match ty.kind() {
+        TypeKind::Base(val) => {
+            object[0].1 = "base".into();
+            object.push(("size", val.byte_size().into()));
+            object.push(("encoding", encoding_name(val.encoding()).into()));
+        }
+        TypeKind::Def(val) => {
+            object[0].1 = "typedef".into();
+            object.push(("size", val.byte_size(hash).into()));
+            object.push(("type", type_ref(hash, val.ty(hash))?));
+            object.push(("source", source_value(val.source(), unit)));
+        }
+        TypeKind::Struct(val) => {
+            object[0].1 = "struct".into();
+            object.push(("size", val.byte_size().into()));
+            object.push(("declaration", val.is_declaration().into()));
+            object.push(("source", source_value(val.source(), unit)));
+            object.push(("layout", layout_value(hash, unit, &val.layout(hash))?));
+        }
+        TypeKind::Union(val) => {
+            object[0].1 = "union".into();
+            object.push(("size", val.byte_size().into()));
+            object.push(("declaration", val.is_declaration().into()));
+            object.push(("source", source_value(val.source(), unit)));
+            let mut members = Vec::new();
+            for member in val.members() {
+                members.push(Value::Object(vec![
+                    ("bit_offset", member.bit_offset().into()),
+                    ("bit_size", member.bit_size(hash).into()),
+                    ("name", member.name().into()),
+                    ("type", type_ref(hash, member.ty(hash))?),
+                ]));
+            }
+            object.push(("members", members.into()));
+        }
+        TypeKind::Enumeration(val) => {
+            object[0].1 = "enum".into();
+            object.push(("size", val.byte_size(hash).into()));
+            object.push(("declaration", val.is_declaration().into()));
+            object.push(("source", source_value(val.source(), unit)));
+            object.push(("type", type_ref(hash, val.ty(hash))?));
+            let enumerators = val
+                .enumerators(hash)
+                .iter()
+                .map(|enumerator| {
+                    Value::Object(vec![
+                        ("name", enumerator.name().into()),
+                        ("value", enumerator.value().into()),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            object.push(("enumerators", enumerators.into()));
+        }
+        TypeKind::Void
+        | TypeKind::Array(..)
+        | TypeKind::Function(..)
+        | TypeKind::Unspecified(..)
+        | TypeKind::PointerToMember(..)
+        | TypeKind::Modifier(..)
+        | TypeKind::Subrange(..) => {
+            object[0].1 = "other".into();
+            object[1].1 = type_ref(hash, Some(Cow::Borrowed(ty)))?;
+            object.push(("size", ty.byte_size(hash).into()));
+        }
+    }
+    Ok(Value::Object(object))
+}
+
+fn encoding_name(encoding: BaseTypeEncoding) -> &'static str {
+    match encoding {
+        BaseTypeEncoding::Other => "other",
+        BaseTypeEncoding::Boolean => "boolean",
+        BaseTypeEncoding::Address => "address",
+        BaseTypeEncoding::Signed => "signed",
+        BaseTypeEncoding::SignedChar => "signed-char",
+        BaseTypeEncoding::Unsigned => "unsigned",
+        BaseTypeEncoding::UnsignedChar => "unsigned-char",
+        BaseTypeEncoding::Float => "float",
+    }
+}
+
+fn layout_value<'input>(
+    hash: &FileHash<'input>,
+    unit: &Unit,
+    layout: &[Layout<'input, '_>],
+) -> Result<Value> {
+    let mut items = Vec::new();
+    for item in layout {
+        let mut object = vec![
+            ("kind", Value::Null),
+            ("bit_offset", item.bit_offset.into()),
+            ("bit_size", item.bit_size.get().into()),
+        ];
+        match item.item {
+            LayoutItem::Padding => {
+                object[0].1 = "padding".into();
+            }
+            LayoutItem::Member(member) => {
+                object[0].1 = "member".into();
+                object.push(("name", member.name().into()));
+                object.push(("type", type_ref(hash, member.ty(hash))?));
+                // Anonymous structs and unions have no other entry, so include their layout.
+                if member.is_inline(hash) {
+                    if let Some(ty) = member.ty(hash) {
+                        object.push(("inline", type_value(hash, unit, &ty)?));
+                    }
+                }
+            }
+            LayoutItem::Inherit(inherit) => {
+                object[0].1 = "inherit".into();
+                object.push(("type", type_ref(hash, inherit.ty(hash))?));
+            }
+            LayoutItem::VariantPart(variant_part) => {
+                object[0].1 = "variant_part".into();
+                let mut variants = Vec::new();
+                for variant in variant_part.variants() {
+                    let layout = variant.layout(item.bit_offset, item.bit_size.get(), hash);
+                    variants.push(Value::Object(vec![
+                        ("name", variant.name().into()),
+                        ("discriminant", variant.discriminant_value().into()),
+                        ("layout", layout_value(hash, unit, &layout)?),
+                    ]));
+                }
+                object.push(("variants", variants.into()));
+            }
+        }
+        items.push(Value::Object(object));
+    }
+    Ok(items.into())
+}
+
+fn function_value(
+    hash: &FileHash,
+    unit: &Unit,
+    function: &Function,
+    options: &Options,
+) -> Result<Value> {
+    let mut object = vec![
+        (
+            "name",
+            qualified_name(function.namespace(), function.name()),
+        ),
+        ("linkage_name", function.linkage_name().into()),
+    ];
+    if !options.ignore_function_symbol_name {
+        object.push(("symbol_name", function.symbol_name().into()));
+    }
+    if !options.ignore_function_address {
+        object.push(("address", Value::address(function.address())));
+    }
+    if !options.ignore_function_size {
+        object.push(("size", function.size().into()));
+    }
+    if !options.ignore_function_inline {
+        object.push(("inline", function.is_inline().into()));
+    }
+    object.push(("declaration", function.is_declaration().into()));
+    object.push(("source", source_value(function.source(), unit)));
+    object.push(("return_type", type_ref(hash, function.return_type(hash))?));
+    let details = function.details(hash);
+    object.push(("parameters", parameters_value(hash, details.parameters())?));
+    object.push(("variables", variables_value(hash, details.variables())?));
+    object.push((
+        "inlined_functions",
+        inlined_functions_value(hash, unit, details.inlined_functions())?,
+    ));
+    Ok(Value::Object(object))
+}
+
+fn parameters_value(hash: &FileHash, parameters: &[Parameter]) -> Result<Value> {
+    let mut values = Vec::new();
+    for parameter in parameters {
+        values.push(Value::Object(vec![
+            ("name", parameter.name().into()),
+            ("type", type_ref(hash, parameter.ty(hash))?),
+            ("size", parameter.byte_size(hash).into()),
+        ]));
+    }
+    Ok(values.into())
+}
+
+fn variables_value(hash: &FileHash, variables: &[LocalVariable]) -> Result<Value> {
+    let mut values = Vec::new();
+    for variable in variables {
+        values.push(Value::Object(vec![
+            ("name", variable.name().into()),
+            ("type", type_ref(hash, variable.ty(hash))?),
+            ("size", variable.byte_size(hash).into()),
+        ]));
+    }
+    Ok(values.into())
+}
+
+fn inlined_functions_value(
+    hash: &FileHash,
+    unit: &Unit,
+    inlined_functions: &[InlinedFunction],
+) -> Result<Value> {
+    let mut values = Vec::new();
+    for inlined_function in inlined_functions {
+        let name = match inlined_function.abstract_origin(hash) {
+            Some(function) => qualified_name(function.namespace(), function.name()),
+            None => Value::Null,
+        };
+        values.push(Value::Object(vec![
+            ("name", name),
+            ("size", inlined_function.size().into()),
+            (
+                "call_source",
+                source_value(inlined_function.call_source(), unit),
+            ),
+            (
+                "inlined_functions",
+                inlined_functions_value(hash, unit, inlined_function.inlined_functions())?,
+            ),
+        ]));
+    }
+    Ok(values.into())
+}
+
+fn variable_value(
+    hash: &FileHash,
+    unit: &Unit,
+    variable: &Variable,
+    options: &Options,
+) -> Result<Value> {
+    let mut object = vec![
+        (
+            "name",
+            qualified_name(variable.namespace(), variable.name()),
+        ),
+        ("linkage_name", variable.linkage_name().into()),
+    ];
+    if !options.ignore_variable_symbol_name {
+        object.push(("symbol_name", variable.symbol_name().into()));
+    }
+    if !options.ignore_variable_address {
+        object.push(("address", Value::address(variable.address())));
+    }
+    object.push(("size", variable.byte_size(hash).into()));
+    object.push(("type", type_ref(hash, variable.ty(hash))?));
+    object.push(("declaration", variable.is_declaration().into()));
+    object.push(("source", source_value(variable.source(), unit)));
+    Ok(Value::Object(object))
+}
+
+fn qualified_name(namespace: Option<&Namespace>, name: Option<&str>) -> Value {
+    match name {
+        Some(name) => Namespace::qualified_name(namespace, name).into(),
+        None => Value::Null,
+    }
+}
+
+fn source_value(source: &Source, unit: &Unit) -> Value {
+    source_string(source, unit).into()
+}
+
+// The name of a type as it is printed by the text format, such as `*const u8`.
+fn type_ref(hash: &FileHash, ty: Option<Cow<Type>>) -> Result<Value> {
+    if ty.is_none() {
+        return Ok(Value::Null);
+    }
+    let mut buf = Vec::new();
+    print::types::print_ref(ty, &mut TextValuePrinter::new(&mut buf), hash)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned().into())
+}
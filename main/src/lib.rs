@@ -11,15 +11,62 @@ extern crate log;
 
 use parser::Namespace;
 
-pub use parser::{File, Result};
+pub use parser::{File, ParseOptions, Result};
+
+mod c_header;
+pub use self::c_header::c_header;
+
+mod call_graph;
+pub use self::call_graph::{call_graph, CallEdge, CallGraph};
 
 mod code;
+
+mod core_dump;
+pub use self::core_dump::core_dump;
+
+mod false_sharing;
+pub use self::false_sharing::false_sharing;
+
 mod filter;
+mod json;
+
+mod json_output;
+pub use self::json_output::{diff_json, print_json};
+
+mod member_access;
+pub use self::member_access::{member_accesses, MemberAccess, MemberAccesses};
+
+mod padding;
+pub use self::padding::padding;
 
 mod print;
 pub use self::print::file::{diff, print};
 pub use self::print::{DiffPrefix, HtmlPrinter, Printer, TextPrinter};
 
+mod reach;
+pub use self::reach::reach;
+
+mod reorder;
+pub use self::reorder::reorder;
+
+mod rust_bindings;
+pub use self::rust_bindings::rust_bindings;
+
+mod snapshot;
+pub use self::snapshot::save_snapshot;
+
+mod stack;
+pub use self::stack::{frame_size, stack_usage, FrameSize};
+
+mod symbolize;
+pub use self::symbolize::symbolize;
+
+mod unreferenced;
+pub use self::unreferenced::unreferenced;
+
+mod xref;
+pub use self::xref::{VariableAccess, VariableAccesses};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sort {
     None,
@@ -39,13 +86,26 @@ pub struct Options<'a> {
     pub print_file_address: bool,
     pub print_unit_address: bool,
     pub print_function_calls: bool,
+    pub print_function_callers: bool,
+    pub print_function_accesses: bool,
     pub print_function_instructions: bool,
     pub print_function_variables: bool,
     pub print_function_stack_frame: bool,
     pub print_inlined_function_parameters: bool,
     pub print_variable_locations: bool,
+    pub print_variable_accessed_by: bool,
+    pub print_variable_value: bool,
+    pub print_struct_cachelines: bool,
+    pub print_function_source_lines: bool,
+    pub print_function_source_text: bool,
+    pub print_file_source_lines: bool,
     pub inline_depth: usize,
+    pub source_line_limit: usize,
+    pub call_graph_depth: usize,
+    pub cacheline_size: u64,
+    pub reorder_cachelines: bool,
     pub html: bool,
+    pub json: bool,
 
     pub category_file: bool,
     pub category_unit: bool,
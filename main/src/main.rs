@@ -14,16 +14,49 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 
-use std::io::BufWriter;
+use std::io::{BufWriter, Read, Write};
 
 // Mode
+const OPT_MODE: &str = "mode";
 const OPT_FILE: &str = "file";
 const OPT_DIFF: &str = "diff";
+const OPT_SYMBOLIZE: &str = "symbolize";
+const OPT_SYMBOLIZE_ADDRESS: &str = "address";
+const OPT_CALL_GRAPH: &str = "call-graph";
+const OPT_REACH: &str = "reach";
+const OPT_REACH_FROM: &str = "reach-from";
+const OPT_STACK_USAGE: &str = "stack-usage";
+const OPT_UNREFERENCED: &str = "unreferenced";
+const OPT_MEMBER_ACCESSES: &str = "member-accesses";
+const OPT_CORE: &str = "core";
+const OPT_SAVE_SNAPSHOT: &str = "save-snapshot";
+const OPT_C_HEADER: &str = "c-header";
+const OPT_RUST_BINDINGS: &str = "rust-bindings";
+const OPT_REORDER: &str = "reorder";
+const OPT_REORDER_CACHELINES: &str = "reorder-cachelines";
+const OPT_PADDING: &str = "padding";
+const OPT_FALSE_SHARING: &str = "false-sharing";
+
+type Mode = fn(&ddbug::File, &mut dyn Write, &ddbug::Options) -> ddbug::Result<()>;
+
+// Modes that only need the file and the options.
+const MODES: &[(&str, Mode)] = &[
+    (OPT_CALL_GRAPH, ddbug::call_graph),
+    (OPT_STACK_USAGE, ddbug::stack_usage),
+    (OPT_UNREFERENCED, ddbug::unreferenced),
+    (OPT_MEMBER_ACCESSES, ddbug::member_accesses),
+    (OPT_C_HEADER, ddbug::c_header),
+    (OPT_RUST_BINDINGS, ddbug::rust_bindings),
+    (OPT_REORDER, ddbug::reorder),
+    (OPT_PADDING, ddbug::padding),
+    (OPT_FALSE_SHARING, ddbug::false_sharing),
+];
 
 // Print format
 const OPT_OUTPUT: &str = "format";
 const OPT_OUTPUT_TEXT: &str = "text";
 const OPT_OUTPUT_HTML: &str = "html";
+const OPT_OUTPUT_JSON: &str = "json";
 
 // Print categories
 const OPT_CATEGORY: &str = "category";
@@ -41,14 +74,26 @@ const OPT_PRINT_SOURCE: &str = "source";
 const OPT_PRINT_FILE_ADDRESS: &str = "file-address";
 const OPT_PRINT_UNIT_ADDRESS: &str = "unit-address";
 const OPT_PRINT_FUNCTION_CALLS: &str = "function-calls";
+const OPT_PRINT_FUNCTION_CALLERS: &str = "function-callers";
+const OPT_PRINT_FUNCTION_ACCESSES: &str = "function-accesses";
 const OPT_PRINT_FUNCTION_INSTRUCTIONS: &str = "function-instructions";
 const OPT_PRINT_FUNCTION_VARIABLES: &str = "function-variables";
 const OPT_PRINT_FUNCTION_STACK_FRAME: &str = "function-stack-frame";
 const OPT_PRINT_INLINED_FUNCTION_PARAMETERS: &str = "inlined-function-parameters";
 const OPT_PRINT_VARIABLE_LOCATIONS: &str = "variable-locations";
+const OPT_PRINT_VARIABLE_ACCESSED_BY: &str = "variable-accessed-by";
+const OPT_PRINT_VARIABLE_VALUE: &str = "variable-value";
+const OPT_PRINT_STRUCT_CACHELINES: &str = "struct-cachelines";
+const OPT_PRINT_FILE_SOURCE_LINES: &str = "file-source-lines";
+const OPT_PRINT_FUNCTION_SOURCE_LINES: &str = "function-source-lines";
+const OPT_PRINT_FUNCTION_SOURCE_TEXT: &str = "function-source-text";
 
 // Print parameters
 const OPT_INLINE_DEPTH: &str = "inline-depth";
+const OPT_SOURCE_LINE_LIMIT: &str = "source-line-limit";
+const OPT_CALL_GRAPH_DEPTH: &str = "call-graph-depth";
+const OPT_CACHELINE_SIZE: &str = "cacheline-size";
+const OPT_SECTION_ADDRESSES: &str = "section-addresses";
 
 // Filters
 const OPT_FILTER: &str = "filter";
@@ -95,9 +140,111 @@ fn main() {
             clap::Arg::with_name(OPT_DIFF)
                 .short("d")
                 .long(OPT_DIFF)
-                .help("Print difference between two files")
+                .help("Print difference between two files, either of which may be a snapshot")
                 .value_names(&["FILE", "FILE"]),
         )
+        .arg(
+            clap::Arg::with_name(OPT_SYMBOLIZE)
+                .long(OPT_SYMBOLIZE)
+                .help("Print the function and source location of addresses"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_SYMBOLIZE_ADDRESS)
+                .help(
+                    "Hexadecimal addresses to symbolize, with an optional 0x prefix \
+                     (defaults to reading from stdin)",
+                )
+                .value_name("ADDRESS")
+                .index(2)
+                .multiple(true)
+                .requires(OPT_SYMBOLIZE),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_CALL_GRAPH)
+                .long(OPT_CALL_GRAPH)
+                .help("Print the call graph in Graphviz DOT format"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_REACH)
+                .long(OPT_REACH)
+                .help("Print the functions that can call the given functions")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+                .value_name("FUNCTION"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_REACH_FROM)
+                .long(OPT_REACH_FROM)
+                .help("Print the shortest call paths from the given functions instead")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+                .value_name("FUNCTION")
+                .requires(OPT_REACH),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_STACK_USAGE)
+                .long(OPT_STACK_USAGE)
+                .help("Print the worst case stack depth of functions"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_UNREFERENCED)
+                .long(OPT_UNREFERENCED)
+                .help("Print the functions that are never referenced"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_MEMBER_ACCESSES)
+                .long(OPT_MEMBER_ACCESSES)
+                .help("Print the struct members that are accessed by functions"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_CORE)
+                .long(OPT_CORE)
+                .help("Print the variables and stack frames in a core file of the given executable")
+                .takes_value(true)
+                .value_name("CORE"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_SAVE_SNAPSHOT)
+                .long(OPT_SAVE_SNAPSHOT)
+                .help("Write a snapshot of the file that can be used with --diff")
+                .takes_value(true)
+                .value_name("SNAPSHOT"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_C_HEADER)
+                .long(OPT_C_HEADER)
+                .help("Print C declarations of the types and the types they depend on"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_RUST_BINDINGS)
+                .long(OPT_RUST_BINDINGS)
+                .help(
+                    "Print Rust #[repr(C)] declarations of the types and the types they depend on",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_REORDER)
+                .long(OPT_REORDER)
+                .help("Print the structs that can be made smaller by reordering their members"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_REORDER_CACHELINES)
+                .long(OPT_REORDER_CACHELINES)
+                .help("Only reorder members within their current cache lines")
+                .requires(OPT_REORDER),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_PADDING)
+                .long(OPT_PADDING)
+                .help("Print the structs and unions that contain padding"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_FALSE_SHARING)
+                .long(OPT_FALSE_SHARING)
+                .help("Print the atomic struct members that share a cache line with other members"),
+        )
         .arg(
             clap::Arg::with_name(OPT_OUTPUT)
                 .short("o")
@@ -105,7 +252,7 @@ fn main() {
                 .help("Output format")
                 .takes_value(true)
                 .value_name("FORMAT")
-                .possible_values(&[OPT_OUTPUT_TEXT, OPT_OUTPUT_HTML]),
+                .possible_values(&[OPT_OUTPUT_TEXT, OPT_OUTPUT_HTML, OPT_OUTPUT_JSON]),
         )
         .arg(
             clap::Arg::with_name(OPT_CATEGORY)
@@ -140,11 +287,19 @@ fn main() {
                     OPT_PRINT_FILE_ADDRESS,
                     OPT_PRINT_UNIT_ADDRESS,
                     OPT_PRINT_FUNCTION_CALLS,
+                    OPT_PRINT_FUNCTION_CALLERS,
+                    OPT_PRINT_FUNCTION_ACCESSES,
                     OPT_PRINT_FUNCTION_INSTRUCTIONS,
                     OPT_PRINT_FUNCTION_VARIABLES,
                     OPT_PRINT_FUNCTION_STACK_FRAME,
                     OPT_PRINT_INLINED_FUNCTION_PARAMETERS,
                     OPT_PRINT_VARIABLE_LOCATIONS,
+                    OPT_PRINT_VARIABLE_ACCESSED_BY,
+                    OPT_PRINT_VARIABLE_VALUE,
+                    OPT_PRINT_STRUCT_CACHELINES,
+                    OPT_PRINT_FILE_SOURCE_LINES,
+                    OPT_PRINT_FUNCTION_SOURCE_LINES,
+                    OPT_PRINT_FUNCTION_SOURCE_TEXT,
                 ]),
         )
         .arg(
@@ -153,6 +308,29 @@ fn main() {
                 .help("Depth of inlined function calls to print (defaults to 1, 0 to disable)")
                 .value_name("DEPTH"),
         )
+        .arg(
+            clap::Arg::with_name(OPT_SOURCE_LINE_LIMIT)
+                .long(OPT_SOURCE_LINE_LIMIT)
+                .help("Number of source files and lines to print (defaults to 10)")
+                .value_name("COUNT"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_CALL_GRAPH_DEPTH)
+                .long(OPT_CALL_GRAPH_DEPTH)
+                .help("Depth of calls to follow from the matching functions (defaults to 1)")
+                .value_name("DEPTH"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_CACHELINE_SIZE)
+                .long(OPT_CACHELINE_SIZE)
+                .help("Size in bytes of cache lines (defaults to 64)")
+                .value_name("SIZE"),
+        )
+        .arg(
+            clap::Arg::with_name(OPT_SECTION_ADDRESSES)
+                .long(OPT_SECTION_ADDRESSES)
+                .help("Assign distinct addresses to the sections of relocatable objects"),
+        )
         .arg(
             clap::Arg::with_name(OPT_FILTER)
                 .short("f")
@@ -205,6 +383,22 @@ fn main() {
                 .require_delimiter(true)
                 .value_name("OLD>=<NEW"),
         )
+        .group(clap::ArgGroup::with_name(OPT_MODE).args(&[
+            OPT_DIFF,
+            OPT_SYMBOLIZE,
+            OPT_CALL_GRAPH,
+            OPT_REACH,
+            OPT_STACK_USAGE,
+            OPT_UNREFERENCED,
+            OPT_MEMBER_ACCESSES,
+            OPT_CORE,
+            OPT_SAVE_SNAPSHOT,
+            OPT_C_HEADER,
+            OPT_RUST_BINDINGS,
+            OPT_REORDER,
+            OPT_PADDING,
+            OPT_FALSE_SHARING,
+        ]))
         .after_help(concat!(
             "FILTERS:\n",
             "    function-inline=<yes|no>        Match function 'inline' value\n",
@@ -231,10 +425,58 @@ fn main() {
         1
     };
 
+    options.source_line_limit = if let Some(limit) = matches.value_of(OPT_SOURCE_LINE_LIMIT) {
+        match limit.parse::<usize>() {
+            Ok(limit) => limit,
+            Err(_) => {
+                clap::Error::with_description(
+                    &format!("invalid {} value: {}", OPT_SOURCE_LINE_LIMIT, limit),
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit();
+            }
+        }
+    } else {
+        10
+    };
+
+    options.call_graph_depth = if let Some(depth) = matches.value_of(OPT_CALL_GRAPH_DEPTH) {
+        match depth.parse::<usize>() {
+            Ok(depth) => depth,
+            Err(_) => {
+                clap::Error::with_description(
+                    &format!("invalid {} value: {}", OPT_CALL_GRAPH_DEPTH, depth),
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit();
+            }
+        }
+    } else {
+        1
+    };
+
+    options.cacheline_size = if let Some(size) = matches.value_of(OPT_CACHELINE_SIZE) {
+        match size.parse::<u64>() {
+            Ok(size) if size != 0 => size,
+            _ => {
+                clap::Error::with_description(
+                    &format!("invalid {} value: {}", OPT_CACHELINE_SIZE, size),
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit();
+            }
+        }
+    } else {
+        64
+    };
+
+    options.reorder_cachelines = matches.is_present(OPT_REORDER_CACHELINES);
+
     if let Some(value) = matches.value_of(OPT_OUTPUT) {
         match value {
             OPT_OUTPUT_TEXT => options.html = false,
             OPT_OUTPUT_HTML => options.html = true,
+            OPT_OUTPUT_JSON => options.json = true,
             _ => clap::Error::with_description(
                 &format!("invalid {} value: {}", OPT_OUTPUT, value),
                 clap::ErrorKind::InvalidValue,
@@ -245,6 +487,24 @@ fn main() {
         options.html = false;
     }
 
+    if options.json {
+        // Only the print, diff and symbolize modes have JSON output.
+        let mut modes = MODES
+            .iter()
+            .map(|&(name, _)| name)
+            .chain([OPT_REACH, OPT_CORE, OPT_SAVE_SNAPSHOT].iter().cloned());
+        if let Some(mode) = modes.find(|mode| matches.is_present(mode)) {
+            clap::Error::with_description(
+                &format!(
+                    "--{} {} is not supported with --{}",
+                    OPT_OUTPUT, OPT_OUTPUT_JSON, mode
+                ),
+                clap::ErrorKind::ArgumentConflict,
+            )
+            .exit();
+        }
+    }
+
     if let Some(values) = matches.values_of(OPT_CATEGORY) {
         for value in values {
             match value {
@@ -276,11 +536,20 @@ fn main() {
                     options.print_unit_address = true;
                     options.print_source = true;
                     options.print_function_calls = true;
+                    options.print_function_callers = true;
+                    options.print_function_accesses = true;
                     options.print_function_instructions = true;
                     options.print_function_variables = true;
                     options.print_function_stack_frame = true;
                     options.print_inlined_function_parameters = true;
                     options.print_variable_locations = true;
+                    options.print_variable_accessed_by = true;
+                    options.print_variable_value = true;
+                    options.print_struct_cachelines = true;
+                    options.print_file_source_lines = true;
+                    options.print_function_source_lines = true;
+                    // Source text is not included because it depends on the
+                    // local source files, so it must be requested explicitly.
                 }
                 OPT_PRINT_ADDRESS => {
                     options.print_file_address = true;
@@ -290,6 +559,8 @@ fn main() {
                 OPT_PRINT_FILE_ADDRESS => options.print_file_address = true,
                 OPT_PRINT_UNIT_ADDRESS => options.print_unit_address = true,
                 OPT_PRINT_FUNCTION_CALLS => options.print_function_calls = true,
+                OPT_PRINT_FUNCTION_CALLERS => options.print_function_callers = true,
+                OPT_PRINT_FUNCTION_ACCESSES => options.print_function_accesses = true,
                 OPT_PRINT_FUNCTION_INSTRUCTIONS => options.print_function_instructions = true,
                 OPT_PRINT_FUNCTION_VARIABLES => options.print_function_variables = true,
                 OPT_PRINT_FUNCTION_STACK_FRAME => options.print_function_stack_frame = true,
@@ -297,6 +568,12 @@ fn main() {
                     options.print_inlined_function_parameters = true
                 }
                 OPT_PRINT_VARIABLE_LOCATIONS => options.print_variable_locations = true,
+                OPT_PRINT_VARIABLE_ACCESSED_BY => options.print_variable_accessed_by = true,
+                OPT_PRINT_VARIABLE_VALUE => options.print_variable_value = true,
+                OPT_PRINT_STRUCT_CACHELINES => options.print_struct_cachelines = true,
+                OPT_PRINT_FILE_SOURCE_LINES => options.print_file_source_lines = true,
+                OPT_PRINT_FUNCTION_SOURCE_LINES => options.print_function_source_lines = true,
+                OPT_PRINT_FUNCTION_SOURCE_TEXT => options.print_function_source_text = true,
                 _ => clap::Error::with_description(
                     &format!("invalid {} value: {}", OPT_PRINT, value),
                     clap::ErrorKind::InvalidValue,
@@ -398,58 +675,170 @@ fn main() {
         options.prefix_map.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
     }
 
+    let parse_options = ddbug::ParseOptions {
+        section_addresses: matches.is_present(OPT_SECTION_ADDRESSES),
+    };
+
     if let Some(mut paths) = matches.values_of(OPT_DIFF) {
         let path_a = paths.next().unwrap();
         let path_b = paths.next().unwrap();
 
-        if let Err(e) = ddbug::File::parse(path_a, |file_a| {
-            if let Err(e) = ddbug::File::parse(path_b, |file_b| diff_file(file_a, file_b, &options))
-            {
-                error!("{}: {}", path_b, e);
-            }
+        if let Err(e) = ddbug::File::parse_with_options(path_a, &parse_options, |file_a| {
+            run(path_b, &parse_options, |file_b, w| {
+                diff_file(file_a, file_b, w, &options)
+            });
             Ok(())
         }) {
             error!("{}: {}", path_a, e);
         }
-    } else {
-        let path = matches.value_of(OPT_FILE).unwrap();
+        return;
+    }
 
-        if let Err(e) = ddbug::File::parse(path, |file| print_file(file, &options)) {
+    let path = matches.value_of(OPT_FILE).unwrap();
+    if matches.is_present(OPT_SYMBOLIZE) {
+        let addresses: Vec<_> = if let Some(values) = matches.values_of(OPT_SYMBOLIZE_ADDRESS) {
+            values.map(parse_address).collect()
+        } else {
+            let mut input = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+                error!("stdin: {}", e);
+                return;
+            }
+            input.split_whitespace().map(parse_address).collect()
+        };
+        run(path, &parse_options, |file, w| {
+            ddbug::symbolize(file, &addresses, w, &options)
+        });
+    } else if let Some(targets) = matches.values_of(OPT_REACH) {
+        let targets: Vec<_> = targets.collect();
+        let sources: Vec<_> = matches
+            .values_of(OPT_REACH_FROM)
+            .map(|values| values.collect())
+            .unwrap_or_default();
+        run(path, &parse_options, |file, w| {
+            ddbug::reach(file, &sources, &targets, w, &options)
+        });
+    } else if let Some(core_path) = matches.value_of(OPT_CORE) {
+        run(path, &parse_options, |file, w| {
+            core_file(file, core_path, w, &options)
+        });
+    } else if let Some(snapshot_path) = matches.value_of(OPT_SAVE_SNAPSHOT) {
+        if let Err(e) = ddbug::File::parse_with_options(path, &parse_options, |file| {
+            save_snapshot_file(file, snapshot_path)
+        }) {
             error!("{}: {}", path, e);
         }
+    } else if let Some(&(_, mode)) = MODES.iter().find(|(name, _)| matches.is_present(name)) {
+        run(path, &parse_options, |file, w| mode(file, w, &options));
+    } else {
+        run(path, &parse_options, |file, w| {
+            print_file(file, w, &options)
+        });
+    }
+}
+
+fn parse_address(value: &str) -> u64 {
+    let digits = if value.starts_with("0x") || value.starts_with("0X") {
+        &value[2..]
+    } else {
+        value
+    };
+    match u64::from_str_radix(digits, 16) {
+        Ok(address) => address,
+        Err(_) => clap::Error::with_description(
+            &format!("invalid {} value: {}", OPT_SYMBOLIZE_ADDRESS, value),
+            clap::ErrorKind::InvalidValue,
+        )
+        .exit(),
+    }
+}
+
+/// Parse the file at `path`, and write the output of `mode` to stdout.
+fn run<F>(path: &str, parse_options: &ddbug::ParseOptions, mode: F)
+where
+    F: FnOnce(&ddbug::File, &mut dyn Write) -> ddbug::Result<()>,
+{
+    if let Err(e) = ddbug::File::parse_with_options(path, parse_options, |file| {
+        let stdout = std::io::stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+        mode(file, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }) {
+        error!("{}: {}", path, e);
     }
 }
 
 fn diff_file(
     file_a: &ddbug::File,
     file_b: &ddbug::File,
+    w: &mut dyn Write,
     options: &ddbug::Options,
 ) -> ddbug::Result<()> {
-    format(options, |printer| {
-        if let Err(e) = ddbug::diff(printer, file_a, file_b, options) {
-            error!("{}", e);
-        }
-        Ok(())
+    if options.json {
+        return ddbug::diff_json(file_a, file_b, w, options);
+    }
+    format(w, options, |printer| {
+        ddbug::diff(printer, file_a, file_b, options)
     })
 }
 
-fn print_file(file: &ddbug::File, options: &ddbug::Options) -> ddbug::Result<()> {
-    format(options, |printer| ddbug::print(file, printer, options))
+fn core_file(
+    file: &ddbug::File,
+    core_path: &str,
+    w: &mut dyn Write,
+    options: &ddbug::Options,
+) -> ddbug::Result<()> {
+    let handle = match std::fs::File::open(core_path) {
+        Ok(handle) => handle,
+        Err(e) => {
+            return Err(format!("{}: open failed: {}", core_path, e).into());
+        }
+    };
+    let map = match unsafe { memmap::Mmap::map(&handle) } {
+        Ok(map) => map,
+        Err(e) => {
+            return Err(format!("{}: memmap failed: {}", core_path, e).into());
+        }
+    };
+    ddbug::core_dump(file, &map, w, options)
+}
+
+fn save_snapshot_file(file: &ddbug::File, snapshot_path: &str) -> ddbug::Result<()> {
+    let handle = match std::fs::File::create(snapshot_path) {
+        Ok(handle) => handle,
+        Err(e) => {
+            return Err(format!("{}: create failed: {}", snapshot_path, e).into());
+        }
+    };
+    let mut writer = BufWriter::new(handle);
+    ddbug::save_snapshot(file, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn print_file(
+    file: &ddbug::File,
+    w: &mut dyn Write,
+    options: &ddbug::Options,
+) -> ddbug::Result<()> {
+    if options.json {
+        return ddbug::print_json(file, w, options);
+    }
+    format(w, options, |printer| ddbug::print(file, printer, options))
 }
 
-fn format<F>(options: &ddbug::Options, f: F) -> ddbug::Result<()>
+fn format<F>(w: &mut dyn Write, options: &ddbug::Options, f: F) -> ddbug::Result<()>
 where
     F: FnOnce(&mut dyn ddbug::Printer) -> ddbug::Result<()>,
 {
-    let stdout = std::io::stdout();
-    let mut writer = BufWriter::new(stdout.lock());
     if options.html {
-        let mut printer = ddbug::HtmlPrinter::new(&mut writer, options);
+        let mut printer = ddbug::HtmlPrinter::new(w, options);
         printer.begin()?;
         f(&mut printer)?;
         printer.end()
     } else {
-        let mut printer = ddbug::TextPrinter::new(&mut writer, options);
+        let mut printer = ddbug::TextPrinter::new(w, options);
         f(&mut printer)
     }
 }
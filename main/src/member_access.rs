@@ -0,0 +1,377 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::ptr;
+
+use parser::{
+    File, FileHash, Function, FunctionOffset, InlinedFunction, LocalVariable, Member, Namespace,
+    Parameter, Range, Register, Type, TypeKind, TypeModifierKind, TypeOffset,
+};
+
+use crate::code::Code;
+use crate::filter;
+use crate::symbolize::function_name;
+use crate::xref;
+use crate::{Options, Result};
+
+/// An access to a struct member through a pointer by the machine code of a function.
+#[derive(Debug, Clone, Copy)]
+pub struct MemberAccess<'input> {
+    function: &'input Function<'input>,
+    ty: &'input Type<'input>,
+    member: Option<&'input Member<'input>>,
+    address: u64,
+    offset: u64,
+    write: bool,
+}
+
+impl<'input> MemberAccess<'input> {
+    /// The function containing the instruction.
+    #[inline]
+    pub fn function(&self) -> &'input Function<'input> {
+        self.function
+    }
+
+    /// The struct type that is accessed.
+    #[inline]
+    pub fn ty(&self) -> &'input Type<'input> {
+        self.ty
+    }
+
+    /// The member of the struct containing the accessed offset.
+    ///
+    /// Returns `None` if the offset is not within any member, such as for padding
+    /// or inherited members.
+    #[inline]
+    pub fn member(&self) -> Option<&'input Member<'input>> {
+        self.member
+    }
+
+    /// The address of the instruction.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The offset of the accessed address within the struct.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Whether the instruction writes to the accessed address.
+    ///
+    /// This is estimated from the instruction, and read-modify-write
+    /// instructions are counted as writes.
+    #[inline]
+    pub fn is_write(&self) -> bool {
+        self.write
+    }
+
+    /// The path of the accessed member within the struct, such as `.flags` or `.inner.x`.
+    pub fn path(&self, hash: &FileHash) -> String {
+        xref::type_member_path(hash, self.ty, self.offset)
+    }
+}
+
+/// The accesses to struct members by all functions in a file.
+///
+/// An access is a memory operand that is relative to a register, where the
+/// register holds a parameter or local variable that is a pointer to a struct.
+/// This is only a best effort: it relies on the register locations in the
+/// debuginfo, and doesn't track pointers that are copied to other registers.
+#[derive(Debug, Default)]
+pub struct MemberAccesses<'input> {
+    accesses: Vec<MemberAccess<'input>>,
+    by_function: HashMap<FunctionOffset, Vec<usize>>,
+    by_type: HashMap<TypeOffset, Vec<usize>>,
+}
+
+impl<'input> MemberAccesses<'input> {
+    /// Find the member accesses for the file of the given `FileHash`.
+    pub fn new(hash: &'input FileHash<'input>) -> Self {
+        let code = Code::new(hash.file);
+        MemberAccesses::with_code(hash, code.as_ref())
+    }
+
+    pub(crate) fn with_code(hash: &'input FileHash<'input>, code: Option<&Code>) -> Self {
+        let code = match code {
+            Some(code) => code,
+            None => return MemberAccesses::default(),
+        };
+        let mut accesses = Vec::new();
+        for unit in hash.file.units() {
+            for function in unit.functions() {
+                let range = match function.range() {
+                    Some(range) => range,
+                    None => continue,
+                };
+                let details = function.details(hash);
+                let mut pointers = Vec::new();
+                add_pointers(
+                    &mut pointers,
+                    hash,
+                    details.parameters(),
+                    details.variables(),
+                    details.inlined_functions(),
+                );
+                if pointers.is_empty() {
+                    continue;
+                }
+                for operand in code.register_offsets(range) {
+                    if operand.offset < 0 {
+                        continue;
+                    }
+                    let offset = operand.offset as u64;
+                    let pointer = pointers.iter().find(|(range, register, _, size)| {
+                        *register == operand.register
+                            && range.contains(operand.address)
+                            && offset < *size
+                    });
+                    if let Some(&(_, _, ty, _)) = pointer {
+                        accesses.push(MemberAccess {
+                            function,
+                            ty,
+                            member: containing_member(hash, ty, offset),
+                            address: operand.address,
+                            offset,
+                            write: operand.write,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut by_function = HashMap::new();
+        let mut by_type = HashMap::new();
+        for (index, access) in accesses.iter().enumerate() {
+            by_function
+                .entry(access.function.offset())
+                .or_insert_with(Vec::new)
+                .push(index);
+            by_type
+                .entry(access.ty.offset())
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+        MemberAccesses {
+            accesses,
+            by_function,
+            by_type,
+        }
+    }
+
+    /// All member accesses in the file.
+    #[inline]
+    pub fn list(&self) -> &[MemberAccess<'input>] {
+        &self.accesses
+    }
+
+    /// The member accesses made by the given function.
+    pub fn accesses<'a>(
+        &'a self,
+        function: &Function,
+    ) -> impl Iterator<Item = &'a MemberAccess<'input>> + 'a {
+        self.lookup(self.by_function.get(&function.offset()))
+    }
+
+    /// The accesses of members of the given struct type.
+    pub fn accessed_by<'a>(
+        &'a self,
+        ty: &Type,
+    ) -> impl Iterator<Item = &'a MemberAccess<'input>> + 'a {
+        self.lookup(self.by_type.get(&ty.offset()))
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        indices: Option<&'a Vec<usize>>,
+    ) -> impl Iterator<Item = &'a MemberAccess<'input>> + 'a {
+        indices
+            .into_iter()
+            .flatten()
+            .map(move |&index| &self.accesses[index])
+    }
+}
+
+// Add the registers that hold pointers to structs, along with the address
+// ranges for which they hold them, and the size of the struct.
+fn add_pointers<'input>(
+    pointers: &mut Vec<(Range, Register, &'input Type<'input>, u64)>,
+    hash: &'input FileHash<'input>,
+    parameters: &[Parameter<'input>],
+    variables: &[LocalVariable<'input>],
+    inlined_functions: &[InlinedFunction<'input>],
+) {
+    for parameter in parameters {
+        if let Some((ty, size)) = parameter.ty(hash).and_then(|ty| pointee_struct(hash, &ty)) {
+            for (range, register) in parameter.registers() {
+                pointers.push((range, register, ty, size));
+            }
+        }
+    }
+    for variable in variables {
+        if let Some((ty, size)) = variable.ty(hash).and_then(|ty| pointee_struct(hash, &ty)) {
+            for (range, register) in variable.registers() {
+                pointers.push((range, register, ty, size));
+            }
+        }
+    }
+    for inlined_function in inlined_functions {
+        add_pointers(
+            pointers,
+            hash,
+            inlined_function.parameters(),
+            inlined_function.variables(),
+            inlined_function.inlined_functions(),
+        );
+    }
+}
+
+// Return the struct type that a pointer or reference type points to, and its size.
+fn pointee_struct<'input>(
+    hash: &'input FileHash<'input>,
+    ty: &Type<'input>,
+) -> Option<(&'input Type<'input>, u64)> {
+    match ty.kind() {
+        TypeKind::Def(def) => pointee_struct(hash, &def.ty(hash)?),
+        TypeKind::Modifier(modifier) => match modifier.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => struct_type(hash, &modifier.ty(hash)?),
+            _ => pointee_struct(hash, &modifier.ty(hash)?),
+        },
+        _ => None,
+    }
+}
+
+fn struct_type<'input>(
+    hash: &'input FileHash<'input>,
+    ty: &Type<'input>,
+) -> Option<(&'input Type<'input>, u64)> {
+    match ty.kind() {
+        TypeKind::Def(def) => struct_type(hash, &def.ty(hash)?),
+        TypeKind::Modifier(modifier) => match modifier.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => None,
+            _ => struct_type(hash, &modifier.ty(hash)?),
+        },
+        TypeKind::Struct(struct_type) => {
+            let size = struct_type.byte_size().filter(|size| *size != 0)?;
+            let ty = hash.types.get(&ty.offset())?;
+            Some((ty, size))
+        }
+        _ => None,
+    }
+}
+
+fn containing_member<'input>(
+    hash: &FileHash,
+    ty: &'input Type<'input>,
+    offset: u64,
+) -> Option<&'input Member<'input>> {
+    let struct_type = match ty.kind() {
+        TypeKind::Struct(struct_type) => struct_type,
+        _ => return None,
+    };
+    let bit_offset = offset * 8;
+    struct_type.members().iter().find(|member| {
+        let bit_size = member.bit_size(hash).unwrap_or(0);
+        bit_offset >= member.bit_offset() && bit_offset < member.bit_offset() + bit_size
+    })
+}
+
+fn type_name(ty: &Type) -> String {
+    match ty.kind() {
+        TypeKind::Struct(struct_type) => Namespace::qualified_name(
+            struct_type.namespace(),
+            struct_type.name().unwrap_or("<anon>"),
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Print the struct members that are accessed by the functions that match the
+/// filter options.
+///
+/// For each struct, this prints the number of accesses of each member, how many
+/// of those are writes, and the number of functions that access it. Members
+/// that are never accessed are included, since they are candidates for moving
+/// out of hot cache lines.
+/// This is followed by the members accessed by each function.
+pub fn member_accesses(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let accesses = MemberAccesses::new(&hash);
+
+    let mut functions = Vec::new();
+    let mut types = BTreeMap::new();
+    for unit in filter::filter_units(file, options) {
+        for function in filter::filter_functions(unit, options) {
+            let mut paths = BTreeMap::new();
+            for access in accesses.accesses(function) {
+                let path = format!("{}{}", type_name(access.ty), access.path(&hash));
+                *paths.entry(path).or_insert(0) += 1;
+                types
+                    .entry((type_name(access.ty), access.ty.offset()))
+                    .or_insert_with(Vec::new)
+                    .push(access);
+            }
+            if !paths.is_empty() {
+                functions.push((function_name(function), paths));
+            }
+        }
+    }
+
+    for ((name, _), accesses) in types {
+        let ty = accesses[0].ty;
+        writeln!(w, "struct {}", name)?;
+        let members: &[Member] = match ty.kind() {
+            TypeKind::Struct(struct_type) => struct_type.members(),
+            _ => &[],
+        };
+        for member in members {
+            let member_accesses: Vec<_> = accesses
+                .iter()
+                .filter(|access| access.member.map_or(false, |x| ptr::eq(x, member)))
+                .cloned()
+                .collect();
+            print_member_count(w, member.name().unwrap_or("<anon>"), &member_accesses)?;
+        }
+        let other: Vec<_> = accesses
+            .iter()
+            .filter(|access| access.member.is_none())
+            .cloned()
+            .collect();
+        if !other.is_empty() {
+            print_member_count(w, "<other>", &other)?;
+        }
+    }
+    if !functions.is_empty() {
+        writeln!(w)?;
+    }
+    functions.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, paths) in functions {
+        writeln!(w, "fn {}", name)?;
+        for (path, count) in paths {
+            writeln!(w, "\t[{}]\t{}", count, path)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_member_count(w: &mut dyn Write, name: &str, accesses: &[&MemberAccess]) -> Result<()> {
+    let functions: HashSet<_> = accesses
+        .iter()
+        .map(|access| access.function.offset())
+        .collect();
+    let writes = accesses.iter().filter(|access| access.write).count();
+    writeln!(
+        w,
+        "\t[{}]\t{} ({} writes, {} functions)",
+        accesses.len(),
+        name,
+        writes,
+        functions.len()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::io::Write;
+
+use parser::{File, FileHash, LayoutItem, StructType, TypeKind, TypeOffset, UnionType};
+
+use crate::filter;
+use crate::print::member::format_bit;
+use crate::symbolize::tagged_name;
+use crate::{Options, Result};
+
+/// Print the structs and unions that match the filter options and contain padding.
+///
+/// Each type is printed with the offset and size of each hole, the total
+/// padding, and the ratio of padding to the size of the type. Identical types
+/// in multiple units are printed once, along with the number of units.
+/// Anonymous types can't be matched by name, so they are never merged.
+/// Types are sorted by their total padding.
+pub fn padding(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    // Anonymous types are also keyed by their offset.
+    let mut types: HashMap<(String, Option<TypeOffset>, u64, Vec<Hole>), usize> = HashMap::new();
+    for unit in filter::filter_units(file, options) {
+        for ty in filter::filter_types(unit, &hash, options, false) {
+            let anon = |name: Option<&str>| match name {
+                Some(_) => None,
+                None => Some(ty.offset()),
+            };
+            let entry = match *ty.kind() {
+                TypeKind::Struct(ref val) => match struct_holes(val, &hash) {
+                    Some((size, holes)) => (
+                        tagged_name("struct", val.namespace(), val.name()),
+                        anon(val.name()),
+                        size,
+                        holes,
+                    ),
+                    None => continue,
+                },
+                TypeKind::Union(ref val) => match union_holes(val, &hash) {
+                    Some((size, holes)) => (
+                        tagged_name("union", val.namespace(), val.name()),
+                        anon(val.name()),
+                        size,
+                        holes,
+                    ),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if !entry.3.is_empty() {
+                *types.entry(entry).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut types: Vec<_> = types
+        .into_iter()
+        .map(|((name, anon, size, holes), units)| {
+            let padding: u64 = holes.iter().map(|hole| hole.bit_size).sum();
+            (name, size, holes, units, padding, anon)
+        })
+        .collect();
+    types.sort_by(|a, b| {
+        // Compare ratios without division.
+        let ratio_a = u128::from(a.4) * u128::from(b.1);
+        let ratio_b = u128::from(b.4) * u128::from(a.1);
+        b.4.cmp(&a.4)
+            .then_with(|| ratio_b.cmp(&ratio_a))
+            .then_with(|| a.0.cmp(&b.0))
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.5.cmp(&b.5))
+    });
+
+    let mut total = 0;
+    for (name, size, holes, units, padding, _) in types {
+        total += padding;
+        writeln!(
+            w,
+            "{} [size {}, padding {}, {:.1}%, units {}]",
+            name,
+            size,
+            format_bit(padding),
+            padding as f64 * 100.0 / (size * 8) as f64,
+            units
+        )?;
+        for hole in holes {
+            writeln!(
+                w,
+                "\t{}[{}]\t{}",
+                format_bit(hole.bit_offset),
+                format_bit(hole.bit_size),
+                if hole.tail {
+                    "<tail padding>"
+                } else {
+                    "<padding>"
+                }
+            )?;
+        }
+    }
+    writeln!(w, "total [{}]", format_bit(total))?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Hole {
+    bit_offset: u64,
+    bit_size: u64,
+    tail: bool,
+}
+
+fn struct_holes(ty: &StructType, hash: &FileHash) -> Option<(u64, Vec<Hole>)> {
+    if ty.is_declaration() {
+        return None;
+    }
+    let size = ty.byte_size()?;
+    if size == 0 {
+        return None;
+    }
+    let holes = ty
+        .layout(hash)
+        .into_iter()
+        .filter_map(|layout| match layout.item {
+            LayoutItem::Padding => {
+                let bit_size = layout.bit_size.get()?;
+                Some(Hole {
+                    bit_offset: layout.bit_offset,
+                    bit_size,
+                    tail: layout.bit_offset + bit_size >= size * 8,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    Some((size, holes))
+}
+
+fn union_holes(ty: &UnionType, hash: &FileHash) -> Option<(u64, Vec<Hole>)> {
+    if ty.is_declaration() {
+        return None;
+    }
+    let size = ty.byte_size()?;
+    if size == 0 {
+        return None;
+    }
+    let mut bit_size = 0;
+    for member in ty.members() {
+        bit_size = cmp::max(bit_size, member.bit_offset() + member.bit_size(hash)?);
+    }
+    let mut holes = Vec::new();
+    if bit_size < size * 8 {
+        holes.push(Hole {
+            bit_offset: bit_size,
+            bit_size: size * 8 - bit_size,
+            tail: true,
+        });
+    }
+    Some((size, holes))
+}
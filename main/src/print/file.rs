@@ -1,8 +1,12 @@
 use parser::{File, FileHash, Unit};
 
+use crate::call_graph::CallGraph;
 use crate::code::Code;
 use crate::filter;
+use crate::print::source_line::LineSizes;
 use crate::print::{self, DiffState, MergeIterator, MergeResult, PrintState, Printer, SortList};
+use crate::snapshot;
+use crate::xref::VariableAccesses;
 use crate::{Options, Result};
 
 fn assign_ids(file: &File, options: &Options) {
@@ -112,7 +116,7 @@ fn assign_merged_ids_in_unit(
     id
 }
 
-fn merged_units<'a, 'input>(
+pub(crate) fn merged_units<'a, 'input>(
     hash_a: &FileHash,
     file_a: &'a File<'input>,
     hash_b: &FileHash,
@@ -133,7 +137,24 @@ pub fn print(file: &File, printer: &mut dyn Printer, options: &Options) -> Resul
     assign_ids(file, options);
     let hash = FileHash::new(file);
     let code = Code::new(file);
-    let mut state = PrintState::new(printer, &hash, code.as_ref(), options);
+    let call_graph = if options.print_function_callers {
+        Some(CallGraph::with_code(&hash, code.as_ref()))
+    } else {
+        None
+    };
+    let accesses = if options.print_function_accesses || options.print_variable_accessed_by {
+        Some(VariableAccesses::with_code(&hash, code.as_ref()))
+    } else {
+        None
+    };
+    let mut state = PrintState::new(
+        printer,
+        &hash,
+        code.as_ref(),
+        call_graph.as_ref(),
+        accesses.as_ref(),
+        options,
+    );
 
     if options.category_file {
         state.collapsed(
@@ -157,6 +178,18 @@ pub fn print(file: &File, printer: &mut dyn Printer, options: &Options) -> Resul
                 state.field_u64("var size", var_size)?;
                 state.field_u64("other size", other_size)?;
                 state.field_collapsed("sections", |state| state.list(&(), file.sections()))?;
+                if options.print_file_source_lines {
+                    let mut lines = LineSizes::new();
+                    for unit in filter::filter_units(file, options) {
+                        lines.add_unit(unit, state.hash());
+                    }
+                    state.field_collapsed("source files", |state| {
+                        print::source_line::print_files(state, &lines)
+                    })?;
+                    state.field_collapsed("source lines", |state| {
+                        print::source_line::print_list(state, &lines)
+                    })?;
+                }
                 Ok(())
             },
         )?;
@@ -172,10 +205,28 @@ pub fn diff(
     file_b: &File,
     options: &Options,
 ) -> Result<()> {
+    snapshot::check_diff_options(file_a, file_b, options)?;
     let hash_a = FileHash::new(file_a);
     let hash_b = FileHash::new(file_b);
     let code_a = Code::new(file_a);
     let code_b = Code::new(file_b);
+    let (call_graph_a, call_graph_b) = if options.print_function_callers {
+        (
+            Some(CallGraph::with_code(&hash_a, code_a.as_ref())),
+            Some(CallGraph::with_code(&hash_b, code_b.as_ref())),
+        )
+    } else {
+        (None, None)
+    };
+    let (accesses_a, accesses_b) =
+        if options.print_function_accesses || options.print_variable_accessed_by {
+            (
+                Some(VariableAccesses::with_code(&hash_a, code_a.as_ref())),
+                Some(VariableAccesses::with_code(&hash_b, code_b.as_ref())),
+            )
+        } else {
+            (None, None)
+        };
     assign_merged_ids(&hash_a, file_a, &hash_b, file_b, options);
 
     let mut state = DiffState::new(
@@ -184,6 +235,10 @@ pub fn diff(
         &hash_b,
         code_a.as_ref(),
         code_b.as_ref(),
+        call_graph_a.as_ref(),
+        call_graph_b.as_ref(),
+        accesses_a.as_ref(),
+        accesses_b.as_ref(),
         options,
     );
 
@@ -219,6 +274,22 @@ pub fn diff(
                 state.field_collapsed("sections", |state| {
                     state.list(&(), file_a.sections(), &(), file_b.sections())
                 })?;
+                if options.print_file_source_lines {
+                    let mut lines_a = LineSizes::new();
+                    for unit in filter::filter_units(file_a, options) {
+                        lines_a.add_unit(unit, state.hash_a());
+                    }
+                    let mut lines_b = LineSizes::new();
+                    for unit in filter::filter_units(file_b, options) {
+                        lines_b.add_unit(unit, state.hash_b());
+                    }
+                    state.field_collapsed("source files", |state| {
+                        print::source_line::diff_files(state, &lines_a, &lines_b)
+                    })?;
+                    state.field_collapsed("source lines", |state| {
+                        print::source_line::diff_list(state, &lines_a, &lines_b)
+                    })?;
+                }
                 Ok(())
             },
         )?;
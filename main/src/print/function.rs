@@ -1,12 +1,17 @@
 use std::cmp;
+use std::collections::HashMap;
+use std::fs;
 
 use parser::{
     Cfi, CfiDirective, FileHash, Function, FunctionDetails, InlinedFunction, LocalVariable,
-    Parameter, ParameterType, Range, Type, TypeOffset, Unit,
+    Parameter, ParameterType, Range, Type, TypeOffset, Unit, Variable,
 };
 
+use crate::call_graph::CallGraph;
 use crate::code::{Call, Code};
+use crate::print::source_line::LineSizes;
 use crate::print::{self, DiffList, DiffState, Print, PrintState, SortList, ValuePrinter};
+use crate::xref::VariableAccesses;
 use crate::{Options, Result, Sort};
 
 pub(crate) fn print_ref(f: &Function, w: &mut dyn ValuePrinter) -> Result<()> {
@@ -127,9 +132,24 @@ impl<'input> Print for Function<'input> {
                     let calls = calls(self, state.code);
                     state.field_collapsed("calls", |state| state.list(&(), &calls))?;
                 }
+                if state.options().print_function_callers {
+                    let callers = callers(self, state.hash(), state.call_graph);
+                    state.field_collapsed("callers", |state| state.list(&(), &callers))?;
+                }
+                if state.options().print_function_accesses {
+                    let accesses = accesses(self, state.hash(), state.accesses);
+                    state.field_collapsed("accesses", |state| state.list(&(), &accesses))?;
+                }
+                if state.options().print_function_source_lines {
+                    let mut lines = LineSizes::new();
+                    lines.add_function(self, unit, &details);
+                    state.field_collapsed("source lines", |state| {
+                        print::source_line::print_list(state, &lines)
+                    })?;
+                }
                 if state.options().print_function_instructions {
                     state.field_collapsed("instructions", |state| {
-                        print_instructions(state, self, &details)
+                        print_instructions(state, unit, self, &details)
                     })?;
                 }
                 Ok(())
@@ -227,13 +247,40 @@ impl<'input> Print for Function<'input> {
                         state.list(&(), &calls_a, &(), &calls_b)
                     })?;
                 }
+                if state.options().print_function_callers {
+                    let callers_a = callers(a, state.hash_a(), state.call_graph_a);
+                    let callers_b = callers(b, state.hash_b(), state.call_graph_b);
+                    state.field_collapsed("callers", |state| {
+                        state.list(&(), &callers_a, &(), &callers_b)
+                    })?;
+                }
+                if state.options().print_function_accesses {
+                    let accesses_a = accesses(a, state.hash_a(), state.accesses_a);
+                    let accesses_b = accesses(b, state.hash_b(), state.accesses_b);
+                    state.field_collapsed("accesses", |state| {
+                        state.list(&(), &accesses_a, &(), &accesses_b)
+                    })?;
+                }
+                if state.options().print_function_source_lines {
+                    let mut lines_a = LineSizes::new();
+                    lines_a.add_function(a, unit_a, &details_a);
+                    let mut lines_b = LineSizes::new();
+                    lines_b.add_function(b, unit_b, &details_b);
+                    state.field_collapsed("source lines", |state| {
+                        print::source_line::diff_list(state, &lines_a, &lines_b)
+                    })?;
+                }
                 if state.options().print_function_instructions {
                     state.field_collapsed("instructions", |state| {
                         // TODO: diff instructions
                         state.ignore_diff(true, |state| {
-                            state.block((a, &details_a), (b, &details_b), |state, (x, details)| {
-                                print_instructions(state, x, details)
-                            })
+                            state.block(
+                                (unit_a, a, &details_a),
+                                (unit_b, b, &details_b),
+                                |state, (unit, x, details)| {
+                                    print_instructions(state, unit, x, details)
+                                },
+                            )
                         })
                     })?;
                 }
@@ -311,6 +358,8 @@ fn print_call(
     }
     if let Some(function) = hash.functions_by_address.get(&call.to) {
         print_ref(function, w)?;
+    } else if let Some(import) = call.import {
+        write!(w, "{}", import)?;
     } else if options.ignore_function_address {
         // We haven't displayed an address yet, so we need to display something.
         write!(w, "0x{:x}", call.to)?;
@@ -318,7 +367,7 @@ fn print_call(
     Ok(())
 }
 
-impl Print for Call {
+impl<'code> Print for Call<'code> {
     type Arg = ();
 
     fn print(&self, state: &mut PrintState, _arg: &()) -> Result<()> {
@@ -332,7 +381,7 @@ impl Print for Call {
     }
 }
 
-impl DiffList for Call {
+impl<'code> DiffList for Call<'code> {
     fn step_cost(&self, _state: &DiffState, _arg: &()) -> usize {
         1
     }
@@ -355,7 +404,11 @@ impl DiffList for Call {
                     cost += 1;
                 }
             }
-            (None, None) => {}
+            (None, None) => {
+                if a.import != b.import {
+                    cost += 1;
+                }
+            }
             _ => {
                 cost += 1;
             }
@@ -364,15 +417,149 @@ impl DiffList for Call {
     }
 }
 
-fn calls(f: &Function, code: Option<&Code>) -> Vec<Call> {
+fn calls<'code>(f: &Function, code: Option<&Code<'code>>) -> Vec<Call<'code>> {
     if let (Some(code), Some(range)) = (code, f.range()) {
         return code.calls(range);
     }
     Vec::new()
 }
 
+/// A function that calls the function being printed.
+#[derive(Debug)]
+struct Caller<'a> {
+    function: &'a Function<'a>,
+    inlined: bool,
+}
+
+fn callers<'a>(
+    f: &Function,
+    hash: &FileHash,
+    call_graph: Option<&CallGraph<'a>>,
+) -> Vec<Caller<'a>> {
+    let mut callers: Vec<_> = match call_graph {
+        Some(call_graph) => call_graph
+            .callers(f)
+            .map(|edge| Caller {
+                function: edge.caller(),
+                inlined: edge.is_inlined(),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    callers.sort_by(|x, y| Function::cmp_id(hash, x.function, hash, y.function));
+    callers
+}
+
+fn print_caller(caller: &Caller, w: &mut dyn ValuePrinter) -> Result<()> {
+    print_ref(caller.function, w)?;
+    if caller.inlined {
+        write!(w, " (inlined)")?;
+    }
+    Ok(())
+}
+
+impl<'a> Print for Caller<'a> {
+    type Arg = ();
+
+    fn print(&self, state: &mut PrintState, _arg: &()) -> Result<()> {
+        state.line(|w, _hash| print_caller(self, w))
+    }
+
+    fn diff(state: &mut DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> Result<()> {
+        state.line(a, b, |w, _hash, x| print_caller(x, w))
+    }
+}
+
+impl<'a> DiffList for Caller<'a> {
+    fn step_cost(&self, _state: &DiffState, _arg: &()) -> usize {
+        1
+    }
+
+    fn diff_cost(state: &DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> usize {
+        let mut cost = 0;
+        if Function::cmp_id(state.hash_a(), a.function, state.hash_b(), b.function)
+            != cmp::Ordering::Equal
+        {
+            cost += 1;
+        }
+        if a.inlined != b.inlined {
+            cost += 1;
+        }
+        cost
+    }
+}
+
+/// A global variable that is accessed by the function being printed.
+#[derive(Debug)]
+struct Access<'a> {
+    variable: &'a Variable<'a>,
+    member: String,
+}
+
+fn accesses<'a>(
+    f: &Function,
+    hash: &FileHash,
+    accesses: Option<&VariableAccesses<'a>>,
+) -> Vec<Access<'a>> {
+    let mut accesses: Vec<_> = match accesses {
+        Some(accesses) => accesses
+            .accesses(f)
+            .map(|access| Access {
+                variable: access.variable(),
+                member: access.member(hash),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    accesses.sort_by(|x, y| {
+        Variable::cmp_id(hash, x.variable, hash, y.variable)
+            .then_with(|| x.variable.offset().cmp(&y.variable.offset()))
+            .then_with(|| x.member.cmp(&y.member))
+    });
+    accesses.dedup_by(|x, y| x.variable.offset() == y.variable.offset() && x.member == y.member);
+    accesses
+}
+
+fn print_access(access: &Access, w: &mut dyn ValuePrinter) -> Result<()> {
+    print::variable::print_ref(access.variable, w)?;
+    write!(w, "{}", access.member)?;
+    Ok(())
+}
+
+impl<'a> Print for Access<'a> {
+    type Arg = ();
+
+    fn print(&self, state: &mut PrintState, _arg: &()) -> Result<()> {
+        state.line(|w, _hash| print_access(self, w))
+    }
+
+    fn diff(state: &mut DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> Result<()> {
+        state.line(a, b, |w, _hash, x| print_access(x, w))
+    }
+}
+
+impl<'a> DiffList for Access<'a> {
+    fn step_cost(&self, _state: &DiffState, _arg: &()) -> usize {
+        1
+    }
+
+    fn diff_cost(state: &DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> usize {
+        let mut cost = 0;
+        if Variable::cmp_id(state.hash_a(), a.variable, state.hash_b(), b.variable)
+            != cmp::Ordering::Equal
+        {
+            cost += 1;
+        }
+        if a.member != b.member {
+            cost += 1;
+        }
+        cost
+    }
+}
+
 fn print_instructions(
     state: &mut PrintState,
+    unit: &Unit,
     f: &Function,
     details: &FunctionDetails,
 ) -> Result<()> {
@@ -384,7 +571,7 @@ fn print_instructions(
         Some(x) => x,
         None => return Ok(()),
     };
-    let disassembler = match code.disassembler() {
+    let disassembler = match code.disassembler(range) {
         Some(x) => x,
         None => return Ok(()),
     };
@@ -394,6 +581,12 @@ fn print_instructions(
     };
     let cfis = f.cfi(state.hash());
 
+    let mut inline_events = Vec::new();
+    add_inline_events(details.inlined_functions(), 0, range, &mut inline_events);
+    inline_events.sort_by(InlineEvent::cmp);
+    let mut inline_index = 0;
+    let mut source_lines = SourceLines::default();
+
     let mut insns = insns.iter();
     let mut cfis = cfis.iter();
 
@@ -406,11 +599,17 @@ fn print_instructions(
                     print_cfi(state, cfi, range)?;
                     cfi_next = cfis.next();
                 } else {
+                    let address = insn.address().get().unwrap_or(0);
+                    print_inline_events(state, unit, &inline_events, &mut inline_index, address)?;
+                    source_lines.print(state, unit, address)?;
                     insn.print(state, &disassembler, details, range)?;
                     insn_next = insns.next();
                 }
             }
             (&Some(ref insn), None) => {
+                let address = insn.address().get().unwrap_or(0);
+                print_inline_events(state, unit, &inline_events, &mut inline_index, address)?;
+                source_lines.print(state, unit, address)?;
                 insn.print(state, &disassembler, details, range)?;
                 insn_next = insns.next();
             }
@@ -421,9 +620,151 @@ fn print_instructions(
             (&None, None) => break,
         }
     }
+    print_inline_events(state, unit, &inline_events, &mut inline_index, range.end)?;
+    Ok(())
+}
+
+fn print_inline_events(
+    state: &mut PrintState,
+    unit: &Unit,
+    events: &[InlineEvent],
+    index: &mut usize,
+    address: u64,
+) -> Result<()> {
+    while let Some(event) = events.get(*index) {
+        if event.address > address {
+            break;
+        }
+        event.print(state, unit)?;
+        *index += 1;
+    }
     Ok(())
 }
 
+// The beginning or end of an address range of an inlined function.
+struct InlineEvent<'a, 'input> {
+    address: u64,
+    begin: bool,
+    depth: usize,
+    function: &'a InlinedFunction<'input>,
+}
+
+impl<'a, 'input> InlineEvent<'a, 'input> {
+    // Order by address, with inner ranges ending before outer ranges,
+    // and outer ranges beginning before inner ranges.
+    fn cmp(a: &Self, b: &Self) -> cmp::Ordering {
+        a.address
+            .cmp(&b.address)
+            .then_with(|| match (a.begin, b.begin) {
+                (false, false) => b.depth.cmp(&a.depth),
+                (true, true) => a.depth.cmp(&b.depth),
+                (false, true) => cmp::Ordering::Less,
+                (true, false) => cmp::Ordering::Greater,
+            })
+    }
+
+    fn print(&self, state: &mut PrintState, unit: &Unit) -> Result<()> {
+        state.line(|w, hash| {
+            write!(w, "{:3}   ", "")?;
+            for _ in 0..self.depth {
+                write!(w, "  ")?;
+            }
+            if self.begin {
+                write!(w, "inline begin: ")?;
+            } else {
+                write!(w, "inline end: ")?;
+            }
+            match self.function.abstract_origin(hash) {
+                Some(function) => print_ref(function, w)?,
+                None => write!(w, "<anon>")?,
+            }
+            if self.begin && self.function.call_source().is_some() {
+                write!(w, " at ")?;
+                print::source::print(self.function.call_source(), w, unit)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn add_inline_events<'a, 'input>(
+    inlined_functions: &'a [InlinedFunction<'input>],
+    depth: usize,
+    range: Range,
+    events: &mut Vec<InlineEvent<'a, 'input>>,
+) {
+    for function in inlined_functions {
+        for inline_range in function.ranges() {
+            if inline_range.begin < range.begin || inline_range.end > range.end {
+                continue;
+            }
+            events.push(InlineEvent {
+                address: inline_range.begin,
+                begin: true,
+                depth,
+                function,
+            });
+            events.push(InlineEvent {
+                address: inline_range.end,
+                begin: false,
+                depth,
+                function,
+            });
+        }
+        add_inline_events(function.inlined_functions(), depth + 1, range, events);
+    }
+}
+
+// Tracks line table transitions while printing instructions.
+#[derive(Default)]
+struct SourceLines {
+    prev: Option<(Option<String>, u32)>,
+    // Cache of source files read for printing source text.
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl SourceLines {
+    fn print(&mut self, state: &mut PrintState, unit: &Unit, address: u64) -> Result<()> {
+        let source = match unit.find_line(address) {
+            Some(line) => line.source(),
+            None => {
+                self.prev = None;
+                return Ok(());
+            }
+        };
+        let path = source.path(unit);
+        let line = source.line();
+        if self.prev.as_ref() == Some(&(path.clone(), line)) {
+            return Ok(());
+        }
+
+        let text = match path {
+            Some(ref path) if line != 0 && state.options().print_function_source_text => self
+                .files
+                .entry(path.clone())
+                .or_insert_with(|| {
+                    fs::read_to_string(path)
+                        .ok()
+                        .map(|text| text.lines().map(String::from).collect())
+                })
+                .as_ref()
+                .and_then(|lines| lines.get(line as usize - 1))
+                .map(|text| text.trim().to_string()),
+            _ => None,
+        };
+        state.line(|w, _hash| {
+            write!(w, "{:3}   ", "")?;
+            print::source::print(source, w, unit)?;
+            if let Some(ref text) = text {
+                write!(w, "\t{}", text)?;
+            }
+            Ok(())
+        })?;
+        self.prev = Some((path, line));
+        Ok(())
+    }
+}
+
 fn print_cfi(state: &mut PrintState, cfi: &Cfi, range: Range) -> Result<()> {
     state.line(|w, hash| {
         macro_rules! write_reg {
@@ -474,7 +815,10 @@ fn print_cfi(state: &mut PrintState, cfi: &Cfi, range: Range) -> Result<()> {
                 write!(w, ".cfi_def_cfa_register")?;
                 write_reg!(w, " ", r)?;
             }
-            CfiDirective::DefCfaOffset(o) => write!(w, ".cfi_def_cfa_offset 0x{:x}", o)?,
+            CfiDirective::DefCfaOffset(o) => {
+                write!(w, ".cfi_def_cfa_offset")?;
+                write_ofs!(w, " ", o)?;
+            }
             CfiDirective::Offset(r, o) => {
                 write!(w, ".cfi_offset")?;
                 write_reg!(w, " ", r)?;
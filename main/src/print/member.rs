@@ -283,7 +283,7 @@ impl<'input, 'member> DiffList for Layout<'input, 'member> {
     }
 }
 
-fn format_bit(val: u64) -> String {
+pub(crate) fn format_bit(val: u64) -> String {
     let byte = val / 8;
     let bit = val % 8;
     if bit == 0 {
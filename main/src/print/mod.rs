@@ -5,11 +5,14 @@ use std::usize;
 
 use parser::FileHash;
 
+use crate::call_graph::CallGraph;
 use crate::code::Code;
+use crate::xref::VariableAccesses;
 use crate::{Options, Result};
 
 mod text;
 pub use self::text::TextPrinter;
+pub(crate) use self::text::TextValuePrinter;
 
 mod html;
 pub use self::html::HtmlPrinter;
@@ -29,12 +32,14 @@ pub(crate) mod range;
 pub(crate) mod register;
 pub(crate) mod section;
 pub(crate) mod source;
+pub(crate) mod source_line;
 pub(crate) mod struct_type;
 pub(crate) mod symbol;
 pub(crate) mod type_def;
 pub(crate) mod types;
 pub(crate) mod union_type;
 pub(crate) mod unit;
+pub(crate) mod value;
 pub(crate) mod variable;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -100,6 +105,8 @@ pub(crate) struct PrintState<'a> {
     // The remaining fields contain information that is commonly needed in print methods.
     hash: &'a FileHash<'a>,
     code: Option<&'a Code<'a>>,
+    call_graph: Option<&'a CallGraph<'a>>,
+    accesses: Option<&'a VariableAccesses<'a>>,
     options: &'a Options<'a>,
 }
 
@@ -118,12 +125,16 @@ impl<'a> PrintState<'a> {
         printer: &'a mut dyn Printer,
         hash: &'a FileHash<'a>,
         code: Option<&'a Code<'a>>,
+        call_graph: Option<&'a CallGraph<'a>>,
+        accesses: Option<&'a VariableAccesses<'a>>,
         options: &'a Options<'a>,
     ) -> Self {
         PrintState {
             printer,
             hash,
             code,
+            call_graph,
+            accesses,
             options,
         }
     }
@@ -143,17 +154,20 @@ impl<'a> PrintState<'a> {
     {
         let hash = self.hash;
         let code = self.code;
+        let call_graph = self.call_graph;
+        let accesses = self.accesses;
         let options = self.options;
         let mut body_buf = Vec::new();
         self.printer.indent_body(&mut body_buf, &mut |printer| {
-            let mut state = PrintState::new(printer, hash, code, options);
+            let mut state = PrintState::new(printer, hash, code, call_graph, accesses, options);
             body(&mut state)?;
             Ok(())
         })?;
         if !body_buf.is_empty() {
             self.printer
                 .indent_header(collapsed, &*body_buf, &mut |printer| {
-                    let mut state = PrintState::new(printer, hash, code, options);
+                    let mut state =
+                        PrintState::new(printer, hash, code, call_graph, accesses, options);
                     header(&mut state)?;
                     Ok(())
                 })?;
@@ -292,18 +306,36 @@ pub(crate) struct DiffState<'a> {
     hash_b: &'a FileHash<'a>,
     code_a: Option<&'a Code<'a>>,
     code_b: Option<&'a Code<'a>>,
+    call_graph_a: Option<&'a CallGraph<'a>>,
+    call_graph_b: Option<&'a CallGraph<'a>>,
+    accesses_a: Option<&'a VariableAccesses<'a>>,
+    accesses_b: Option<&'a VariableAccesses<'a>>,
     options: &'a Options<'a>,
 }
 
 impl<'a> DiffState<'a> {
     #[inline]
     fn a(&mut self) -> PrintState {
-        PrintState::new(self.printer, self.hash_a, self.code_a, self.options)
+        PrintState::new(
+            self.printer,
+            self.hash_a,
+            self.code_a,
+            self.call_graph_a,
+            self.accesses_a,
+            self.options,
+        )
     }
 
     #[inline]
     fn b(&mut self) -> PrintState {
-        PrintState::new(self.printer, self.hash_b, self.code_b, self.options)
+        PrintState::new(
+            self.printer,
+            self.hash_b,
+            self.code_b,
+            self.call_graph_b,
+            self.accesses_b,
+            self.options,
+        )
     }
 
     #[inline]
@@ -327,6 +359,10 @@ impl<'a> DiffState<'a> {
         hash_b: &'a FileHash<'a>,
         code_a: Option<&'a Code<'a>>,
         code_b: Option<&'a Code<'a>>,
+        call_graph_a: Option<&'a CallGraph<'a>>,
+        call_graph_b: Option<&'a CallGraph<'a>>,
+        accesses_a: Option<&'a VariableAccesses<'a>>,
+        accesses_b: Option<&'a VariableAccesses<'a>>,
         options: &'a Options<'a>,
     ) -> Self {
         DiffState {
@@ -336,6 +372,10 @@ impl<'a> DiffState<'a> {
             hash_b,
             code_a,
             code_b,
+            call_graph_a,
+            call_graph_b,
+            accesses_a,
+            accesses_b,
             options,
         }
     }
@@ -350,11 +390,26 @@ impl<'a> DiffState<'a> {
         let hash_b = self.hash_b;
         let code_a = self.code_a;
         let code_b = self.code_b;
+        let call_graph_a = self.call_graph_a;
+        let call_graph_b = self.call_graph_b;
+        let accesses_a = self.accesses_a;
+        let accesses_b = self.accesses_b;
         let options = self.options;
         let mut buf = Vec::new();
         let mut diff = false;
         self.printer.buffer(&mut buf, &mut |printer| {
-            let mut state = DiffState::new(printer, hash_a, hash_b, code_a, code_b, options);
+            let mut state = DiffState::new(
+                printer,
+                hash_a,
+                hash_b,
+                code_a,
+                code_b,
+                call_graph_a,
+                call_graph_b,
+                accesses_a,
+                accesses_b,
+                options,
+            );
             f(&mut state)?;
             diff = state.diff;
             Ok(())
@@ -398,13 +453,28 @@ impl<'a> DiffState<'a> {
         let hash_b = self.hash_b;
         let code_a = self.code_a;
         let code_b = self.code_b;
+        let call_graph_a = self.call_graph_a;
+        let call_graph_b = self.call_graph_b;
+        let accesses_a = self.accesses_a;
+        let accesses_b = self.accesses_b;
         let options = self.options;
 
         let mut body_buf = Vec::new();
         let mut diff = false;
         self.printer.indent_body(&mut body_buf, &mut |printer| {
             printer.prefix(DiffPrefix::Equal);
-            let mut state = DiffState::new(printer, hash_a, hash_b, code_a, code_b, options);
+            let mut state = DiffState::new(
+                printer,
+                hash_a,
+                hash_b,
+                code_a,
+                code_b,
+                call_graph_a,
+                call_graph_b,
+                accesses_a,
+                accesses_b,
+                options,
+            );
             body(&mut state)?;
             if state.diff {
                 diff = true;
@@ -420,8 +490,18 @@ impl<'a> DiffState<'a> {
                     } else {
                         printer.prefix(DiffPrefix::Equal);
                     }
-                    let mut state =
-                        DiffState::new(printer, hash_a, hash_b, code_a, code_b, options);
+                    let mut state = DiffState::new(
+                        printer,
+                        hash_a,
+                        hash_b,
+                        code_a,
+                        code_b,
+                        call_graph_a,
+                        call_graph_b,
+                        accesses_a,
+                        accesses_b,
+                        options,
+                    );
                     header(&mut state)?;
                     if state.diff {
                         diff = true;
@@ -510,10 +590,25 @@ impl<'a> DiffState<'a> {
         let hash_b = self.hash_b;
         let code_a = self.code_a;
         let code_b = self.code_b;
+        let call_graph_a = self.call_graph_a;
+        let call_graph_b = self.call_graph_b;
+        let accesses_a = self.accesses_a;
+        let accesses_b = self.accesses_b;
         let options = self.options;
         let mut buf = Vec::new();
         self.printer.buffer(&mut buf, &mut |printer| {
-            let mut state = DiffState::new(printer, hash_a, hash_b, code_a, code_b, options);
+            let mut state = DiffState::new(
+                printer,
+                hash_a,
+                hash_b,
+                code_a,
+                code_b,
+                call_graph_a,
+                call_graph_b,
+                accesses_a,
+                accesses_b,
+                options,
+            );
             state
                 .a()
                 .prefix(DiffPrefix::Delete, &mut |state| f(state, arg_a))?;
@@ -0,0 +1,277 @@
+use std::cmp;
+use std::collections::BTreeMap;
+
+use parser::{FileHash, Function, FunctionDetails, Range, Source, Unit};
+
+use crate::print::{DiffState, MergeIterator, MergeResult, PrintState, ValuePrinter};
+use crate::Result;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SourceLine {
+    path: Option<String>,
+    line: u32,
+}
+
+impl SourceLine {
+    fn new(source: &Source, unit: &Unit) -> Self {
+        SourceLine {
+            path: source.path(unit),
+            line: source.line(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct LineKey {
+    source: SourceLine,
+    // The location within the containing function that the code was inlined at.
+    call_source: Option<SourceLine>,
+}
+
+/// The number of bytes of code attributed to each source line.
+#[derive(Debug, Default)]
+pub(crate) struct LineSizes {
+    sizes: BTreeMap<LineKey, u64>,
+}
+
+impl LineSizes {
+    pub fn new() -> Self {
+        LineSizes::default()
+    }
+
+    /// Attribute the code for a function using the line table of its unit.
+    ///
+    /// This includes all of the address ranges of the function, such as cold
+    /// parts that have been split from the main body.
+    pub fn add_function(&mut self, f: &Function, unit: &Unit, details: &FunctionDetails) {
+        for range in f.ranges() {
+            self.add_range(*range, unit, details);
+        }
+    }
+
+    fn add_range(&mut self, range: Range, unit: &Unit, details: &FunctionDetails) {
+        let lines = unit.lines();
+        let start = match lines.binary_search_by(|x| x.range().begin.cmp(&range.begin)) {
+            Ok(index) => index,
+            Err(index) => index.saturating_sub(1),
+        };
+        for line in &lines[start..] {
+            if line.range().begin >= range.end {
+                break;
+            }
+            let begin = cmp::max(line.range().begin, range.begin);
+            let end = cmp::min(line.range().end, range.end);
+            if begin >= end {
+                continue;
+            }
+            let call_source = details
+                .inlined_functions()
+                .iter()
+                .find(|x| x.ranges().iter().any(|r| r.contains(begin)))
+                .map(|x| SourceLine::new(x.call_source(), unit));
+            let key = LineKey {
+                source: SourceLine::new(line.source(), unit),
+                call_source,
+            };
+            *self.sizes.entry(key).or_insert(0) += end - begin;
+        }
+    }
+
+    /// Attribute the code for all functions in a unit.
+    pub fn add_unit(&mut self, unit: &Unit, hash: &FileHash) {
+        for function in unit.functions() {
+            if !function.ranges().is_empty() {
+                let details = function.details(hash);
+                self.add_function(function, unit, &details);
+            }
+        }
+    }
+
+    /// The total size of all lines.
+    fn total(&self) -> u64 {
+        self.sizes.values().sum()
+    }
+
+    /// Group the lines by source file.
+    fn files(&self) -> BTreeMap<SourceLine, LineSizes> {
+        let mut files = BTreeMap::new();
+        for (key, size) in &self.sizes {
+            let file = SourceLine {
+                path: key.source.path.clone(),
+                line: 0,
+            };
+            files
+                .entry(file)
+                .or_insert_with(LineSizes::new)
+                .sizes
+                .insert(key.clone(), *size);
+        }
+        files
+    }
+}
+
+fn print_source_line(source: &SourceLine, w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(
+        w,
+        "{}",
+        source
+            .path
+            .as_ref()
+            .map(String::as_str)
+            .unwrap_or("<unknown>")
+    )?;
+    if source.line != 0 {
+        write!(w, ":{}", source.line)?;
+    }
+    Ok(())
+}
+
+fn print_size(size: u64, base: Option<u64>, w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(w, "[{}", size)?;
+    if let Some(base) = base {
+        if size != base {
+            write!(w, " ({:+})", size as i64 - base as i64)?;
+        }
+    }
+    write!(w, "]\t")?;
+    Ok(())
+}
+
+fn print_line_size(
+    key: &LineKey,
+    size: u64,
+    base: Option<u64>,
+    w: &mut dyn ValuePrinter,
+) -> Result<()> {
+    print_size(size, base, w)?;
+    print_source_line(&key.source, w)?;
+    if let Some(ref call_source) = key.call_source {
+        write!(w, " (inlined at ")?;
+        print_source_line(call_source, w)?;
+        write!(w, ")")?;
+    }
+    Ok(())
+}
+
+fn print_file_size(
+    file: &SourceLine,
+    size: u64,
+    base: Option<u64>,
+    w: &mut dyn ValuePrinter,
+) -> Result<()> {
+    print_size(size, base, w)?;
+    print_source_line(file, w)
+}
+
+/// Print the source files with the largest sizes, and the largest lines
+/// within each file.
+pub(crate) fn print_files(state: &mut PrintState, sizes: &LineSizes) -> Result<()> {
+    let files = sizes.files();
+    let mut list: Vec<_> = files
+        .iter()
+        .map(|(file, lines)| (file, lines, lines.total()))
+        .collect();
+    list.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+    for (file, lines, size) in list.into_iter().take(state.options().source_line_limit) {
+        state.collapsed(
+            |state| state.line(|w, _hash| print_file_size(file, size, None, w)),
+            |state| print_list(state, lines),
+        )?;
+    }
+    Ok(())
+}
+
+/// Print the source files with the largest changes in size, and the lines
+/// with the largest changes within each file.
+pub(crate) fn diff_files(state: &mut DiffState, a: &LineSizes, b: &LineSizes) -> Result<()> {
+    let files_a = a.files();
+    let files_b = b.files();
+    let empty = LineSizes::new();
+    let mut list = Vec::new();
+    for item in MergeIterator::new(files_a.iter(), files_b.iter(), |a, b| a.0.cmp(b.0)) {
+        match item {
+            MergeResult::Both((file, lines_a), (_, lines_b)) => {
+                if lines_a.sizes != lines_b.sizes {
+                    list.push((file, lines_a, lines_b));
+                }
+            }
+            MergeResult::Left((file, lines_a)) => list.push((file, lines_a, &empty)),
+            MergeResult::Right((file, lines_b)) => list.push((file, &empty, lines_b)),
+        }
+    }
+    let delta = |lines_a: &LineSizes, lines_b: &LineSizes| {
+        (lines_b.total() as i64 - lines_a.total() as i64).abs()
+    };
+    list.sort_by(|x, y| {
+        delta(y.1, y.2)
+            .cmp(&delta(x.1, x.2))
+            .then_with(|| x.0.cmp(y.0))
+    });
+    for (file, lines_a, lines_b) in list.into_iter().take(state.options().source_line_limit) {
+        let size_a = if lines_a.sizes.is_empty() {
+            None
+        } else {
+            Some(lines_a.total())
+        };
+        let size_b = if lines_b.sizes.is_empty() {
+            None
+        } else {
+            Some(lines_b.total())
+        };
+        state.collapsed(
+            |state| {
+                state.line(size_a, size_b, |w, _hash, size| {
+                    if let Some(size) = size {
+                        print_file_size(file, size, size_a, w)?;
+                    }
+                    Ok(())
+                })
+            },
+            |state| diff_list(state, lines_a, lines_b),
+        )?;
+    }
+    Ok(())
+}
+
+/// Print the source lines with the largest sizes.
+pub(crate) fn print_list(state: &mut PrintState, sizes: &LineSizes) -> Result<()> {
+    let mut list: Vec<_> = sizes.sizes.iter().collect();
+    list.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    for (key, size) in list.into_iter().take(state.options().source_line_limit) {
+        state.line(|w, _hash| print_line_size(key, *size, None, w))?;
+    }
+    Ok(())
+}
+
+/// Print the source lines with the largest changes in size.
+pub(crate) fn diff_list(state: &mut DiffState, a: &LineSizes, b: &LineSizes) -> Result<()> {
+    let mut list = Vec::new();
+    for item in MergeIterator::new(a.sizes.iter(), b.sizes.iter(), |a, b| a.0.cmp(b.0)) {
+        match item {
+            MergeResult::Both((key, size_a), (_, size_b)) => {
+                if size_a != size_b {
+                    list.push((key, Some(*size_a), Some(*size_b)));
+                }
+            }
+            MergeResult::Left((key, size_a)) => list.push((key, Some(*size_a), None)),
+            MergeResult::Right((key, size_b)) => list.push((key, None, Some(*size_b))),
+        }
+    }
+    let delta = |size_a: Option<u64>, size_b: Option<u64>| {
+        (size_b.unwrap_or(0) as i64 - size_a.unwrap_or(0) as i64).abs()
+    };
+    list.sort_by(|x, y| {
+        delta(y.1, y.2)
+            .cmp(&delta(x.1, x.2))
+            .then_with(|| x.0.cmp(y.0))
+    });
+    for (key, size_a, size_b) in list.into_iter().take(state.options().source_line_limit) {
+        state.line(size_a, size_b, |w, _hash, size| {
+            if let Some(size) = size {
+                print_line_size(key, size, size_a, w)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
@@ -1,7 +1,7 @@
-use parser::{FileHash, StructType, Unit};
+use parser::{FileHash, Layout, LayoutItem, StructType, Unit};
 
-use crate::print::{self, DiffState, PrintState, ValuePrinter};
-use crate::Result;
+use crate::print::{self, DiffState, Print, PrintState, ValuePrinter};
+use crate::{Options, Result};
 
 fn print_name(ty: &StructType, w: &mut dyn ValuePrinter) -> Result<()> {
     write!(w, "struct ")?;
@@ -26,7 +26,14 @@ pub(crate) fn print(ty: &StructType, state: &mut PrintState, unit: &Unit, id: us
             state.field("declaration", |w, state| print_declaration(ty, w, state))?;
             state.field("size", |w, state| print_byte_size(ty, w, state))?;
             print::inherit::print_list(state, ty.inherits())?;
-            state.field_expanded("members", |state| print_members(ty, state, unit))
+            if let Some(summary) = cacheline_summary(ty, state.hash(), state.options()) {
+                print_cacheline_summary(&summary, state)?;
+                state.field_expanded("members", |state| {
+                    print_cacheline_members(ty, state, unit, summary.cacheline_size)
+                })
+            } else {
+                state.field_expanded("members", |state| print_members(ty, state, unit))
+            }
         },
     )?;
     state.line_break()?;
@@ -58,6 +65,7 @@ pub(crate) fn diff(
             })?;
             state.field("size", a, b, |w, state, x| print_byte_size(x, w, state))?;
             print::inherit::diff_list(state, a.inherits(), b.inherits())?;
+            diff_cacheline_summary(state, a, b)?;
             state.field_expanded("members", |state| diff_members(state, unit_a, a, unit_b, b))
         },
     )?;
@@ -90,6 +98,150 @@ pub(crate) fn print_members(ty: &StructType, state: &mut PrintState, unit: &Unit
     state.list(unit, &layout)
 }
 
+/// Print the members with markers at cache line boundaries.
+///
+/// Boundaries that are inside an item are marked after the item.
+fn print_cacheline_members(
+    ty: &StructType,
+    state: &mut PrintState,
+    unit: &Unit,
+    cacheline_size: u64,
+) -> Result<()> {
+    let layout = ty.layout(state.hash());
+    let mut cacheline = 1;
+    for item in &layout {
+        while cacheline * cacheline_size * 8 <= item.bit_offset {
+            print_cacheline_boundary(state, cacheline, cacheline_size, None)?;
+            cacheline += 1;
+        }
+        item.print(state, unit)?;
+        if let Some(bit_size) = item.bit_size.get() {
+            while cacheline * cacheline_size * 8 < item.bit_offset + bit_size {
+                print_cacheline_boundary(state, cacheline, cacheline_size, Some(item))?;
+                cacheline += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_cacheline_boundary(
+    state: &mut PrintState,
+    cacheline: u64,
+    cacheline_size: u64,
+    straddle: Option<&Layout>,
+) -> Result<()> {
+    state.line(|w, _hash| {
+        write!(
+            w,
+            "--- cacheline {} boundary ({} bytes) ---",
+            cacheline,
+            cacheline * cacheline_size
+        )?;
+        if let Some(straddle) = straddle {
+            match straddle.item {
+                LayoutItem::Padding => {}
+                LayoutItem::Member(member) => {
+                    write!(w, " <straddled by {}>", member.name().unwrap_or("<anon>"))?
+                }
+                LayoutItem::Inherit(..) => write!(w, " <straddled by inherit>")?,
+                LayoutItem::VariantPart(..) => write!(w, " <straddled by variant part>")?,
+            }
+        }
+        Ok(())
+    })
+}
+
+struct CachelineSummary {
+    cacheline_size: u64,
+    holes: u64,
+    wasted_bits: u64,
+    cachelines: u64,
+    last_cacheline: u64,
+}
+
+/// Summarize the padding and cache line usage of a struct.
+///
+/// Returns `None` if cache lines are not being printed or the size is unknown.
+fn cacheline_summary(
+    ty: &StructType,
+    hash: &FileHash,
+    options: &Options,
+) -> Option<CachelineSummary> {
+    let cacheline_size = options.cacheline_size;
+    if !options.print_struct_cachelines || cacheline_size == 0 {
+        return None;
+    }
+    let byte_size = ty.byte_size()?;
+    let mut holes = 0;
+    let mut wasted_bits = 0;
+    for item in ty.layout(hash) {
+        if let LayoutItem::Padding = item.item {
+            let bit_size = item.bit_size.get().unwrap_or(0);
+            if item.bit_offset + bit_size < byte_size * 8 {
+                holes += 1;
+            }
+            wasted_bits += bit_size;
+        }
+    }
+    let cachelines = (byte_size + cacheline_size - 1) / cacheline_size;
+    let last_cacheline = if cachelines == 0 {
+        0
+    } else {
+        byte_size - (cachelines - 1) * cacheline_size
+    };
+    Some(CachelineSummary {
+        cacheline_size,
+        holes,
+        wasted_bits,
+        cachelines,
+        last_cacheline,
+    })
+}
+
+fn print_cacheline_summary(summary: &CachelineSummary, state: &mut PrintState) -> Result<()> {
+    state.field_u64("holes", summary.holes)?;
+    state.field("wasted", |w, _hash| print_wasted(summary, w))?;
+    state.field_u64("cachelines", summary.cachelines)?;
+    state.field("last cacheline", |w, _hash| {
+        print_last_cacheline(summary, w)
+    })
+}
+
+/// Diff the cache line summaries of two structs.
+///
+/// The boundary markers are not diffed, because they would need to be aligned
+/// with the diff of the members, so they are only shown when printing.
+fn diff_cacheline_summary(state: &mut DiffState, a: &StructType, b: &StructType) -> Result<()> {
+    let summary_a = cacheline_summary(a, state.hash_a(), state.options());
+    let summary_b = cacheline_summary(b, state.hash_b(), state.options());
+    if let (Some(summary_a), Some(summary_b)) = (summary_a, summary_b) {
+        state.field_u64("holes", summary_a.holes, summary_b.holes)?;
+        state.field("wasted", &summary_a, &summary_b, |w, _hash, x| {
+            print_wasted(x, w)
+        })?;
+        state.field_u64("cachelines", summary_a.cachelines, summary_b.cachelines)?;
+        state.field("last cacheline", &summary_a, &summary_b, |w, _hash, x| {
+            print_last_cacheline(x, w)
+        })?;
+    }
+    Ok(())
+}
+
+fn print_wasted(summary: &CachelineSummary, w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(w, "{}", print::member::format_bit(summary.wasted_bits))?;
+    Ok(())
+}
+
+fn print_last_cacheline(summary: &CachelineSummary, w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(
+        w,
+        "{}/{} bytes",
+        summary.last_cacheline, summary.cacheline_size
+    )?;
+    Ok(())
+}
+
 pub(crate) fn diff_members(
     state: &mut DiffState,
     unit_a: &Unit,
@@ -135,8 +135,15 @@ impl<'w> Printer for TextPrinter<'w> {
     }
 }
 
-struct TextValuePrinter<'w> {
-    w: &'w mut Vec<u8>,
+/// Print values as plain text, without links.
+pub(crate) struct TextValuePrinter<'w> {
+    w: &'w mut dyn Write,
+}
+
+impl<'w> TextValuePrinter<'w> {
+    pub(crate) fn new(w: &'w mut dyn Write) -> Self {
+        TextValuePrinter { w }
+    }
 }
 
 impl<'w> Write for TextValuePrinter<'w> {
@@ -0,0 +1,400 @@
+use parser::{
+    BaseType, BaseTypeEncoding, Endianity, File, FileHash, Relocation, Type, TypeKind,
+    TypeModifierKind, Variable,
+};
+
+use crate::print::{self, ValuePrinter};
+use crate::Result;
+
+// Limit the output for large arrays.
+const MAX_ELEMENTS: u64 = 64;
+const MAX_STRING: u64 = 256;
+
+// Sections that are zero initialized, and so have no data in the file.
+const ZERO_SECTIONS: &[&str] = &[".bss", ".sbss", ".tbss", "__bss", "__common"];
+
+/// Print the initial value of a variable, decoded using its type.
+///
+/// Nothing is printed if the variable's data can't be found in the file.
+pub(crate) fn print(v: &Variable, w: &mut dyn ValuePrinter, hash: &FileHash) -> Result<()> {
+    let (address, size, ty) = match (v.address(), v.byte_size(hash), v.ty(hash)) {
+        (Some(address), Some(size), Some(ty)) => (address, size, ty),
+        _ => return Ok(()),
+    };
+    let memory = match Memory::new(hash.file, address, size) {
+        Some(memory) => memory,
+        None => return Ok(()),
+    };
+    print_value(&memory, 0, &ty, w, hash)
+}
+
+/// Print a value that was read from the memory of a running process, decoded using its type.
+///
+/// `bias` is the difference between addresses in the process and addresses in the file.
+/// It is used to resolve pointers to functions and variables.
+pub(crate) fn print_bytes(
+    bytes: &[u8],
+    bias: u64,
+    ty: &Type,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    let memory = Memory {
+        file: hash.file,
+        address: 0,
+        bytes: Some(bytes),
+        bias: Some(bias),
+    };
+    print_value(&memory, 0, ty, w, hash)
+}
+
+// The bytes of a variable.
+struct Memory<'a, 'input> {
+    file: &'a File<'input>,
+    address: u64,
+    // `None` if the variable is zero initialized.
+    bytes: Option<&'a [u8]>,
+    // `None` if the bytes are from the file, and so may need relocation.
+    bias: Option<u64>,
+}
+
+impl<'a, 'input> Memory<'a, 'input> {
+    fn new(file: &'a File<'input>, address: u64, size: u64) -> Option<Self> {
+        for segment in file.segments() {
+            if address >= segment.address
+                && address + size <= segment.address + segment.bytes.len() as u64
+            {
+                let begin = (address - segment.address) as usize;
+                return Some(Memory {
+                    file,
+                    address,
+                    bytes: Some(&segment.bytes[begin..][..size as usize]),
+                    bias: None,
+                });
+            }
+        }
+        for section in file.sections() {
+            if let (Some(name), Some(range)) = (section.name(), section.address()) {
+                if ZERO_SECTIONS.contains(&name) && range.contains(address) {
+                    return Some(Memory {
+                        file,
+                        address,
+                        bytes: None,
+                        bias: None,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn read(&self, offset: u64, size: u64, big_endian: bool) -> Option<u64> {
+        if size == 0 || size > 8 {
+            return None;
+        }
+        let bytes = match self.bytes {
+            Some(bytes) => bytes.get(offset as usize..(offset + size) as usize)?,
+            None => return Some(0),
+        };
+        let fold = |value, byte: &u8| (value << 8) | u64::from(*byte);
+        if big_endian {
+            Some(bytes.iter().fold(0, fold))
+        } else {
+            Some(bytes.iter().rev().fold(0, fold))
+        }
+    }
+
+    // The relocation for the value at the given offset.
+    fn relocation(&self, offset: u64) -> Option<&'a Relocation<'input>> {
+        if self.bias.is_some() {
+            return None;
+        }
+        let address = self.address + offset;
+        let find = |relocations: &'a [Relocation<'input>]| {
+            relocations
+                .binary_search_by_key(&address, |relocation| relocation.address())
+                .ok()
+                .map(|index| &relocations[index])
+        };
+        find(self.file.relocations()).or_else(|| find(self.file.dynamic_relocations()))
+    }
+
+    fn big_endian(&self) -> bool {
+        !self.file.is_little_endian()
+    }
+}
+
+fn print_value(
+    memory: &Memory,
+    offset: u64,
+    ty: &Type,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    match ty.kind() {
+        TypeKind::Base(base) => print_base(memory, offset, base, w),
+        TypeKind::Def(def) => match def.ty(hash) {
+            Some(ty) => print_value(memory, offset, &ty, w, hash),
+            None => print_unknown(w),
+        },
+        TypeKind::Enumeration(enumeration) => {
+            let size = enumeration.byte_size(hash).unwrap_or(0);
+            let value = match memory.read(offset, size, memory.big_endian()) {
+                Some(value) => value,
+                None => return print_unknown(w),
+            };
+            let mask = mask(size * 8);
+            let name = enumeration
+                .enumerators(hash)
+                .into_iter()
+                .find(|enumerator| enumerator.value().map(|x| x as u64 & mask) == Some(value))
+                .and_then(|enumerator| enumerator.name().map(String::from));
+            match name {
+                Some(name) => write!(w, "{}", name)?,
+                None => write!(w, "{}", value)?,
+            }
+            Ok(())
+        }
+        TypeKind::Modifier(modifier) => match modifier.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => {
+                let size = modifier.byte_size(hash).unwrap_or(0);
+                print_pointer(memory, offset, size, w, hash)
+            }
+            _ => match modifier.ty(hash) {
+                Some(ty) => print_value(memory, offset, &ty, w, hash),
+                None => print_unknown(w),
+            },
+        },
+        TypeKind::Array(array) => {
+            let (ty, size, count) = match (array.element_type(hash), array.count(hash)) {
+                (Some(ty), Some(count)) => match ty.byte_size(hash) {
+                    Some(size) if size != 0 => (ty, size, count),
+                    _ => return print_unknown(w),
+                },
+                _ => return print_unknown(w),
+            };
+            if let TypeKind::Base(base) = ty.kind() {
+                if size == 1 && is_char(base) {
+                    return print_string(memory, offset, count, w);
+                }
+            }
+            write!(w, "[")?;
+            for i in 0..count {
+                if i != 0 {
+                    write!(w, ", ")?;
+                }
+                if i == MAX_ELEMENTS {
+                    write!(w, "...")?;
+                    break;
+                }
+                print_value(memory, offset + i * size, &ty, w, hash)?;
+            }
+            write!(w, "]")?;
+            Ok(())
+        }
+        TypeKind::Struct(struct_type) => {
+            write!(w, "{{")?;
+            let mut first = true;
+            for member in struct_type.members() {
+                let (ty, bit_size) = match (member.ty(hash), member.bit_size(hash)) {
+                    (Some(ty), Some(bit_size)) if bit_size != 0 => (ty, bit_size),
+                    _ => continue,
+                };
+                if !first {
+                    write!(w, ",")?;
+                }
+                first = false;
+                write!(w, " {}: ", member.name().unwrap_or("<anon>"))?;
+                let bit_offset = member.bit_offset();
+                if bit_offset % 8 == 0 && Some(bit_size) == ty.byte_size(hash).map(|x| x * 8) {
+                    print_value(memory, offset + bit_offset / 8, &ty, w, hash)?;
+                } else {
+                    print_bit_field(memory, offset * 8 + bit_offset, bit_size, w)?;
+                }
+            }
+            if first {
+                write!(w, "}}")?;
+            } else {
+                write!(w, " }}")?;
+            }
+            Ok(())
+        }
+        TypeKind::Subrange(subrange) => match subrange.ty(hash) {
+            Some(ty) => print_value(memory, offset, &ty, w, hash),
+            None => print_unknown(w),
+        },
+        // TODO: unions and Rust enums could use the discriminant or the size of each member.
+        TypeKind::Union(_)
+        | TypeKind::Void
+        | TypeKind::Function(_)
+        | TypeKind::Unspecified(_)
+        | TypeKind::PointerToMember(_) => print_unknown(w),
+    }
+}
+
+fn print_unknown(w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(w, "?")?;
+    Ok(())
+}
+
+fn print_base(
+    memory: &Memory,
+    offset: u64,
+    base: &BaseType,
+    w: &mut dyn ValuePrinter,
+) -> Result<()> {
+    let size = base.byte_size().unwrap_or(0);
+    let big_endian = match base.endianity() {
+        Endianity::Default => memory.big_endian(),
+        Endianity::Big => true,
+        Endianity::Little => false,
+    };
+    let value = match memory.read(offset, size, big_endian) {
+        Some(value) => value,
+        None => return print_unknown(w),
+    };
+    match base.encoding() {
+        BaseTypeEncoding::Boolean => match value {
+            0 => write!(w, "false")?,
+            1 => write!(w, "true")?,
+            _ => write!(w, "{}", value)?,
+        },
+        BaseTypeEncoding::Signed | BaseTypeEncoding::SignedChar => {
+            write!(w, "{}", sign_extend(value, size * 8))?
+        }
+        BaseTypeEncoding::Unsigned | BaseTypeEncoding::UnsignedChar => write!(w, "{}", value)?,
+        BaseTypeEncoding::Float if size == 4 => write!(w, "{}", f32::from_bits(value as u32))?,
+        BaseTypeEncoding::Float if size == 8 => write!(w, "{}", f64::from_bits(value))?,
+        BaseTypeEncoding::Float | BaseTypeEncoding::Address | BaseTypeEncoding::Other => {
+            write!(w, "0x{:x}", value)?
+        }
+    }
+    Ok(())
+}
+
+fn print_bit_field(
+    memory: &Memory,
+    bit_offset: u64,
+    bit_size: u64,
+    w: &mut dyn ValuePrinter,
+) -> Result<()> {
+    let begin = bit_offset / 8;
+    let end = (bit_offset + bit_size + 7) / 8;
+    let big_endian = memory.big_endian();
+    let value = match memory.read(begin, end - begin, big_endian) {
+        Some(value) => value,
+        None => return print_unknown(w),
+    };
+    // For big endian, the bit offset is from the most significant bit.
+    let shift = if big_endian {
+        (end - begin) * 8 - bit_offset % 8 - bit_size
+    } else {
+        bit_offset % 8
+    };
+    write!(w, "{}", (value >> shift) & mask(bit_size))?;
+    Ok(())
+}
+
+fn print_pointer(
+    memory: &Memory,
+    offset: u64,
+    size: u64,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    let value = match memory.relocation(offset) {
+        // A relocation against an undefined symbol has a target of 0.
+        Some(relocation) if relocation.target() == 0 && relocation.symbol().is_some() => {
+            write!(w, "{}", relocation.symbol().unwrap())?;
+            if relocation.addend() != 0 {
+                write!(w, "+0x{:x}", relocation.addend())?;
+            }
+            return Ok(());
+        }
+        Some(relocation) => relocation.value(),
+        None => match memory.read(offset, size, memory.big_endian()) {
+            Some(value) => value,
+            None => return print_unknown(w),
+        },
+    };
+    let address = value.wrapping_sub(memory.bias.unwrap_or(0));
+    if value == 0 {
+        write!(w, "null")?;
+    } else if let Some(function) = hash.functions_by_address.get(&address) {
+        write!(w, "&")?;
+        print::function::print_ref(function, w)?;
+    } else if let Some((variable, offset)) = hash.find_variable(address) {
+        write!(w, "&")?;
+        print::variable::print_member_ref(variable, offset, w, hash)?;
+    } else if let Some(symbol) = find_symbol(hash.file, address) {
+        write!(w, "&{}", symbol.0)?;
+        if symbol.1 != 0 {
+            write!(w, "+0x{:x}", symbol.1)?;
+        }
+    } else {
+        write!(w, "0x{:x}", value)?;
+    }
+    Ok(())
+}
+
+fn find_symbol<'a>(file: &'a File, address: u64) -> Option<(&'a str, u64)> {
+    let symbols = file.symbols();
+    let index = match symbols.binary_search_by_key(&address, |symbol| symbol.address().begin) {
+        Ok(index) => index,
+        Err(0) => return None,
+        Err(index) => index - 1,
+    };
+    let symbol = &symbols[index];
+    let range = symbol.address();
+    if address == range.begin || range.contains(address) {
+        symbol.name().map(|name| (name, address - range.begin))
+    } else {
+        None
+    }
+}
+
+fn print_string(memory: &Memory, offset: u64, count: u64, w: &mut dyn ValuePrinter) -> Result<()> {
+    write!(w, "\"")?;
+    for i in 0..count {
+        if i == MAX_STRING {
+            write!(w, "...")?;
+            break;
+        }
+        let c = match memory.read(offset + i, 1, false) {
+            Some(c) => c as u8,
+            None => break,
+        };
+        if c == 0 {
+            break;
+        }
+        write!(w, "{}", std::ascii::escape_default(c))?;
+    }
+    write!(w, "\"")?;
+    Ok(())
+}
+
+fn is_char(base: &BaseType) -> bool {
+    match base.encoding() {
+        BaseTypeEncoding::SignedChar | BaseTypeEncoding::UnsignedChar => true,
+        _ => false,
+    }
+}
+
+fn mask(bits: u64) -> u64 {
+    if bits >= 64 {
+        !0
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(value: u64, bits: u64) -> i64 {
+    if bits == 0 || bits >= 64 {
+        value as i64
+    } else {
+        let shift = 64 - bits;
+        ((value << shift) as i64) >> shift
+    }
+}
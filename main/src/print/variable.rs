@@ -1,10 +1,33 @@
 use std::cmp;
 
-use parser::{FileHash, Unit, Variable};
+use parser::{FileHash, Function, Unit, Variable};
 
-use crate::print::{self, DiffState, Print, PrintState, SortList, ValuePrinter};
+use crate::print::{self, DiffList, DiffState, Print, PrintState, SortList, ValuePrinter};
+use crate::xref::{self, VariableAccesses};
 use crate::{Options, Result, Sort};
 
+pub(crate) fn print_ref(v: &Variable, w: &mut dyn ValuePrinter) -> Result<()> {
+    w.link(v.id(), &mut |w| {
+        if let Some(namespace) = v.namespace() {
+            print::namespace::print(namespace, w)?;
+        }
+        write!(w, "{}", v.name().unwrap_or("<anon>"))?;
+        Ok(())
+    })
+}
+
+/// Print a reference to the member at the given offset within a variable.
+pub(crate) fn print_member_ref(
+    v: &Variable,
+    offset: u64,
+    w: &mut dyn ValuePrinter,
+    hash: &FileHash,
+) -> Result<()> {
+    print_ref(v, w)?;
+    write!(w, "{}", xref::member_path(hash, v, offset))?;
+    Ok(())
+}
+
 pub(crate) fn print(v: &Variable, state: &mut PrintState, unit: &Unit) -> Result<()> {
     state.collapsed(
         |state| state.line(|w, state| print_name(v, w, state)),
@@ -16,7 +39,15 @@ pub(crate) fn print(v: &Variable, state: &mut PrintState, unit: &Unit) -> Result
             }
             state.field("address", |w, _state| print_address(v, w))?;
             state.field("size", |w, state| print_size(v, w, state))?;
-            state.field("declaration", |w, _state| print_declaration(v, w))
+            state.field("declaration", |w, _state| print_declaration(v, w))?;
+            if state.options().print_variable_value {
+                state.field("value", |w, hash| print::value::print(v, w, hash))?;
+            }
+            if state.options().print_variable_accessed_by {
+                let accessors = accessors(v, state.hash(), state.accesses);
+                state.field_collapsed("accessed by", |state| state.list(&(), &accessors))?;
+            }
+            Ok(())
             // TODO: print anon type inline
         },
     )?;
@@ -54,7 +85,18 @@ pub(crate) fn diff(
                 state.field("address", a, b, |w, _state, x| print_address(x, w))
             })?;
             state.field("size", a, b, |w, state, x| print_size(x, w, state))?;
-            state.field("declaration", a, b, |w, _state, x| print_declaration(x, w))
+            state.field("declaration", a, b, |w, _state, x| print_declaration(x, w))?;
+            if state.options().print_variable_value {
+                state.field("value", a, b, |w, hash, x| print::value::print(x, w, hash))?;
+            }
+            if state.options().print_variable_accessed_by {
+                let accessors_a = accessors(a, state.hash_a(), state.accesses_a);
+                let accessors_b = accessors(b, state.hash_b(), state.accesses_b);
+                state.field_collapsed("accessed by", |state| {
+                    state.list(&(), &accessors_a, &(), &accessors_b)
+                })?;
+            }
+            Ok(())
         },
     )?;
     state.line_break()?;
@@ -112,6 +154,64 @@ fn print_declaration(v: &Variable, w: &mut dyn ValuePrinter) -> Result<()> {
     Ok(())
 }
 
+/// A function that accesses the variable being printed.
+#[derive(Debug)]
+struct Accessor<'a> {
+    function: &'a Function<'a>,
+}
+
+fn accessors<'a>(
+    v: &Variable,
+    hash: &FileHash,
+    accesses: Option<&VariableAccesses<'a>>,
+) -> Vec<Accessor<'a>> {
+    let mut accessors: Vec<_> = match accesses {
+        Some(accesses) => accesses
+            .accessed_by(v)
+            .map(|access| Accessor {
+                function: access.function(),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    accessors.sort_by(|x, y| {
+        Function::cmp_id(hash, x.function, hash, y.function)
+            .then_with(|| x.function.offset().cmp(&y.function.offset()))
+    });
+    accessors.dedup_by(|x, y| x.function.offset() == y.function.offset());
+    accessors
+}
+
+impl<'a> Print for Accessor<'a> {
+    type Arg = ();
+
+    fn print(&self, state: &mut PrintState, _arg: &()) -> Result<()> {
+        state.line(|w, _hash| print::function::print_ref(self.function, w))
+    }
+
+    fn diff(state: &mut DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> Result<()> {
+        state.line(a, b, |w, _hash, x| {
+            print::function::print_ref(x.function, w)
+        })
+    }
+}
+
+impl<'a> DiffList for Accessor<'a> {
+    fn step_cost(&self, _state: &DiffState, _arg: &()) -> usize {
+        1
+    }
+
+    fn diff_cost(state: &DiffState, _arg_a: &(), a: &Self, _arg_b: &(), b: &Self) -> usize {
+        if Function::cmp_id(state.hash_a(), a.function, state.hash_b(), b.function)
+            != cmp::Ordering::Equal
+        {
+            1
+        } else {
+            0
+        }
+    }
+}
+
 impl<'input> Print for Variable<'input> {
     type Arg = Unit<'input>;
 
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use parser::{File, FileHash, Function};
+
+use crate::call_graph::{CallEdge, CallGraph, FunctionKey};
+use crate::filter;
+use crate::symbolize::function_name;
+use crate::{Options, Result};
+
+/// Print the calls that can reach the functions matching `targets`.
+///
+/// If `sources` is empty, then print all functions from which a target
+/// can be reached. Otherwise, print the shortest path from each function
+/// matching `sources` to any target.
+///
+/// Patterns match either the full name of a function including its namespace,
+/// the function name alone, or the symbol name. A pattern ending in `::*`
+/// matches all functions within that namespace. Imported functions are
+/// matched by their symbol name, and are reached through the functions that
+/// call them.
+///
+/// Only the functions that match the filter options are printed or used as
+/// sources, but paths may pass through any function.
+pub fn reach(
+    file: &File,
+    sources: &[&str],
+    targets: &[&str],
+    w: &mut dyn Write,
+    options: &Options,
+) -> Result<()> {
+    let hash = FileHash::new(file);
+    let graph = CallGraph::new(&hash);
+    let calls_import = |function: &Function| find_import(&graph, function, targets).is_some();
+    let is_target = |function: &Function| matches_any(function, targets) || calls_import(function);
+
+    let mut functions = Vec::new();
+    for unit in filter::filter_units(file, options) {
+        functions.extend(filter::filter_functions(unit, options));
+    }
+
+    if sources.is_empty() {
+        let filtered: HashSet<_> = functions
+            .iter()
+            .map(|function| FunctionKey::new(function))
+            .collect();
+        // Functions that call a target import can reach it directly.
+        let mut names: Vec<_> = graph
+            .reaching(is_target)
+            .into_iter()
+            .chain(
+                functions
+                    .iter()
+                    .cloned()
+                    .filter(|function| calls_import(*function)),
+            )
+            .filter(|function| filtered.contains(&FunctionKey::new(function)))
+            .map(function_name)
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            writeln!(w, "{}", name)?;
+        }
+        return Ok(());
+    }
+
+    let mut paths = Vec::new();
+    for function in functions {
+        if function.address().is_none() || !matches_any(function, sources) {
+            continue;
+        }
+        if let Some(path) = graph.shortest_path(function, is_target) {
+            // The path ends at a function that either is a target, or calls
+            // an imported target.
+            let last = path.last().map(|edge| edge.callee()).unwrap_or(function);
+            let import = if matches_any(last, targets) {
+                None
+            } else {
+                find_import(&graph, last, targets)
+            };
+            paths.push((function, path, import));
+        }
+    }
+    paths.sort_by_key(|(_, path, import)| path.len() + import.is_some() as usize);
+    for (function, path, import) in paths {
+        print_path(function, &path, import, w)?;
+    }
+    Ok(())
+}
+
+fn print_path(
+    source: &Function,
+    path: &[&CallEdge],
+    import: Option<&str>,
+    w: &mut dyn Write,
+) -> Result<()> {
+    write!(w, "{}", function_name(source))?;
+    for edge in path {
+        // Inlined calls are marked differently so that they stand out.
+        let arrow = if edge.is_inlined() { "=>" } else { "->" };
+        write!(w, " {} {}", arrow, function_name(edge.callee()))?;
+    }
+    if let Some(import) = import {
+        write!(w, " -> {}", import)?;
+    }
+    writeln!(w)?;
+    Ok(())
+}
+
+fn find_import<'input>(
+    graph: &CallGraph<'input>,
+    function: &Function,
+    patterns: &[&str],
+) -> Option<&'input str> {
+    graph.imports(function).iter().cloned().find(|import| {
+        patterns
+            .iter()
+            .any(|pattern| matches_import(import, pattern))
+    })
+}
+
+fn matches_import(import: &str, pattern: &str) -> bool {
+    // Ignore any symbol version.
+    let name = import.split('@').next().unwrap_or(import);
+    import == pattern || name == pattern
+}
+
+fn matches_any(function: &Function, patterns: &[&str]) -> bool {
+    patterns.iter().any(|pattern| matches(function, pattern))
+}
+
+fn matches(function: &Function, pattern: &str) -> bool {
+    if pattern.ends_with("::*") {
+        let namespace = &pattern[..pattern.len() - 1];
+        return function_name(function).starts_with(namespace);
+    }
+    function.name() == Some(pattern)
+        || function.symbol_name() == Some(pattern)
+        || function_name(function) == pattern
+}
@@ -0,0 +1,320 @@
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use parser::{
+    File, FileHash, LayoutItem, Member, StructType, Type, TypeKind, TypeModifierKind, TypeOffset,
+};
+
+use crate::c_header;
+use crate::filter;
+use crate::print::{self, TextValuePrinter};
+use crate::symbolize::tagged_name;
+use crate::{Options, Result};
+
+/// Print the structs that match the filter options and that could be made
+/// smaller by reordering their members.
+///
+/// The alignment of members is estimated in the same way as for `c_header`,
+/// and structs are skipped if this estimate doesn't reproduce their current
+/// layout. Structs with inherited types or variant parts are also
+/// skipped. Bitfields that share storage are kept together.
+///
+/// If `options.reorder_cachelines` is set, then members are only reordered
+/// within the cache line that they currently start in, and each cache line
+/// keeps its current start offset, so only the last line can be made smaller.
+///
+/// Structs are sorted by the bytes saved multiplied by the number of instances
+/// in global variables, followed by the bytes saved.
+pub fn reorder(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let instances = instances(file, &hash);
+
+    let mut seen = HashSet::new();
+    let mut suggestions = Vec::new();
+    for unit in filter::filter_units(file, options) {
+        for ty in filter::filter_types(unit, &hash, options, false) {
+            if let TypeKind::Struct(ref val) = *ty.kind() {
+                let key = StructKey::new(ty, val);
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+                if let Some(suggestion) = suggest(val, &hash, options) {
+                    let name = tagged_name("struct", val.namespace(), val.name());
+                    let count = instances.get(&key).cloned().unwrap_or(0);
+                    suggestions.push((name, count, suggestion));
+                }
+            }
+        }
+    }
+    suggestions.sort_by(|a, b| {
+        let saved_a = a.2.saved();
+        let saved_b = b.2.saved();
+        saved_b
+            .saturating_mul(b.1)
+            .cmp(&saved_a.saturating_mul(a.1))
+            .then_with(|| saved_b.cmp(&saved_a))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let mut total = 0;
+    for (name, count, suggestion) in &suggestions {
+        total = suggestion
+            .saved()
+            .saturating_mul(*count)
+            .saturating_add(total);
+        writeln!(
+            w,
+            "{} [{} -> {}, saved {}, instances {}]",
+            name,
+            suggestion.size,
+            suggestion.new_size,
+            suggestion.saved(),
+            count
+        )?;
+        for (block, offset) in suggestion.blocks.iter().zip(&suggestion.offsets) {
+            write!(w, "\t{}[{}]\t", offset, block.size)?;
+            if block.members.len() == 1 && !block.bitfield {
+                let member = block.members[0];
+                write!(w, "{}: ", member.name().unwrap_or("<anon>"))?;
+                print::types::print_ref(member.ty(&hash), &mut TextValuePrinter::new(w), &hash)?;
+            } else {
+                let names: Vec<_> = block
+                    .members
+                    .iter()
+                    .map(|member| member.name().unwrap_or("<anon>"))
+                    .collect();
+                write!(w, "{}: <bitfields>", names.join(", "))?;
+            }
+            writeln!(w)?;
+        }
+    }
+    writeln!(w, "total [{}]", total)?;
+    Ok(())
+}
+
+struct Suggestion<'input, 'item> {
+    size: u64,
+    new_size: u64,
+    blocks: Vec<Block<'input, 'item>>,
+    // The new offset of each block.
+    offsets: Vec<u64>,
+}
+
+impl<'input, 'item> Suggestion<'input, 'item> {
+    fn saved(&self) -> u64 {
+        self.size - self.new_size
+    }
+}
+
+/// Members that must be placed together, such as bitfields sharing storage.
+struct Block<'input, 'item> {
+    offset: u64,
+    size: u64,
+    align: u64,
+    bitfield: bool,
+    members: Vec<&'item Member<'input>>,
+}
+
+fn suggest<'input, 'item>(
+    ty: &'item StructType<'input>,
+    hash: &FileHash<'input>,
+    options: &Options,
+) -> Option<Suggestion<'input, 'item>> {
+    if ty.is_declaration() {
+        return None;
+    }
+    let size = ty.byte_size()?;
+    let mut blocks = blocks(ty, hash)?;
+    if blocks.len() < 2 {
+        return None;
+    }
+    let (offsets, layout_size) = layout(&blocks, u64::MAX);
+    if layout_size != size
+        || offsets
+            .iter()
+            .zip(&blocks)
+            .any(|(offset, block)| *offset != block.offset)
+    {
+        return None;
+    }
+
+    let cacheline_size = if options.reorder_cachelines && options.cacheline_size != 0 {
+        options.cacheline_size
+    } else {
+        u64::MAX
+    };
+    blocks.sort_by(|a, b| {
+        (a.offset / cacheline_size)
+            .cmp(&(b.offset / cacheline_size))
+            .then_with(|| b.align.cmp(&a.align))
+            .then_with(|| b.size.cmp(&a.size))
+            .then_with(|| a.offset.cmp(&b.offset))
+    });
+    let (offsets, new_size) = layout(&blocks, cacheline_size);
+    if new_size >= size {
+        return None;
+    }
+    Some(Suggestion {
+        size,
+        new_size,
+        blocks,
+        offsets,
+    })
+}
+
+/// Split the members of a struct into blocks.
+///
+/// Returns `None` if the struct can't be reordered.
+fn blocks<'input, 'item>(
+    ty: &'item StructType<'input>,
+    hash: &FileHash<'input>,
+) -> Option<Vec<Block<'input, 'item>>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for layout in ty.layout(hash) {
+        let member = match layout.item {
+            LayoutItem::Padding => continue,
+            LayoutItem::Member(member) => member,
+            LayoutItem::Inherit(..) | LayoutItem::VariantPart(..) => return None,
+        };
+        let member_ty = member.ty(hash)?;
+        let type_size = member_ty.byte_size(hash)?;
+        let align = c_header::alignment(&member_ty, hash);
+        let bit_size = layout.bit_size.get()?;
+        if layout.bit_offset % 8 == 0 && bit_size == type_size * 8 {
+            blocks.push(Block {
+                offset: layout.bit_offset / 8,
+                size: type_size,
+                align,
+                bitfield: false,
+                members: vec![member],
+            });
+            continue;
+        }
+
+        // Bitfields are stored in a unit of their declared type.
+        if type_size == 0 {
+            return None;
+        }
+        let start = layout.bit_offset / 8 / type_size * type_size;
+        let end = cmp::max(start + type_size, (layout.bit_offset + bit_size + 7) / 8);
+        if let Some(block) = blocks.last_mut() {
+            if block.bitfield && start < block.offset + block.size {
+                block.size = cmp::max(block.offset + block.size, end) - block.offset;
+                block.align = cmp::max(block.align, align);
+                block.members.push(member);
+                continue;
+            }
+        }
+        blocks.push(Block {
+            offset: start,
+            size: end - start,
+            align,
+            bitfield: true,
+            members: vec![member],
+        });
+    }
+    Some(blocks)
+}
+
+/// Return the offsets of the blocks in the given order, and the size of a
+/// struct containing them.
+///
+/// Blocks are not placed before the start of the cache line that they
+/// currently start in.
+fn layout(blocks: &[Block], cacheline_size: u64) -> (Vec<u64>, u64) {
+    let mut offsets = Vec::with_capacity(blocks.len());
+    let mut offset = 0;
+    let mut max_align = 1;
+    for block in blocks {
+        let line_start = block.offset / cacheline_size * cacheline_size;
+        offset = cmp::max(align(offset, block.align), line_start);
+        offsets.push(offset);
+        offset += block.size;
+        max_align = cmp::max(max_align, block.align);
+    }
+    (offsets, align(offset, max_align))
+}
+
+fn align(offset: u64, align: u64) -> u64 {
+    (offset + align - 1) / align * align
+}
+
+/// The key used to merge the definitions of a struct in different units.
+///
+/// Anonymous structs can't be matched by name, so they are keyed by their
+/// type offset instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum StructKey {
+    Named(String, Option<u64>),
+    Anon(TypeOffset),
+}
+
+impl StructKey {
+    pub(crate) fn new(ty: &Type, val: &StructType) -> Self {
+        match val.name() {
+            Some(_) => StructKey::Named(
+                tagged_name("struct", val.namespace(), val.name()),
+                val.byte_size(),
+            ),
+            None => StructKey::Anon(ty.offset()),
+        }
+    }
+}
+
+/// Count the instances of each struct in global variables, including
+/// array elements and members of other structs.
+fn instances(file: &File, hash: &FileHash) -> HashMap<StructKey, u64> {
+    let mut instances = HashMap::new();
+    for unit in file.units() {
+        for variable in unit.variables() {
+            if variable.is_declaration() || variable.address().is_none() {
+                continue;
+            }
+            if let Some(ty) = variable.ty(hash) {
+                count_instances(&ty, 1, hash, &mut instances);
+            }
+        }
+    }
+    instances
+}
+
+fn count_instances(
+    ty: &Type,
+    count: u64,
+    hash: &FileHash,
+    instances: &mut HashMap<StructKey, u64>,
+) {
+    match *ty.kind() {
+        TypeKind::Struct(ref val) => {
+            let instance_count = instances.entry(StructKey::new(ty, val)).or_insert(0);
+            *instance_count = instance_count.saturating_add(count);
+            for member in val.members() {
+                if let Some(ty) = member.ty(hash) {
+                    count_instances(&ty, count, hash, instances);
+                }
+            }
+        }
+        TypeKind::Array(ref val) => {
+            if let (Some(ty), Some(n)) = (val.element_type(hash), val.count(hash)) {
+                count_instances(&ty, count.saturating_mul(n), hash, instances);
+            }
+        }
+        TypeKind::Def(ref val) => {
+            if let Some(ty) = val.ty(hash) {
+                count_instances(&ty, count, hash, instances);
+            }
+        }
+        TypeKind::Modifier(ref val) => match val.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => {}
+            _ => {
+                if let Some(ty) = val.ty(hash) {
+                    count_instances(&ty, count, hash, instances);
+                }
+            }
+        },
+        _ => {}
+    }
+}
@@ -0,0 +1,772 @@
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use parser::{
+    BaseType, BaseTypeEncoding, EnumerationType, Enumerator, File, FileHash, FunctionType,
+    LayoutItem, Namespace, StructType, Type, TypeDef, TypeKind, TypeModifierKind, TypeOffset,
+    UnionType,
+};
+
+use crate::c_header;
+use crate::filter;
+use crate::{Options, Result};
+
+/// Print Rust `#[repr(C)]` declarations for the types that match the filter
+/// options, and the types that they depend on.
+///
+/// Struct members are placed at their debuginfo offsets using explicit padding
+/// fields, and the size of each type is checked with a constant assertion.
+/// Anonymous types are named after the member or typedef that uses them.
+/// C++ names are qualified with their namespace in the same way as for
+/// `c_header`, and structs with members that aren't naturally aligned are
+/// declared with `#[repr(C, packed)]`.
+/// Enumerations are declared as an integer type alias with a constant for
+/// each enumerator, and members that overlap a previous field are replaced
+/// by a comment.
+///
+/// Bitfields are stored in byte arrays, with accessor methods for each member.
+/// The accessors number the bits from the least significant bit of the first
+/// byte, which matches the layout used by little endian targets.
+pub fn rust_bindings(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let mut bindings = Bindings::new(file, &hash);
+    writeln!(w, "// Generated by ddbug from {}.", file.path())?;
+    for unit in filter::filter_units(file, options) {
+        for ty in filter::filter_types(unit, &hash, options, false) {
+            bindings.require(w, ty)?;
+        }
+    }
+    Ok(())
+}
+
+struct Bindings<'a, 'input> {
+    hash: &'a FileHash<'input>,
+    // The definitions of named structs, unions and enumerations, for use in
+    // place of declarations.
+    definitions: HashMap<String, TypeOffset>,
+    // The names given to anonymous types.
+    names: HashMap<TypeOffset, String>,
+    visited: HashSet<TypeOffset>,
+    defined: HashSet<String>,
+}
+
+struct Bitfield {
+    storage: String,
+    name: String,
+    bit_offset: u64,
+    bit_size: u64,
+}
+
+impl<'a, 'input> Bindings<'a, 'input> {
+    fn new(file: &File<'input>, hash: &'a FileHash<'input>) -> Self {
+        Bindings {
+            hash,
+            definitions: c_header::definitions(file),
+            names: HashMap::new(),
+            visited: HashSet::new(),
+            defined: HashSet::new(),
+        }
+    }
+
+    /// Print the declarations of `ty` and the types it depends on.
+    fn require(&mut self, w: &mut dyn Write, ty: &Type<'input>) -> Result<()> {
+        let hash = self.hash;
+        if !self.visited.insert(ty.offset()) {
+            return Ok(());
+        }
+        match *ty.kind() {
+            TypeKind::Void
+            | TypeKind::Base(..)
+            | TypeKind::Unspecified(..)
+            | TypeKind::PointerToMember(..) => {}
+            TypeKind::Def(ref val) => {
+                if let Some(target) = val.ty(hash) {
+                    if val.name().is_some() && is_anon(&target) {
+                        self.names
+                            .entry(target.offset())
+                            .or_insert_with(|| type_def_name(val));
+                    }
+                    self.require(w, &target)?;
+                    self.define_type_def(w, val, &target)?;
+                }
+            }
+            TypeKind::Struct(ref val) => {
+                let tag = c_header::struct_tag(val);
+                self.require_tag(w, ty, tag, val.is_declaration())?;
+            }
+            TypeKind::Union(ref val) => {
+                let tag = c_header::union_tag(val);
+                self.require_tag(w, ty, tag, val.is_declaration())?;
+            }
+            TypeKind::Enumeration(ref val) => {
+                let tag = c_header::enumeration_tag(val);
+                self.require_tag(w, ty, tag, val.is_declaration())?;
+            }
+            TypeKind::Array(ref val) => {
+                if let Some(ty) = val.element_type(hash) {
+                    self.require(w, &ty)?;
+                }
+            }
+            TypeKind::Function(ref val) => {
+                if let Some(ty) = val.return_type(hash) {
+                    self.require(w, &ty)?;
+                }
+                for parameter in val.parameters() {
+                    if let Some(ty) = parameter.ty(hash) {
+                        self.require(w, &ty)?;
+                    }
+                }
+            }
+            TypeKind::Modifier(ref val) => {
+                if let Some(ty) = val.ty(hash) {
+                    self.require(w, &ty)?;
+                }
+            }
+            TypeKind::Subrange(ref val) => {
+                if let Some(ty) = val.ty(hash) {
+                    self.require(w, &ty)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn require_tag(
+        &mut self,
+        w: &mut dyn Write,
+        ty: &Type<'input>,
+        tag: Option<String>,
+        declaration: bool,
+    ) -> Result<()> {
+        let hash = self.hash;
+        if tag.is_none() && !self.names.contains_key(&ty.offset()) {
+            let name = format!("_Anon{}", self.names.len());
+            self.names.insert(ty.offset(), name);
+        }
+        let name = self.rust_type(ty);
+        if self.defined.contains(&name) {
+            return Ok(());
+        }
+        if !declaration {
+            return self.define(w, &name, ty);
+        }
+        let definition = tag
+            .and_then(|tag| self.definitions.get(&tag))
+            .and_then(|offset| Type::from_offset(hash, *offset));
+        match definition {
+            Some(definition) => {
+                self.visited.insert(definition.offset());
+                self.define(w, &name, &definition)
+            }
+            None => {
+                // Opaque type.
+                self.defined.insert(name.clone());
+                writeln!(w)?;
+                writeln!(w, "#[repr(C)]")?;
+                writeln!(w, "#[derive(Clone, Copy)]")?;
+                writeln!(w, "pub struct {} {{", name)?;
+                writeln!(w, "    _unused: [u8; 0],")?;
+                writeln!(w, "}}")?;
+                Ok(())
+            }
+        }
+    }
+
+    fn define(&mut self, w: &mut dyn Write, name: &str, ty: &Type<'input>) -> Result<()> {
+        let hash = self.hash;
+        // Name the anonymous types of members before their first use.
+        for (i, member) in ty.members().iter().enumerate() {
+            if let Some(member_ty) = member.ty(hash) {
+                if is_anon(&member_ty) {
+                    let member_name = match member.name() {
+                        Some(member_name) => member_name.to_string(),
+                        None => format!("anon{}", i),
+                    };
+                    self.names
+                        .entry(member_ty.offset())
+                        .or_insert_with(|| format!("{}_{}", name, ident(&member_name)));
+                }
+            }
+        }
+        if let TypeKind::Struct(ref val) = *ty.kind() {
+            for inherit in val.inherits() {
+                if let Some(ty) = inherit.ty(hash) {
+                    self.require(w, &ty)?;
+                }
+            }
+        }
+        for member in ty.members() {
+            if let Some(ty) = member.ty(hash) {
+                self.require(w, &ty)?;
+            }
+        }
+
+        if !self.defined.insert(name.to_string()) {
+            return Ok(());
+        }
+        match *ty.kind() {
+            TypeKind::Struct(ref val) => self.define_struct(w, name, val),
+            TypeKind::Union(ref val) => self.define_union(w, name, val),
+            TypeKind::Enumeration(ref val) => self.define_enumeration(w, name, val),
+            _ => Ok(()),
+        }
+    }
+
+    fn define_type_def(
+        &mut self,
+        w: &mut dyn Write,
+        val: &TypeDef<'input>,
+        target: &Type<'input>,
+    ) -> Result<()> {
+        if val.name().is_none() {
+            return Ok(());
+        }
+        let name = type_def_name(val);
+        let target = self.rust_type(target);
+        // Typedefs commonly have the same name as the type they refer to.
+        if target == name || !self.defined.insert(name.clone()) {
+            return Ok(());
+        }
+        writeln!(w)?;
+        writeln!(w, "pub type {} = {};", name, target)?;
+        Ok(())
+    }
+
+    fn define_struct(&self, w: &mut dyn Write, name: &str, val: &StructType<'input>) -> Result<()> {
+        let hash = self.hash;
+        let mut fields = Fields::default();
+        for layout in val.layout(hash) {
+            let bit_size = layout.bit_size.get().unwrap_or(0);
+            match layout.item {
+                LayoutItem::Padding => {}
+                LayoutItem::Member(member) => {
+                    let ty = match member.ty(hash) {
+                        Some(ty) => ty,
+                        None => continue,
+                    };
+                    let type_bit_size = ty.byte_size(hash).map(|v| v * 8);
+                    if layout.bit_offset % 8 != 0
+                        || (layout.bit_size.is_some() && type_bit_size != Some(bit_size))
+                    {
+                        fields.bits(layout.bit_offset, bit_size, member.name());
+                    } else {
+                        let field_name = match member.name() {
+                            Some(member_name) => ident(member_name),
+                            None => format!("_anon{}", fields.fields.len()),
+                        };
+                        fields.field(
+                            layout.bit_offset / 8,
+                            bit_size / 8,
+                            field_name,
+                            self.rust_type(&ty),
+                        );
+                    }
+                }
+                LayoutItem::Inherit(inherit) => {
+                    let ty = match inherit.ty(hash) {
+                        Some(ty) => self.rust_type(&ty),
+                        None => continue,
+                    };
+                    let field_name = format!("_base{}", layout.bit_offset / 8);
+                    fields.field(layout.bit_offset / 8, bit_size / 8, field_name, ty);
+                }
+                LayoutItem::VariantPart(..) => {
+                    // Rust enums with data have no stable layout, so only preserve the size.
+                    let field_name = format!("_variant_part{}", layout.bit_offset / 8);
+                    let ty = format!("[u8; {}]", bit_size / 8);
+                    fields.field(layout.bit_offset / 8, bit_size / 8, field_name, ty);
+                }
+            }
+        }
+        fields.finish(val.byte_size());
+
+        writeln!(w)?;
+        if c_header::is_packed(val, hash) {
+            writeln!(w, "#[repr(C, packed)]")?;
+        } else {
+            writeln!(w, "#[repr(C)]")?;
+        }
+        writeln!(w, "#[derive(Clone, Copy)]")?;
+        writeln!(w, "pub struct {} {{", name)?;
+        for field in &fields.fields {
+            writeln!(w, "    {}", field)?;
+        }
+        writeln!(w, "}}")?;
+        if !fields.bitfields.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "impl {} {{", name)?;
+            for (i, bitfield) in fields.bitfields.iter().enumerate() {
+                if i != 0 {
+                    writeln!(w)?;
+                }
+                print_bitfield_accessors(w, bitfield)?;
+            }
+            writeln!(w, "}}")?;
+        }
+        print_size_assertion(w, name, val.byte_size())
+    }
+
+    fn define_union(&self, w: &mut dyn Write, name: &str, val: &UnionType<'input>) -> Result<()> {
+        let hash = self.hash;
+        writeln!(w)?;
+        writeln!(w, "#[repr(C)]")?;
+        writeln!(w, "#[derive(Clone, Copy)]")?;
+        writeln!(w, "pub union {} {{", name)?;
+        let mut bit_size = 0;
+        for (i, member) in val.members().iter().enumerate() {
+            let ty = match member.ty(hash) {
+                Some(ty) => self.rust_type(&ty),
+                None => continue,
+            };
+            let field_name = match member.name() {
+                Some(member_name) => ident(member_name),
+                None => format!("_anon{}", i),
+            };
+            writeln!(w, "    pub {}: {},", field_name, ty)?;
+            bit_size = cmp::max(bit_size, member.bit_size(hash).unwrap_or(0));
+        }
+        if let Some(byte_size) = val.byte_size() {
+            if bit_size < byte_size * 8 {
+                writeln!(w, "    pub _padding0: [u8; {}],", byte_size)?;
+            }
+        }
+        writeln!(w, "}}")?;
+        print_size_assertion(w, name, val.byte_size())
+    }
+
+    fn define_enumeration(
+        &self,
+        w: &mut dyn Write,
+        name: &str,
+        val: &EnumerationType<'input>,
+    ) -> Result<()> {
+        let hash = self.hash;
+        let enumerators = val.enumerators(hash);
+        let repr = self.enumeration_repr(val, &enumerators);
+        writeln!(w)?;
+        // C enumerations may have any value of the underlying type, and may have
+        // duplicate values, so use constants instead of a Rust enum.
+        writeln!(w, "pub type {} = {};", name, repr)?;
+        for enumerator in &enumerators {
+            let enumerator_name = ident(enumerator.name().unwrap_or("_"));
+            writeln!(
+                w,
+                "pub const {}_{}: {} = {};",
+                name.trim_start_matches("r#"),
+                enumerator_name.trim_start_matches("r#"),
+                name,
+                enumerator.value().unwrap_or(0)
+            )?;
+        }
+        print_size_assertion(w, name, val.byte_size(hash))
+    }
+
+    fn enumeration_repr(
+        &self,
+        val: &EnumerationType<'input>,
+        enumerators: &[Enumerator],
+    ) -> &'static str {
+        if let Some(ty) = val.ty(self.hash) {
+            if let TypeKind::Base(ref base) = *ty.kind() {
+                match base.encoding() {
+                    BaseTypeEncoding::Signed
+                    | BaseTypeEncoding::SignedChar
+                    | BaseTypeEncoding::Unsigned
+                    | BaseTypeEncoding::UnsignedChar => {
+                        if let Some(repr) = integer_type(base.encoding(), base.byte_size()) {
+                            return repr;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let encoding = if enumerators.iter().any(|e| e.value().unwrap_or(0) < 0) {
+            BaseTypeEncoding::Signed
+        } else {
+            BaseTypeEncoding::Unsigned
+        };
+        integer_type(encoding, val.byte_size(self.hash)).unwrap_or("u32")
+    }
+
+    /// Return the Rust type to use for `ty`.
+    fn rust_type(&self, ty: &Type<'input>) -> String {
+        let hash = self.hash;
+        match *ty.kind() {
+            TypeKind::Void | TypeKind::Unspecified(..) => "core::ffi::c_void".to_string(),
+            TypeKind::Base(ref val) => base_type(val),
+            TypeKind::Def(ref val) => type_def_name(val),
+            TypeKind::Struct(ref val) => self.type_name(ty, val.namespace(), val.name()),
+            TypeKind::Union(ref val) => self.type_name(ty, val.namespace(), val.name()),
+            TypeKind::Enumeration(ref val) => self.type_name(ty, val.namespace(), val.name()),
+            TypeKind::Array(ref val) => {
+                let element = match val.element_type(hash) {
+                    Some(ty) => self.rust_type(&ty),
+                    None => "<invalid>".to_string(),
+                };
+                format!("[{}; {}]", element, val.count(hash).unwrap_or(0))
+            }
+            TypeKind::Function(ref val) => self.function_type(val),
+            TypeKind::PointerToMember(ref val) => {
+                // Rust has no pointers to members, so only preserve the size.
+                format!("[u8; {}]", val.byte_size(hash).unwrap_or(0))
+            }
+            TypeKind::Modifier(ref val) => {
+                let target = match val.ty(hash) {
+                    Some(target) => target,
+                    None => return "<invalid>".to_string(),
+                };
+                match val.kind() {
+                    TypeModifierKind::Pointer
+                    | TypeModifierKind::Reference
+                    | TypeModifierKind::RvalueReference => {
+                        if self.is_function(&target) {
+                            format!("Option<{}>", self.rust_type(&target))
+                        } else if self.is_const(&target) {
+                            format!("*const {}", self.rust_type(&target))
+                        } else {
+                            format!("*mut {}", self.rust_type(&target))
+                        }
+                    }
+                    TypeModifierKind::Const
+                    | TypeModifierKind::Volatile
+                    | TypeModifierKind::Restrict
+                    | TypeModifierKind::Atomic
+                    | TypeModifierKind::Packed
+                    | TypeModifierKind::Shared
+                    | TypeModifierKind::Other => self.rust_type(&target),
+                }
+            }
+            TypeKind::Subrange(ref val) => match val.ty(hash) {
+                Some(ty) => self.rust_type(&ty),
+                None => "<invalid>".to_string(),
+            },
+        }
+    }
+
+    fn type_name(
+        &self,
+        ty: &Type<'input>,
+        namespace: Option<&Namespace>,
+        name: Option<&str>,
+    ) -> String {
+        match name {
+            Some(name) => ident(&c_header::qualified_ident(namespace, name)),
+            None => match self.names.get(&ty.offset()) {
+                Some(name) => name.clone(),
+                None => "<anon>".to_string(),
+            },
+        }
+    }
+
+    fn function_type(&self, val: &FunctionType<'input>) -> String {
+        let hash = self.hash;
+        let mut parameters: Vec<_> = val
+            .parameters()
+            .iter()
+            .map(|parameter| match parameter.ty(hash) {
+                Some(ty) => self.rust_type(&ty),
+                None => "<invalid>".to_string(),
+            })
+            .collect();
+        // Rust requires a named parameter before `...`, so variadic functions
+        // without one are declared without parameters.
+        if val.is_variadic() && !parameters.is_empty() {
+            parameters.push("...".to_string());
+        }
+        let mut function = format!("unsafe extern \"C\" fn({})", parameters.join(", "));
+        match val.return_type(hash) {
+            Some(ty) => {
+                if !ty.is_void() {
+                    function.push_str(" -> ");
+                    function.push_str(&self.rust_type(&ty));
+                }
+            }
+            None => function.push_str(" -> <invalid>"),
+        }
+        function
+    }
+
+    /// Return true if the type is a function, ignoring typedefs and qualifiers.
+    fn is_function(&self, ty: &Type<'input>) -> bool {
+        match *ty.kind() {
+            TypeKind::Function(..) => true,
+            TypeKind::Def(ref val) => match val.ty(self.hash) {
+                Some(ty) => self.is_function(&ty),
+                None => false,
+            },
+            TypeKind::Modifier(ref val) => match val.kind() {
+                TypeModifierKind::Pointer
+                | TypeModifierKind::Reference
+                | TypeModifierKind::RvalueReference => false,
+                _ => match val.ty(self.hash) {
+                    Some(ty) => self.is_function(&ty),
+                    None => false,
+                },
+            },
+            _ => false,
+        }
+    }
+
+    /// Return true if the type is const, ignoring other qualifiers.
+    fn is_const(&self, ty: &Type<'input>) -> bool {
+        match *ty.kind() {
+            TypeKind::Modifier(ref val) => match val.kind() {
+                TypeModifierKind::Const => true,
+                TypeModifierKind::Pointer
+                | TypeModifierKind::Reference
+                | TypeModifierKind::RvalueReference => false,
+                _ => match val.ty(self.hash) {
+                    Some(ty) => self.is_const(&ty),
+                    None => false,
+                },
+            },
+            _ => false,
+        }
+    }
+}
+
+/// The fields of a struct, with explicit padding.
+#[derive(Default)]
+struct Fields {
+    // The lines of the struct body.
+    fields: Vec<String>,
+    bitfields: Vec<Bitfield>,
+    // The byte offset of the end of the last field.
+    end: u64,
+    // The byte range of the storage for the current bitfields.
+    storage: Option<(u64, u64)>,
+    padding_count: usize,
+    storage_count: usize,
+}
+
+impl Fields {
+    fn field(&mut self, offset: u64, size: u64, name: String, ty: String) {
+        self.flush();
+        if offset < self.end {
+            // Overlapping members can't be represented.
+            self.overlap(offset, &name);
+            return;
+        }
+        self.pad(offset);
+        self.fields.push(format!("pub {}: {},", name, ty));
+        self.end = offset + size;
+    }
+
+    fn bits(&mut self, bit_offset: u64, bit_size: u64, name: Option<&str>) {
+        let start = bit_offset / 8;
+        let end = (bit_offset + bit_size + 7) / 8;
+        match self.storage {
+            Some((storage_start, storage_end)) if start < storage_end => {
+                self.storage = Some((storage_start, cmp::max(storage_end, end)));
+            }
+            _ => {
+                self.flush();
+                if start < self.end {
+                    self.overlap(start, name.unwrap_or("<anon>"));
+                    return;
+                }
+                self.pad(start);
+                self.storage = Some((start, end));
+            }
+        }
+        if let (Some(name), Some((storage_start, _))) = (name, self.storage) {
+            self.bitfields.push(Bitfield {
+                storage: format!("_bitfield{}", self.storage_count),
+                name: ident(name),
+                bit_offset: bit_offset - storage_start * 8,
+                bit_size,
+            });
+        }
+    }
+
+    fn finish(&mut self, byte_size: Option<u64>) {
+        self.flush();
+        if let Some(byte_size) = byte_size {
+            self.pad(byte_size);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((start, end)) = self.storage.take() {
+            self.fields.push(format!(
+                "pub _bitfield{}: [u8; {}],",
+                self.storage_count,
+                end - start
+            ));
+            self.storage_count += 1;
+            self.end = end;
+        }
+    }
+
+    fn overlap(&mut self, offset: u64, name: &str) {
+        self.fields.push(format!(
+            "// Omitted {} at offset {}, which overlaps the previous field.",
+            name, offset
+        ));
+    }
+
+    fn pad(&mut self, offset: u64) {
+        if offset > self.end {
+            self.fields.push(format!(
+                "pub _padding{}: [u8; {}],",
+                self.padding_count,
+                offset - self.end
+            ));
+            self.padding_count += 1;
+            self.end = offset;
+        }
+    }
+}
+
+fn print_bitfield_accessors(w: &mut dyn Write, bitfield: &Bitfield) -> Result<()> {
+    let ty = match bitfield.bit_size {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        _ => "u64",
+    };
+    let name = &bitfield.name;
+    let setter = if name.starts_with("r#") {
+        format!("set_{}", &name[2..])
+    } else {
+        format!("set_{}", name)
+    };
+    writeln!(w, "    pub fn {}(&self) -> {} {{", name, ty)?;
+    writeln!(w, "        let mut value: {} = 0;", ty)?;
+    writeln!(w, "        for i in 0..{} {{", bitfield.bit_size)?;
+    writeln!(w, "            let bit = {} + i;", bitfield.bit_offset)?;
+    writeln!(
+        w,
+        "            value |= {}::from(self.{}[bit / 8] >> (bit % 8) & 1) << i;",
+        ty, bitfield.storage
+    )?;
+    writeln!(w, "        }}")?;
+    writeln!(w, "        value")?;
+    writeln!(w, "    }}")?;
+    writeln!(w)?;
+    writeln!(w, "    pub fn {}(&mut self, value: {}) {{", setter, ty)?;
+    writeln!(w, "        for i in 0..{} {{", bitfield.bit_size)?;
+    writeln!(w, "            let bit = {} + i;", bitfield.bit_offset)?;
+    writeln!(w, "            let mask = 1u8 << (bit % 8);")?;
+    writeln!(w, "            if value >> i & 1 != 0 {{")?;
+    writeln!(
+        w,
+        "                self.{}[bit / 8] |= mask;",
+        bitfield.storage
+    )?;
+    writeln!(w, "            }} else {{")?;
+    writeln!(
+        w,
+        "                self.{}[bit / 8] &= !mask;",
+        bitfield.storage
+    )?;
+    writeln!(w, "            }}")?;
+    writeln!(w, "        }}")?;
+    writeln!(w, "    }}")?;
+    Ok(())
+}
+
+fn print_size_assertion(w: &mut dyn Write, name: &str, byte_size: Option<u64>) -> Result<()> {
+    if let Some(byte_size) = byte_size {
+        writeln!(
+            w,
+            "const _: () = assert!(core::mem::size_of::<{}>() == {});",
+            name, byte_size
+        )?;
+    }
+    Ok(())
+}
+
+/// Return the qualified name of a typedef, using the same identifier as the C header.
+fn type_def_name(val: &TypeDef) -> String {
+    match val.name() {
+        Some(name) => ident(&c_header::qualified_ident(val.namespace(), name)),
+        None => "<anon-typedef>".to_string(),
+    }
+}
+
+/// Return true if the type is a struct, union or enumeration without a name.
+fn is_anon(ty: &Type) -> bool {
+    match *ty.kind() {
+        TypeKind::Struct(ref val) => val.name().is_none(),
+        TypeKind::Union(ref val) => val.name().is_none(),
+        TypeKind::Enumeration(ref val) => val.name().is_none(),
+        _ => false,
+    }
+}
+
+fn base_type(val: &BaseType) -> String {
+    let encoding = val.encoding();
+    if let Some(ty) = integer_type(encoding, val.byte_size()) {
+        return ty.to_string();
+    }
+    match (encoding, val.byte_size()) {
+        (BaseTypeEncoding::Boolean, Some(1)) => "bool".to_string(),
+        (BaseTypeEncoding::Float, Some(4)) => "f32".to_string(),
+        (BaseTypeEncoding::Float, Some(8)) => "f64".to_string(),
+        (_, Some(byte_size)) => format!("[u8; {}]", byte_size),
+        (_, None) => "()".to_string(),
+    }
+}
+
+fn integer_type(encoding: BaseTypeEncoding, byte_size: Option<u64>) -> Option<&'static str> {
+    match encoding {
+        BaseTypeEncoding::Signed | BaseTypeEncoding::SignedChar => match byte_size {
+            Some(1) => Some("i8"),
+            Some(2) => Some("i16"),
+            Some(4) => Some("i32"),
+            Some(8) => Some("i64"),
+            Some(16) => Some("i128"),
+            _ => None,
+        },
+        BaseTypeEncoding::Unsigned | BaseTypeEncoding::UnsignedChar | BaseTypeEncoding::Address => {
+            match byte_size {
+                Some(1) => Some("u8"),
+                Some(2) => Some("u16"),
+                Some(4) => Some("u32"),
+                Some(8) => Some("u64"),
+                Some(16) => Some("u128"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Keywords that can't be raw identifiers.
+const RESERVED: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Convert a name to a valid Rust identifier.
+fn ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if RESERVED.contains(&&*ident) {
+        ident.push('_');
+        ident
+    } else if KEYWORDS.contains(&&*ident) {
+        format!("r#{}", ident)
+    } else {
+        ident
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::Write;
+
+use parser::{Call, File, FileHash};
+
+use crate::code::Code;
+use crate::{Options, Result};
+
+/// Write a snapshot of the debuginfo of a file.
+///
+/// Snapshots don't contain any code, so the calls made by each function are
+/// found now and included in the snapshot. If the file is itself a snapshot,
+/// then its recorded calls are kept. A snapshot can be used in place of
+/// either file when printing differences.
+pub fn save_snapshot(file: &File, w: &mut dyn Write) -> Result<()> {
+    let hash = FileHash::new(file);
+    let mut calls = Vec::new();
+    if let Some(code) = Code::new(file) {
+        for unit in file.units() {
+            for function in unit.functions() {
+                if let Some(range) = function.range() {
+                    for call in code.calls(range) {
+                        calls.push(Call {
+                            from: call.from,
+                            to: call.to,
+                            import: call.import,
+                        });
+                    }
+                }
+            }
+        }
+    } else if file.is_snapshot() {
+        calls.extend_from_slice(file.calls());
+    }
+    file.write_snapshot(&hash, &calls, w)
+}
+
+// Snapshots don't contain the data needed by some print options, so these
+// would show spurious differences.
+pub(crate) fn check_diff_options(file_a: &File, file_b: &File, options: &Options) -> Result<()> {
+    if !file_a.is_snapshot() && !file_b.is_snapshot() {
+        return Ok(());
+    }
+    let unsupported = [
+        (options.print_function_source_lines, "function source lines"),
+        (options.print_function_source_text, "function source text"),
+        (options.print_file_source_lines, "file source lines"),
+        (options.print_function_stack_frame, "function stack frames"),
+        (options.print_function_instructions, "function instructions"),
+        (options.print_function_accesses, "function accesses"),
+        (options.print_variable_accessed_by, "variable accesses"),
+        (options.print_variable_value, "variable values"),
+    ];
+    for (enabled, name) in unsupported.iter() {
+        if *enabled {
+            return Err(format!("cannot print {} for a snapshot", name).into());
+        }
+    }
+    Ok(())
+}
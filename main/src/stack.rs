@@ -0,0 +1,253 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::io::Write;
+
+use parser::{Cfi, CfiDirective, File, FileHash, Function};
+
+use crate::call_graph::{CallGraph, FunctionKey};
+use crate::filter;
+use crate::symbolize::function_name;
+use crate::{Options, Result};
+
+/// The stack frame size of a function, determined from its CFI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSize {
+    /// The maximum offset of the CFA from the stack pointer.
+    pub size: u64,
+    /// False if the CFA was defined relative to another register, such as
+    /// a frame pointer, in which case `size` is only a lower bound.
+    pub exact: bool,
+}
+
+/// Determine the maximum frame size from the CFI of a function.
+///
+/// This includes the return address if the architecture pushes it.
+pub fn frame_size(cfi: &[Cfi]) -> Option<FrameSize> {
+    let mut size = None;
+    let mut register = None;
+    let mut exact = true;
+    for (_, directive) in cfi {
+        let offset = match *directive {
+            CfiDirective::DefCfa(r, offset) => {
+                // The first register is the stack pointer on entry.
+                if *register.get_or_insert(r) != r {
+                    exact = false;
+                }
+                offset
+            }
+            CfiDirective::DefCfaRegister(r) => {
+                if register != Some(r) {
+                    exact = false;
+                }
+                continue;
+            }
+            CfiDirective::DefCfaOffset(offset) => offset,
+            _ => continue,
+        };
+        if offset >= 0 {
+            size = Some(cmp::max(size.unwrap_or(0), offset as u64));
+        }
+    }
+    size.map(|size| FrameSize { size, exact })
+}
+
+// Why the stack depth of a function has no upper bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unbounded {
+    // The last function in the path calls a function earlier in the path.
+    Recursion,
+    // The last function in the path contains an indirect call.
+    IndirectCall,
+}
+
+// Why the stack depth of a function is only a lower bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LowerBound {
+    // The function calls an address that isn't a known function.
+    UnknownCall,
+    // The function jumps to another function.
+    TailCall,
+}
+
+#[derive(Debug, Clone)]
+struct StackDepth<'input> {
+    size: u64,
+    exact: bool,
+    unbounded: Option<Unbounded>,
+    // A function that may be called by this function, and that calls a function
+    // with an unknown stack usage. If set, then `size` is only a lower bound.
+    unknown: Option<(LowerBound, &'input Function<'input>)>,
+    // The calls that result in the maximum depth, including the function itself.
+    path: Vec<(&'input Function<'input>, Option<FrameSize>)>,
+}
+
+impl<'input> StackDepth<'input> {
+    // Return true if `self` is a worse case than `other`.
+    fn is_worse(&self, other: &StackDepth) -> bool {
+        match (self.unbounded, other.unbounded) {
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            _ => self.size > other.size,
+        }
+    }
+}
+
+enum State<'input> {
+    Visiting,
+    Done(StackDepth<'input>),
+}
+
+struct StackAnalysis<'a, 'input> {
+    hash: &'input FileHash<'input>,
+    graph: &'a CallGraph<'input>,
+    states: HashMap<FunctionKey, State<'input>>,
+}
+
+impl<'a, 'input> StackAnalysis<'a, 'input> {
+    fn depth(&mut self, function: &'input Function<'input>) -> StackDepth<'input> {
+        match self.states.get(&FunctionKey::new(function)) {
+            Some(State::Done(depth)) => return depth.clone(),
+            Some(State::Visiting) => {
+                return StackDepth {
+                    size: 0,
+                    exact: true,
+                    unbounded: Some(Unbounded::Recursion),
+                    unknown: None,
+                    path: vec![(function, None)],
+                };
+            }
+            None => {}
+        }
+        self.states
+            .insert(FunctionKey::new(function), State::Visiting);
+
+        let frame = frame_size(&function.cfi(self.hash));
+        let mut worst: Option<StackDepth> = None;
+        if self.graph.has_indirect_calls(function) {
+            worst = Some(StackDepth {
+                size: 0,
+                exact: true,
+                unbounded: Some(Unbounded::IndirectCall),
+                unknown: None,
+                path: Vec::new(),
+            });
+        }
+        let mut unknown = if self.graph.has_unknown_calls(function) {
+            Some((LowerBound::UnknownCall, function))
+        } else if self.graph.has_tail_calls(function) {
+            Some((LowerBound::TailCall, function))
+        } else {
+            None
+        };
+        let graph = self.graph;
+        for edge in graph.calls(function) {
+            // Inlined functions use the frame of their caller.
+            if edge.is_inlined() {
+                continue;
+            }
+            let depth = self.depth(edge.callee());
+            if unknown.is_none() {
+                unknown = depth.unknown;
+            }
+            if worst.as_ref().map(|worst| depth.is_worse(worst)) != Some(false) {
+                worst = Some(depth);
+            }
+        }
+
+        let mut depth = worst.unwrap_or(StackDepth {
+            size: 0,
+            exact: true,
+            unbounded: None,
+            unknown: None,
+            path: Vec::new(),
+        });
+        depth.unknown = unknown;
+        if unknown.is_some() {
+            depth.exact = false;
+        }
+        match frame {
+            Some(frame) => {
+                depth.size += frame.size;
+                depth.exact &= frame.exact;
+            }
+            None => depth.exact = false,
+        }
+        depth.path.insert(0, (function, frame));
+        self.states
+            .insert(FunctionKey::new(function), State::Done(depth.clone()));
+        depth
+    }
+}
+
+/// Print the worst case stack depth for each function that matches the filter options.
+///
+/// The depth is the sum of the frame sizes along the deepest path of direct calls.
+/// Recursion and indirect calls result in an unbounded depth. Calls to addresses
+/// that aren't a known function, such as imported functions, and jumps to other
+/// functions, such as tail calls, result in a depth that is only a lower bound.
+pub fn stack_usage(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let hash = FileHash::new(file);
+    let graph = CallGraph::new(&hash);
+    let mut analysis = StackAnalysis {
+        hash: &hash,
+        graph: &graph,
+        states: HashMap::new(),
+    };
+
+    let mut depths = Vec::new();
+    for unit in filter::filter_units(file, options) {
+        for function in filter::filter_functions(unit, options) {
+            if function.address().is_some() {
+                depths.push(analysis.depth(function));
+            }
+        }
+    }
+    depths.sort_by(|a, b| {
+        if a.is_worse(b) {
+            cmp::Ordering::Less
+        } else if b.is_worse(a) {
+            cmp::Ordering::Greater
+        } else {
+            cmp::Ordering::Equal
+        }
+    });
+
+    for depth in depths {
+        match depth.unbounded {
+            Some(_) => write!(w, "[unbounded]")?,
+            None if depth.exact => write!(w, "[{}]", depth.size)?,
+            None => write!(w, "[>={}]", depth.size)?,
+        }
+        write!(w, "\t")?;
+        for (i, (function, frame)) in depth.path.iter().enumerate() {
+            if i != 0 {
+                write!(w, " -> ")?;
+            }
+            write!(w, "{}", function_name(function))?;
+            match frame {
+                Some(frame) if frame.exact => write!(w, "[{}]", frame.size)?,
+                Some(frame) => write!(w, "[>={}]", frame.size)?,
+                None if depth.unbounded == Some(Unbounded::Recursion)
+                    && i + 1 == depth.path.len() => {}
+                None => write!(w, "[??]")?,
+            }
+        }
+        match depth.unbounded {
+            Some(Unbounded::Recursion) => write!(w, " (recursion)")?,
+            Some(Unbounded::IndirectCall) => write!(w, " (indirect call)")?,
+            None => match depth.unknown {
+                Some((LowerBound::UnknownCall, function)) => write!(
+                    w,
+                    " (call to unknown function from {})",
+                    function_name(function)
+                )?,
+                Some((LowerBound::TailCall, function)) => {
+                    write!(w, " (tail call from {})", function_name(function))?
+                }
+                None => {}
+            },
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
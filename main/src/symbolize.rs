@@ -0,0 +1,154 @@
+use std::io::Write;
+
+use parser::{AddressLocation, File, FileHash, Function, Namespace, Source, Unit};
+
+use crate::json;
+use crate::{Options, Result};
+
+/// Print the containing function, inlined functions and source location
+/// for each address.
+pub fn symbolize(
+    file: &File,
+    addresses: &[u64],
+    w: &mut dyn Write,
+    options: &Options,
+) -> Result<()> {
+    let hash = FileHash::new(file);
+    if options.json {
+        write!(w, "[")?;
+        for (i, address) in addresses.iter().enumerate() {
+            if i != 0 {
+                write!(w, ",")?;
+            }
+            writeln!(w)?;
+            let location = file.find_location(&hash, *address);
+            print_json(*address, location.as_ref(), w)?;
+        }
+        writeln!(w, "\n]")?;
+    } else {
+        for address in addresses {
+            let location = file.find_location(&hash, *address);
+            print_text(*address, location.as_ref(), w)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_text(address: u64, location: Option<&AddressLocation>, w: &mut dyn Write) -> Result<()> {
+    write!(w, "0x{:x}", address)?;
+    let location = match location {
+        Some(location) => location,
+        None => {
+            writeln!(w, " ??")?;
+            return Ok(());
+        }
+    };
+    let outer = location.frames().last();
+    match outer.and_then(|frame| frame.function()) {
+        Some(function) => {
+            write!(w, " {}", function_name(function))?;
+            if let Some(offset) = function_offset(function, address) {
+                write!(w, "+0x{:x}", offset)?;
+            }
+        }
+        None => write!(w, " ??")?,
+    }
+    writeln!(w)?;
+
+    let len = location.frames().len();
+    for (i, frame) in location.frames().iter().enumerate() {
+        write!(w, "\t")?;
+        if i + 1 != len {
+            write!(w, "inlined ")?;
+        }
+        match frame.function() {
+            Some(function) => write!(w, "{}", function_name(function))?,
+            None => write!(w, "??")?,
+        }
+        if let Some(source) = source_string(frame.source(), location.unit()) {
+            write!(w, " at {}", source)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+fn print_json(address: u64, location: Option<&AddressLocation>, w: &mut dyn Write) -> Result<()> {
+    write!(w, "{{\"address\":\"0x{:x}\"", address)?;
+    let location = match location {
+        Some(location) => location,
+        None => {
+            write!(w, "}}")?;
+            return Ok(());
+        }
+    };
+    if let Some(function) = location.frames().last().and_then(|frame| frame.function()) {
+        write!(w, ",\"function\":")?;
+        json::write_str(w, &function_name(function))?;
+        if let Some(offset) = function_offset(function, address) {
+            write!(w, ",\"offset\":{}", offset)?;
+        }
+    }
+    if let Some(source) = location.source() {
+        write!(w, ",\"source\":")?;
+        print_json_source(source, location.unit(), w)?;
+    }
+    write!(w, ",\"frames\":[")?;
+    let len = location.frames().len();
+    for (i, frame) in location.frames().iter().enumerate() {
+        if i != 0 {
+            write!(w, ",")?;
+        }
+        let name = frame.function().map(function_name);
+        write!(w, "{{\"function\":")?;
+        json::write_opt_str(w, name.as_ref().map(String::as_str))?;
+        write!(w, ",\"inlined\":{}", i + 1 != len)?;
+        write!(w, ",\"source\":")?;
+        print_json_source(frame.source(), location.unit(), w)?;
+        write!(w, "}}")?;
+    }
+    write!(w, "]}}")?;
+    Ok(())
+}
+
+fn print_json_source(source: &Source, unit: &Unit, w: &mut dyn Write) -> Result<()> {
+    write!(w, "{{\"path\":")?;
+    json::write_opt_str(w, source.path(unit).as_ref().map(String::as_str))?;
+    write!(
+        w,
+        ",\"line\":{},\"column\":{}}}",
+        source.line(),
+        source.column()
+    )?;
+    Ok(())
+}
+
+pub(crate) fn source_string(source: &Source, unit: &Unit) -> Option<String> {
+    source.path(unit).map(|mut path| {
+        if source.line() != 0 {
+            path.push_str(&format!(":{}", source.line()));
+            if source.column() != 0 {
+                path.push_str(&format!(":{}", source.column()));
+            }
+        }
+        path
+    })
+}
+
+fn function_offset(function: &Function, address: u64) -> Option<u64> {
+    function.address().map(|begin| address - begin)
+}
+
+pub(crate) fn function_name(function: &Function) -> String {
+    let name = function
+        .name()
+        .or_else(|| function.symbol_name())
+        .unwrap_or("<anon>");
+    Namespace::qualified_name(function.namespace(), name)
+}
+
+// The qualified name of a type, prefixed by its tag, such as `struct a::b`.
+pub(crate) fn tagged_name(tag: &str, namespace: Option<&Namespace>, name: Option<&str>) -> String {
+    let name = Namespace::qualified_name(namespace, name.unwrap_or("<anon>"));
+    format!("{} {}", tag, name)
+}
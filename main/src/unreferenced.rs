@@ -0,0 +1,144 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use parser::{Architecture, File, Namespace, RelocationKind, SymbolKind};
+
+use crate::code::Code;
+use crate::filter;
+use crate::symbolize::function_name;
+use crate::{Options, Result};
+
+/// Print the functions that match the filter options and are never referenced.
+///
+/// A function is referenced if it is the target of a direct call or an
+/// address operand in another function, if it is exported in the dynamic
+/// symbol table, if it is the entry point, if it is the target of an
+/// absolute or dynamic relocation, or if its address is stored in an aligned
+/// pointer in a data section. References from the function itself are ignored.
+///
+/// Every immediate operand and every pointer sized value in data is treated
+/// as a possible reference, so the report errs toward treating functions as
+/// referenced, and may miss functions whose address happens to match a constant.
+///
+/// The functions are grouped by unit and namespace, with their total size.
+pub fn unreferenced(file: &File, w: &mut dyn Write, options: &Options) -> Result<()> {
+    let references = references(file);
+
+    let mut total = 0;
+    for unit in filter::filter_units(file, options) {
+        let mut namespaces = BTreeMap::new();
+        for function in filter::filter_functions(unit, options) {
+            let (address, size) = match (function.address(), function.size()) {
+                (Some(address), Some(size)) => (address, size),
+                _ => continue,
+            };
+            if references.contains(&address) {
+                continue;
+            }
+            // Remove the trailing `::`.
+            let mut namespace = Namespace::qualified_name(function.namespace(), "");
+            namespace.truncate(namespace.len().saturating_sub(2));
+            namespaces
+                .entry(namespace)
+                .or_insert_with(Vec::new)
+                .push((function_name(function), size));
+        }
+        if namespaces.is_empty() {
+            continue;
+        }
+
+        let unit_size: u64 = namespaces
+            .values()
+            .flat_map(|functions| functions.iter().map(|x| x.1))
+            .sum();
+        total += unit_size;
+        writeln!(
+            w,
+            "unit {} [{}]",
+            unit.name().unwrap_or("<anon>"),
+            unit_size
+        )?;
+        for (namespace, mut functions) in namespaces {
+            let namespace_size: u64 = functions.iter().map(|x| x.1).sum();
+            if namespace.is_empty() {
+                writeln!(w, "\tnamespace <global> [{}]", namespace_size)?;
+            } else {
+                writeln!(w, "\tnamespace {} [{}]", namespace, namespace_size)?;
+            }
+            functions.sort();
+            for (name, size) in functions {
+                writeln!(w, "\t\t[{}]\t{}", size, name)?;
+            }
+        }
+    }
+    writeln!(w, "total [{}]", total)?;
+    Ok(())
+}
+
+// Return the addresses that are referenced from outside of the function
+// containing them.
+fn references(file: &File) -> HashSet<u64> {
+    let mut references = HashSet::new();
+    if let Some(code) = Code::new(file) {
+        for unit in file.units() {
+            for function in unit.functions() {
+                let range = match function.range() {
+                    Some(range) => range,
+                    None => continue,
+                };
+                for address in code.references(range) {
+                    if !range.contains(address) {
+                        references.insert(address);
+                    }
+                }
+            }
+        }
+        // Scan data for code pointers, such as constructors in `.init_array`,
+        // vtables and function pointer tables. Position dependent executables
+        // don't have relocations for these unless linked with `--emit-relocs`.
+        for section in file.sections() {
+            if let (Some(name), Some(range)) = (section.name(), section.address()) {
+                if is_data_section(name) {
+                    references.extend(code.pointers(range));
+                }
+            }
+        }
+    }
+    for symbol in file.dynamic_symbols() {
+        if symbol.kind() == SymbolKind::Function {
+            references.insert(symbol.address().begin);
+        }
+    }
+    for relocation in file.relocations().iter().chain(file.dynamic_relocations()) {
+        if relocation.kind() == RelocationKind::Absolute {
+            references.insert(relocation.value());
+        }
+    }
+    references.insert(file.entry());
+
+    if let Architecture::Arm(_) = file.machine() {
+        // The address of a Thumb function has the low bit set.
+        references = references.into_iter().map(|address| address & !1).collect();
+    }
+    references
+}
+
+// Return true if the section may contain pointers to functions.
+fn is_data_section(name: &str) -> bool {
+    // ELF sections, and Mach-O `__const`, `__data`, `__mod_init_func` and `__mod_term_func`.
+    [
+        ".data",
+        ".rodata",
+        ".init_array",
+        ".fini_array",
+        ".preinit_array",
+        ".ctors",
+        ".dtors",
+        "__const",
+        "__data",
+        "__mod_init_func",
+        "__mod_term_func",
+    ]
+    .iter()
+    .any(|prefix| name.starts_with(prefix))
+}
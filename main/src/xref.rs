@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use parser::{FileHash, Function, Type, TypeKind, TypeModifierKind, Variable};
+
+use crate::call_graph::FunctionKey;
+use crate::code::Code;
+
+/// A key that identifies a variable.
+///
+/// Variables from the symbol table don't have a debuginfo offset, so this
+/// uses the address of the `Variable` within the file instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct VariableKey(usize);
+
+impl VariableKey {
+    fn new(variable: &Variable) -> Self {
+        VariableKey(variable as *const Variable as usize)
+    }
+}
+
+/// An access to a global variable by the machine code of a function.
+#[derive(Debug, Clone, Copy)]
+pub struct VariableAccess<'input> {
+    function: &'input Function<'input>,
+    variable: &'input Variable<'input>,
+    address: u64,
+    offset: u64,
+}
+
+impl<'input> VariableAccess<'input> {
+    /// The function containing the instruction.
+    #[inline]
+    pub fn function(&self) -> &'input Function<'input> {
+        self.function
+    }
+
+    /// The variable that is accessed.
+    #[inline]
+    pub fn variable(&self) -> &'input Variable<'input> {
+        self.variable
+    }
+
+    /// The address of the instruction.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The offset of the accessed address within the variable.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The member of the variable that is accessed, such as `.flags` or `[2]`.
+    ///
+    /// This is empty if the access is to the start of the variable.
+    pub fn member(&self, hash: &FileHash) -> String {
+        member_path(hash, self.variable, self.offset)
+    }
+}
+
+/// The accesses to global variables by all functions in a file.
+///
+/// Accesses are found by disassembling the code of each function, and looking
+/// up the addresses of memory operands, immediates and relocations.
+#[derive(Debug, Default)]
+pub struct VariableAccesses<'input> {
+    accesses: Vec<VariableAccess<'input>>,
+    by_function: HashMap<FunctionKey, Vec<usize>>,
+    by_variable: HashMap<VariableKey, Vec<usize>>,
+}
+
+impl<'input> VariableAccesses<'input> {
+    /// Find the variable accesses for the file of the given `FileHash`.
+    pub fn new(hash: &'input FileHash<'input>) -> Self {
+        let code = Code::new(hash.file);
+        VariableAccesses::with_code(hash, code.as_ref())
+    }
+
+    pub(crate) fn with_code(hash: &'input FileHash<'input>, code: Option<&Code>) -> Self {
+        let code = match code {
+            Some(code) => code,
+            None => return VariableAccesses::default(),
+        };
+        let mut accesses = Vec::new();
+        for unit in hash.file.units() {
+            for function in unit.functions() {
+                let range = match function.range() {
+                    Some(range) => range,
+                    None => continue,
+                };
+                for data_ref in code.data_refs(range) {
+                    if let Some((variable, offset)) = hash.find_variable(data_ref.to) {
+                        accesses.push(VariableAccess {
+                            function,
+                            variable,
+                            address: data_ref.from,
+                            offset,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut by_function = HashMap::new();
+        let mut by_variable = HashMap::new();
+        for (index, access) in accesses.iter().enumerate() {
+            by_function
+                .entry(FunctionKey::new(access.function))
+                .or_insert_with(Vec::new)
+                .push(index);
+            by_variable
+                .entry(VariableKey::new(access.variable))
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+        VariableAccesses {
+            accesses,
+            by_function,
+            by_variable,
+        }
+    }
+
+    /// All variable accesses in the file.
+    #[inline]
+    pub fn list(&self) -> &[VariableAccess<'input>] {
+        &self.accesses
+    }
+
+    /// The variable accesses made by the given function.
+    pub fn accesses<'a>(
+        &'a self,
+        function: &Function,
+    ) -> impl Iterator<Item = &'a VariableAccess<'input>> + 'a {
+        self.lookup(self.by_function.get(&FunctionKey::new(function)))
+    }
+
+    /// The accesses of the given variable.
+    pub fn accessed_by<'a>(
+        &'a self,
+        variable: &Variable,
+    ) -> impl Iterator<Item = &'a VariableAccess<'input>> + 'a {
+        self.lookup(self.by_variable.get(&VariableKey::new(variable)))
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        indices: Option<&'a Vec<usize>>,
+    ) -> impl Iterator<Item = &'a VariableAccess<'input>> + 'a {
+        indices
+            .into_iter()
+            .flatten()
+            .map(move |&index| &self.accesses[index])
+    }
+}
+
+/// The path to the member at the given offset within a variable.
+///
+/// Struct members are appended as `.name` and array elements as `[index]`.
+/// Any remaining offset that can't be resolved is appended as `+0x..`.
+pub(crate) fn member_path(hash: &FileHash, variable: &Variable, offset: u64) -> String {
+    match variable.ty(hash) {
+        Some(ty) => type_member_path(hash, &ty, offset),
+        None if offset != 0 => format!("+0x{:x}", offset),
+        None => String::new(),
+    }
+}
+
+/// The path to the member at the given offset within a type.
+pub(crate) fn type_member_path(hash: &FileHash, ty: &Type, offset: u64) -> String {
+    let mut path = String::new();
+    let offset = append_member_path(hash, ty, offset, &mut path);
+    if offset != 0 {
+        write!(path, "+0x{:x}", offset).unwrap();
+    }
+    path
+}
+
+// Append the path to the member at the given offset, and return the offset
+// within that member.
+fn append_member_path(hash: &FileHash, ty: &Type, offset: u64, path: &mut String) -> u64 {
+    match ty.kind() {
+        TypeKind::Def(def) => {
+            if let Some(ty) = def.ty(hash) {
+                return append_member_path(hash, &ty, offset, path);
+            }
+        }
+        TypeKind::Modifier(modifier) => match modifier.kind() {
+            TypeModifierKind::Pointer
+            | TypeModifierKind::Reference
+            | TypeModifierKind::RvalueReference => {}
+            _ => {
+                if let Some(ty) = modifier.ty(hash) {
+                    return append_member_path(hash, &ty, offset, path);
+                }
+            }
+        },
+        TypeKind::Struct(struct_type) => {
+            let bit_offset = offset * 8;
+            for member in struct_type.members() {
+                let bit_size = match member.bit_size(hash) {
+                    Some(bit_size) if bit_size != 0 => bit_size,
+                    _ => continue,
+                };
+                if bit_offset < member.bit_offset() || bit_offset >= member.bit_offset() + bit_size
+                {
+                    continue;
+                }
+                // Anonymous members are transparent.
+                if let Some(name) = member.name() {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                let offset = offset - member.bit_offset() / 8;
+                return match member.ty(hash) {
+                    Some(ty) => append_member_path(hash, &ty, offset, path),
+                    None => offset,
+                };
+            }
+        }
+        TypeKind::Array(array) => {
+            if let Some(ty) = array.element_type(hash) {
+                if let Some(size) = ty.byte_size(hash).filter(|size| *size != 0) {
+                    write!(path, "[{}]", offset / size).unwrap();
+                    return append_member_path(hash, &ty, offset % size, path);
+                }
+            }
+        }
+        // TODO: unions are ambiguous, but we could choose a member using the access size.
+        _ => {}
+    }
+    offset
+}
@@ -0,0 +1,28 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+#[test]
+fn compile() {
+    let options = ddbug::Options::default();
+    let mut header = Vec::new();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::c_header(file, &mut header, &options)
+    })
+    .unwrap();
+
+    let path = env::temp_dir().join(format!("ddbug-c-header-{}.h", std::process::id()));
+    fs::write(&path, &header).unwrap();
+    let status = Command::new("cc")
+        .args(&["-std=c11", "-fsyntax-only", "-x", "c"])
+        .arg(&path)
+        .status();
+    fs::remove_file(&path).unwrap();
+    match status {
+        Ok(status) => assert!(status.success(), "{}", String::from_utf8_lossy(&header)),
+        // Skip the check if there is no C compiler.
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => panic!("{}", e),
+    }
+}
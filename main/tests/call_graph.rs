@@ -0,0 +1,91 @@
+fn function<'a>(file: &'a ddbug::File, name: &str) -> &'a parser::Function<'a> {
+    file.units()
+        .iter()
+        .flat_map(|unit| unit.functions())
+        .find(|function| function.name() == Some(name))
+        .unwrap()
+}
+
+fn names<'a, I>(functions: I) -> Vec<String>
+where
+    I: Iterator<Item = &'a parser::Function<'a>>,
+{
+    functions
+        .map(|function| function.name().unwrap_or("").to_string())
+        .collect()
+}
+
+#[test]
+fn callers() {
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        let hash = parser::FileHash::new(file);
+        let graph = ddbug::CallGraph::new(&hash);
+
+        // These functions are only in the symbol table.
+        let callers = graph.callers(function(file, "_init"));
+        assert_eq!(
+            names(callers.map(|edge| edge.caller())),
+            vec!["__libc_csu_init"]
+        );
+        let callers = graph.callers(function(file, "deregister_tm_clones"));
+        assert_eq!(
+            names(callers.map(|edge| edge.caller())),
+            vec!["__do_global_dtors_aux"]
+        );
+        let calls = graph.calls(function(file, "__libc_csu_init"));
+        assert_eq!(names(calls.map(|edge| edge.callee())), vec!["_init"]);
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn dot() {
+    let options = ddbug::Options {
+        filter_name: Some("__do_global_dtors_aux"),
+        call_graph_depth: 1,
+        ..Default::default()
+    };
+    let mut dot = Vec::new();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::call_graph(file, &mut dot, &options)
+    })
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(dot).unwrap(),
+        "digraph calls {\n\
+         \x20   node [shape=box];\n\
+         \x20   f0 [label=\"__do_global_dtors_aux\"];\n\
+         \x20   f1 [label=\"deregister_tm_clones\"];\n\
+         \x20   f0 -> f1;\n\
+         }\n"
+    );
+}
+
+fn reach(sources: &[&str], targets: &[&str]) -> String {
+    let options = ddbug::Options::default();
+    let mut out = Vec::new();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::reach(file, sources, targets, &mut out, &options)
+    })
+    .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn reach_import() {
+    assert_eq!(reach(&[], &["__libc_start_main"]), "_start\n");
+    assert_eq!(
+        reach(&["_start"], &["__libc_start_main"]),
+        "_start -> __libc_start_main\n"
+    );
+}
+
+#[test]
+fn reach_source() {
+    assert_eq!(reach(&["_init"], &["_init"]), "_init\n");
+    assert_eq!(
+        reach(&["__libc_csu_init"], &["_init"]),
+        "__libc_csu_init -> _init\n"
+    );
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+#[test]
+fn compile() {
+    let options = ddbug::Options::default();
+    let mut bindings = Vec::new();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::rust_bindings(file, &mut bindings, &options)
+    })
+    .unwrap();
+
+    let dir = env::temp_dir().join(format!("ddbug-rust-bindings-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bindings.rs");
+    fs::write(&path, &bindings).unwrap();
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let status = Command::new(rustc)
+        .args(&[
+            "--edition",
+            "2018",
+            "--crate-type",
+            "lib",
+            "--emit",
+            "metadata",
+        ])
+        .arg("--out-dir")
+        .arg(&dir)
+        .arg(&path)
+        .status();
+    fs::remove_dir_all(&dir).unwrap();
+    match status {
+        Ok(status) => assert!(status.success(), "{}", String::from_utf8_lossy(&bindings)),
+        // Skip the check if rustc isn't in the path.
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => panic!("{}", e),
+    }
+}
@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+
+#[test]
+fn round_trip() {
+    let path = env::temp_dir().join(format!("ddbug-snapshot-{}", std::process::id()));
+    let path = path.to_str().unwrap();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        let mut snapshot = Vec::new();
+        ddbug::save_snapshot(file, &mut snapshot)?;
+        fs::write(path, &snapshot)?;
+        Ok(())
+    })
+    .unwrap();
+
+    let options = ddbug::Options {
+        print_source: true,
+        print_function_calls: true,
+        print_function_variables: true,
+        print_variable_locations: true,
+        inline_depth: 1,
+
+        category_unit: true,
+        category_type: true,
+        category_function: true,
+        category_variable: true,
+        ..Default::default()
+    };
+    let mut diff = Vec::new();
+    let result = ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::File::parse(path, |snapshot| {
+            let mut printer = ddbug::TextPrinter::new(&mut diff, &options);
+            ddbug::diff(&mut printer, file, snapshot, &options)
+        })
+    });
+    fs::remove_file(path).unwrap();
+    result.unwrap();
+    assert_eq!(String::from_utf8(diff).unwrap(), "");
+}
+
+fn save(input: &str, name: &str) -> String {
+    let path = env::temp_dir().join(format!("ddbug-{}-{}", name, std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    ddbug::File::parse(input, |file| {
+        let mut snapshot = Vec::new();
+        ddbug::save_snapshot(file, &mut snapshot)?;
+        fs::write(&path, &snapshot)?;
+        Ok(())
+    })
+    .unwrap();
+    path
+}
+
+fn calls(path: &str) -> usize {
+    let mut len = 0;
+    ddbug::File::parse(path, |file| {
+        len = file.calls().len();
+        Ok(())
+    })
+    .unwrap();
+    len
+}
+
+#[test]
+fn resave() {
+    let path_a = save("tests/bin/diff1", "resave-a");
+    let path_b = save(&path_a, "resave-b");
+    let calls_a = calls(&path_a);
+    let calls_b = calls(&path_b);
+    fs::remove_file(path_a).unwrap();
+    fs::remove_file(path_b).unwrap();
+    assert_ne!(calls_a, 0);
+    assert_eq!(calls_a, calls_b);
+}
+
+#[test]
+fn unsupported_options() {
+    let path = save("tests/bin/diff1", "unsupported");
+    let options = ddbug::Options {
+        print_function_instructions: true,
+        category_function: true,
+        ..Default::default()
+    };
+    let mut diff = Vec::new();
+    let result = ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::File::parse(&path, |snapshot| {
+            let mut printer = ddbug::TextPrinter::new(&mut diff, &options);
+            ddbug::diff(&mut printer, file, snapshot, &options)
+        })
+    });
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+}
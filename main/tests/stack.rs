@@ -0,0 +1,25 @@
+fn stack_usage(name: &str) -> String {
+    let options = ddbug::Options {
+        filter_name: Some(name),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        ddbug::stack_usage(file, &mut out, &options)
+    })
+    .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn symbol_calls() {
+    // Both functions are only in the symbol table, so this must not be
+    // reported as recursion.
+    let out = stack_usage("__do_global_dtors_aux");
+    assert!(
+        out.contains("__do_global_dtors_aux") && out.contains(" -> deregister_tm_clones"),
+        "{}",
+        out
+    );
+    assert!(!out.contains("(recursion)"), "{}", out);
+}
@@ -0,0 +1,27 @@
+use std::ptr;
+
+#[test]
+fn symbol_accesses() {
+    ddbug::File::parse("tests/bin/diff1", |file| {
+        let hash = parser::FileHash::new(file);
+        let accesses = ddbug::VariableAccesses::new(&hash);
+        assert!(!accesses.list().is_empty());
+
+        // Functions and variables that are only in the symbol table must not
+        // share their accesses.
+        for unit in file.units() {
+            for function in unit.functions() {
+                for access in accesses.accesses(function) {
+                    assert!(ptr::eq(access.function(), function));
+                }
+            }
+            for variable in unit.variables() {
+                for access in accesses.accessed_by(variable) {
+                    assert!(ptr::eq(access.variable(), variable));
+                }
+            }
+        }
+        Ok(())
+    })
+    .unwrap();
+}
@@ -8,6 +8,7 @@ pub type Cfi = (Address, CfiDirective);
 
 /// A CFI directive.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CfiDirective {
     /// .cfi_startproc
     StartProc,
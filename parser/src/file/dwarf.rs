@@ -10,10 +10,11 @@ use gimli::Reader as GimliReader;
 use object::{self, ObjectSection};
 
 use crate::cfi::{Cfi, CfiDirective};
-use crate::file::{Architecture, DebugInfo, FileHash, StringCache};
+use crate::file::{register, Architecture, DebugInfo, FileHash, SectionAddresses, StringCache};
 use crate::function::{
     Function, FunctionDetails, FunctionOffset, InlinedFunction, Parameter, ParameterOffset,
 };
+use crate::line::Line;
 use crate::location::{Location, Piece, Register};
 use crate::namespace::{Namespace, NamespaceKind};
 use crate::range::Range;
@@ -34,6 +35,7 @@ fn add_relocations<'input, 'file, Object>(
     relocations: &mut RelocationMap,
     file: &'file Object,
     section: &Object::Section,
+    addresses: &SectionAddresses,
 ) where
     Object: object::Object<'input, 'file>,
 {
@@ -46,7 +48,7 @@ fn add_relocations<'input, 'file, Object>(
         let target = match relocation.target() {
             object::RelocationTarget::Symbol(index) => {
                 if let Some(symbol) = file.symbol_by_index(index) {
-                    symbol.address()
+                    addresses.symbol(&symbol)
                 } else {
                     println!(
                         "Relocation with invalid symbol index {} for section {} at offset 0x{:08x}",
@@ -59,7 +61,7 @@ fn add_relocations<'input, 'file, Object>(
             }
             object::RelocationTarget::Section(index) => {
                 if let Some(section) = file.section_by_index(index) {
-                    section.address()
+                    addresses.section(&section)
                 } else {
                     println!(
                         "Relocation with invalid section index {} for section {} at offset 0x{:08x}",
@@ -86,7 +88,7 @@ fn add_relocations<'input, 'file, Object>(
             object::RelocationKind::Relative => {
                 let addend = target
                     .wrapping_add(relocation.addend() as u64)
-                    .wrapping_sub(section.address())
+                    .wrapping_sub(addresses.section(section))
                     .wrapping_sub(offset as u64);
                 relocation.set_addend(addend as i64);
                 if relocations.insert(offset, relocation).is_some() {
@@ -345,8 +347,10 @@ where
         })
     }
 
-    pub(crate) fn get_cfi(&self, address: Address, size: Size) -> Vec<Cfi> {
-        self.frame.get_cfi(address, size).unwrap_or_default()
+    pub(crate) fn get_cfi(&self, machine: Architecture, address: Address, size: Size) -> Vec<Cfi> {
+        self.frame
+            .get_cfi(machine, address, size)
+            .unwrap_or_default()
     }
 
     pub(crate) fn get_register_name(
@@ -354,16 +358,31 @@ where
         machine: Architecture,
         register: Register,
     ) -> Option<&'static str> {
-        let register_name = match machine {
-            Architecture::Arm(_) => gimli::Arm::register_name,
-            Architecture::I386 => gimli::X86::register_name,
-            Architecture::X86_64 => gimli::X86_64::register_name,
-            _ => return None,
-        };
-        register_name(gimli::Register(register.0))
+        register_name(machine, register)
     }
 }
 
+pub(crate) fn register_name(machine: Architecture, register: Register) -> Option<&'static str> {
+    let register_name = match machine {
+        Architecture::Arm(_) => gimli::Arm::register_name,
+        Architecture::I386 => gimli::X86::register_name,
+        Architecture::X86_64 => gimli::X86_64::register_name,
+        Architecture::Aarch64(_) => return register::aarch64_name(register),
+        Architecture::Mips
+        | Architecture::Mipsel
+        | Architecture::Mips64
+        | Architecture::Mips64el => return register::mips_name(register),
+        Architecture::Powerpc | Architecture::Powerpc64 | Architecture::Powerpc64le => {
+            return register::powerpc_name(register);
+        }
+        Architecture::Riscv32 | Architecture::Riscv64 => {
+            return register::riscv_name(register);
+        }
+        _ => return None,
+    };
+    register_name(gimli::Register(register.0))
+}
+
 type DwarfUnit<'input, Endian> = gimli::Unit<Reader<'input, Endian>>;
 
 struct DwarfSubprogram<'input> {
@@ -382,6 +401,7 @@ struct DwarfVariable<'input> {
 pub(crate) fn parse<'input, 'file, Endian, Object, Cb>(
     endian: Endian,
     object: &'file Object,
+    addresses: &SectionAddresses,
     strings: &'input StringCache,
     cb: Cb,
 ) -> Result<()>
@@ -395,7 +415,7 @@ where
         let mut relocations = RelocationMap::default();
         let data = match object.section_by_name(id.name()) {
             Some(ref section) => {
-                add_relocations(&mut relocations, object, section);
+                add_relocations(&mut relocations, object, section, addresses);
                 section.uncompressed_data()
             }
             None => Cow::Borrowed(&[][..]),
@@ -419,13 +439,13 @@ where
     let eh_frame = get_section(gimli::SectionId::EhFrame)?;
     let mut bases = gimli::BaseAddresses::default();
     if let Some(section) = object.section_by_name(".eh_frame") {
-        bases = bases.set_eh_frame(section.address());
+        bases = bases.set_eh_frame(addresses.section(&section));
     }
     if let Some(section) = object.section_by_name(".text") {
-        bases = bases.set_text(section.address());
+        bases = bases.set_text(addresses.section(&section));
     }
     if let Some(section) = object.section_by_name(".got") {
-        bases = bases.set_got(section.address());
+        bases = bases.set_got(addresses.section(&section));
     }
     let frame = DwarfFrame::new(
         get_reader(&debug_frame).into(),
@@ -521,8 +541,16 @@ where
     if let Some(program) = dwarf_unit.line_program.clone() {
         let mut rows = program.rows();
         let mut seq_addr = None;
-        while let Some((_, row)) = rows.next_row()? {
+        let mut prev_line: Option<Line> = None;
+        while let Some((header, row)) = rows.next_row()? {
             let addr = row.address();
+            if let Some(mut line) = prev_line.take() {
+                // Rows within a sequence should be in increasing address order.
+                if seq_addr != Some(0) && line.range.begin < addr {
+                    line.range.end = addr;
+                    unit.lines.push(line);
+                }
+            }
             if row.end_sequence() {
                 if let Some(seq_addr) = seq_addr {
                     // Sequences starting at 0 are probably invalid.
@@ -535,8 +563,17 @@ where
                     }
                 }
                 seq_addr = None;
-            } else if seq_addr.is_none() {
-                seq_addr = Some(addr);
+            } else {
+                if seq_addr.is_none() {
+                    seq_addr = Some(addr);
+                }
+                prev_line = Some(Line {
+                    range: Range {
+                        begin: addr,
+                        end: addr,
+                    },
+                    source: parse_line_source(dwarf, &dwarf_unit, header, row),
+                });
             }
         }
     } else if let Some(offset) = ranges {
@@ -565,6 +602,7 @@ where
         }
     }
     unit.ranges.sort();
+    unit.lines.sort_by(|a, b| a.range.begin.cmp(&b.range.begin));
 
     let namespace = None;
     parse_namespace_children(
@@ -590,6 +628,41 @@ where
     Ok(unit)
 }
 
+fn parse_line_source<'input, Endian>(
+    dwarf: &DwarfDebugInfo<'input, Endian>,
+    dwarf_unit: &DwarfUnit<'input, Endian>,
+    header: &gimli::LineProgramHeader<Reader<'input, Endian>>,
+    row: &gimli::LineRow,
+) -> Source<'input>
+where
+    Endian: gimli::Endianity,
+{
+    let mut source = Source::default();
+    if let Some(entry) = row.file(header) {
+        source.file = dwarf.string(dwarf_unit, entry.path_name());
+        if let Some(directory) = entry.directory(header) {
+            source.directory = dwarf.string(dwarf_unit, directory);
+        }
+    } else {
+        debug!("invalid line row file index {}", row.file_index());
+    }
+    if let Some(line) = row.line() {
+        if line <= u64::from(u32::MAX) {
+            source.line = line as u32;
+        } else {
+            debug!("large line row line: {}", line);
+        }
+    }
+    if let gimli::ColumnType::Column(column) = row.column() {
+        if column <= u64::from(u32::MAX) {
+            source.column = column as u32;
+        } else {
+            debug!("large line row column: {}", column);
+        }
+    }
+    source
+}
+
 #[inline(never)]
 fn fixup_subprogram_specifications<'input, Endian>(
     unit: &mut Unit<'input>,
@@ -951,7 +1024,7 @@ where
             TypeKind::Subrange(parse_subrange_type(dwarf, dwarf_unit, node)?)
         }
         gimli::DW_TAG_subroutine_type => {
-            TypeKind::Function(parse_subroutine_type(dwarf, dwarf_unit, node)?)
+            TypeKind::Function(parse_subroutine_type(unit, dwarf, dwarf_unit, node)?)
         }
         gimli::DW_TAG_ptr_to_member_type => {
             TypeKind::PointerToMember(parse_pointer_to_member_type(dwarf, dwarf_unit, node)?)
@@ -2014,6 +2087,7 @@ where
 }
 
 fn parse_subroutine_type<'input, 'abbrev, 'unit, 'tree, Endian>(
+    unit: &Unit<'input>,
     dwarf: &DwarfDebugInfo<'input, Endian>,
     dwarf_unit: &DwarfUnit<'input, Endian>,
     node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, Reader<'input, Endian>>,
@@ -2025,6 +2099,14 @@ where
         // Go treats subroutine types as pointers.
         // Not sure if this is valid for all languages.
         byte_size: Size::new(u64::from(dwarf_unit.header.address_size())),
+        // Only C has functions without prototypes.
+        prototyped: match unit.language {
+            Some(gimli::DW_LANG_C89)
+            | Some(gimli::DW_LANG_C)
+            | Some(gimli::DW_LANG_C99)
+            | Some(gimli::DW_LANG_C11) => false,
+            _ => true,
+        },
         ..Default::default()
     };
 
@@ -2036,7 +2118,12 @@ where
                     function.return_type = offset;
                 }
             }
-            gimli::DW_AT_name | gimli::DW_AT_prototyped | gimli::DW_AT_sibling => {}
+            gimli::DW_AT_prototyped => {
+                if let gimli::AttributeValue::Flag(flag) = attr.value() {
+                    function.prototyped |= flag;
+                }
+            }
+            gimli::DW_AT_name | gimli::DW_AT_sibling => {}
             _ => debug!(
                 "unknown subroutine attribute: {} {:?}",
                 attr.name(),
@@ -2051,6 +2138,9 @@ where
             gimli::DW_TAG_formal_parameter => {
                 parse_parameter_type(&mut function.parameters, dwarf, dwarf_unit, child)?;
             }
+            gimli::DW_TAG_unspecified_parameters => {
+                function.variadic = true;
+            }
             tag => {
                 debug!("unknown subroutine child tag: {}", tag);
             }
@@ -2169,15 +2259,18 @@ where
         source: Source::default(),
         address: Address::none(),
         size: Size::none(),
+        ranges: Vec::new(),
         inline: false,
         declaration: false,
         parameters: Vec::new(),
         return_type: TypeOffset::none(),
+        frame_base: None,
     };
 
     let mut specification = None;
     let mut abstract_origin = false;
     let mut high_pc = None;
+    let mut ranges = None;
 
     let entry = node.entry();
     let mut attrs = entry.attrs();
@@ -2218,6 +2311,11 @@ where
                 }
                 _ => {}
             },
+            gimli::DW_AT_ranges => {
+                if let gimli::AttributeValue::RangeListsRef(val) = attr.value() {
+                    ranges = Some(val);
+                }
+            }
             gimli::DW_AT_type => {
                 if let Some(offset) = parse_type_offset(dwarf_unit, &attr) {
                     function.return_type = offset;
@@ -2234,9 +2332,12 @@ where
                     function.declaration = flag;
                 }
             }
-            gimli::DW_AT_frame_base => {
-                // FIXME
-            }
+            gimli::DW_AT_frame_base => match attr.value() {
+                gimli::AttributeValue::Exprloc(expr) => {
+                    function.frame_base = evaluate_frame_base(&dwarf_unit.header, expr);
+                }
+                _ => debug!("unknown DW_AT_frame_base: {:?}", attr.value()),
+            },
             gimli::DW_AT_external
             | gimli::DW_AT_GNU_all_call_sites
             | gimli::DW_AT_GNU_all_tail_call_sites
@@ -2267,6 +2368,18 @@ where
         }
     }
 
+    if let Some(offset) = ranges {
+        let mut ranges = dwarf.read.ranges(dwarf_unit, offset)?;
+        while let Some(range) = ranges.next()? {
+            if range.begin < range.end {
+                function.ranges.push(range.into());
+            }
+        }
+        function.ranges.sort();
+    } else if let Some(range) = function.range() {
+        function.ranges.push(range);
+    }
+
     if let Some(specification) = specification {
         subprograms.push(DwarfSubprogram {
             offset,
@@ -2944,12 +3057,23 @@ where
         let mut ranges = dwarf.read.ranges(dwarf_unit, offset)?;
         while let Some(range) = ranges.next()? {
             size += range.end.wrapping_sub(range.begin);
+            function.ranges.push(range.into());
         }
         function.size = Size::new(size);
     } else if let Some(size) = size {
         function.size = Size::new(size);
+        if let Some(low_pc) = low_pc {
+            function.ranges.push(Range {
+                begin: low_pc,
+                end: low_pc.wrapping_add(size),
+            });
+        }
     } else if let (Some(low_pc), Some(high_pc)) = (low_pc, high_pc) {
         function.size = Size::new(high_pc.wrapping_sub(low_pc));
+        function.ranges.push(Range {
+            begin: low_pc,
+            end: high_pc,
+        });
     } else {
         debug!("unknown inlined_subroutine size");
     }
@@ -3339,6 +3463,27 @@ fn evaluate_parameter_location<'input, Endian>(
         .extend(pieces.into_iter().map(|piece| (range, piece)));
 }
 
+fn evaluate_frame_base<'input, Endian>(
+    unit: &gimli::UnitHeader<Reader<'input, Endian>>,
+    expression: gimli::Expression<Reader<'input, Endian>>,
+) -> Option<Location>
+where
+    Endian: gimli::Endianity,
+{
+    let pieces = match evaluate_simple(unit, expression, false) {
+        Ok(pieces) => pieces,
+        Err(e) => {
+            debug!("frame base evaluation failed: {}", e);
+            return None;
+        }
+    };
+    if pieces.len() != 1 || pieces[0].is_value {
+        debug!("unsupported DW_AT_frame_base: {:?}", pieces);
+        return None;
+    }
+    Some(pieces[0].location)
+}
+
 fn evaluate_simple<'input, Endian>(
     unit: &gimli::UnitHeader<Reader<'input, Endian>>,
     expression: gimli::Expression<Reader<'input, Endian>>,
@@ -3892,11 +4037,11 @@ impl<R: gimli::Reader<Offset = usize>> DwarfFrame<R> {
         }
     }
 
-    fn get_cfi(&self, address: Address, size: Size) -> Option<Vec<Cfi>> {
+    fn get_cfi(&self, machine: Architecture, address: Address, size: Size) -> Option<Vec<Cfi>> {
         let cfi = self
             .eh_frame
-            .get_cfi(address, size)
-            .or_else(|| self.debug_frame.get_cfi(address, size));
+            .get_cfi(machine, address, size)
+            .or_else(|| self.debug_frame.get_cfi(machine, address, size));
         if cfi.is_none() {
             debug!(
                 "no FDE for 0x{:x}[0x{:x}]",
@@ -3925,8 +4070,15 @@ impl<R: gimli::Reader<Offset = usize>> DebugFrameTable<R> {
         }
     }
 
-    fn get_cfi(&self, address: Address, size: Size) -> Option<Vec<Cfi>> {
-        get_cfi(&self.debug_frame, &self.bases, &self.fdes, address, size)
+    fn get_cfi(&self, machine: Architecture, address: Address, size: Size) -> Option<Vec<Cfi>> {
+        get_cfi(
+            &self.debug_frame,
+            &self.bases,
+            &self.fdes,
+            machine,
+            address,
+            size,
+        )
     }
 }
 
@@ -3946,8 +4098,15 @@ impl<R: gimli::Reader<Offset = usize>> EhFrameTable<R> {
         }
     }
 
-    fn get_cfi(&self, address: Address, size: Size) -> Option<Vec<Cfi>> {
-        get_cfi(&self.eh_frame, &self.bases, &self.fdes, address, size)
+    fn get_cfi(&self, machine: Architecture, address: Address, size: Size) -> Option<Vec<Cfi>> {
+        get_cfi(
+            &self.eh_frame,
+            &self.bases,
+            &self.fdes,
+            machine,
+            address,
+            size,
+        )
     }
 }
 
@@ -4009,6 +4168,7 @@ fn get_cfi<R: gimli::Reader, S: gimli::UnwindSection<R>>(
     section: &S,
     bases: &gimli::BaseAddresses,
     fdes: &FdeOffsetTable,
+    machine: Architecture,
     address: Address,
     size: Size,
 ) -> Option<Vec<Cfi>>
@@ -4055,6 +4215,12 @@ where
     }
 
     let cie = fde.cie();
+    // Only show the return column if it isn't the default for the architecture.
+    let return_column: Register = cie.return_address_register().into();
+    if register::return_address(machine) != Some(return_column) {
+        cfi.push((Address::none(), CfiDirective::ReturnColumn(return_column)));
+    }
+
     let mut address = 0;
     let mut instructions = cie.instructions(section, bases);
     while let Ok(Some(instruction)) = instructions.next() {
@@ -1,9 +1,16 @@
 use std::borrow::Cow;
+use std::cmp;
+use std::convert::TryInto;
 use std::default::Default;
 use std::fs;
 use std::ops::Deref;
+use std::ptr;
 
 mod dwarf;
+mod register;
+mod snapshot;
+
+pub use self::snapshot::Call;
 
 use fnv::FnvHashMap as HashMap;
 use gimli;
@@ -14,6 +21,7 @@ use typed_arena::Arena;
 
 use crate::cfi::Cfi;
 use crate::function::{Function, FunctionDetails, FunctionOffset};
+use crate::line::{AddressLocation, InlineFrame};
 use crate::location::Register;
 use crate::range::{Range, RangeList};
 use crate::types::{Enumerator, Type, TypeOffset};
@@ -26,6 +34,7 @@ where
     Endian: gimli::Endianity + 'input,
 {
     Dwarf(&'input dwarf::DwarfDebugInfo<'input, Endian>),
+    Snapshot(&'input snapshot::SnapshotDebugInfo<'input>),
 }
 
 impl<'input, Endian> DebugInfo<'input, Endian>
@@ -35,12 +44,14 @@ where
     fn get_type(&self, offset: TypeOffset) -> Option<Type<'input>> {
         match self {
             DebugInfo::Dwarf(dwarf) => dwarf.get_type(offset),
+            DebugInfo::Snapshot(snapshot) => snapshot.get_type(offset),
         }
     }
 
     fn get_enumerators(&self, offset: TypeOffset) -> Vec<Enumerator<'input>> {
         match self {
             DebugInfo::Dwarf(dwarf) => dwarf.get_enumerators(offset),
+            DebugInfo::Snapshot(snapshot) => snapshot.get_enumerators(offset),
         }
     }
 
@@ -51,18 +62,22 @@ where
     ) -> Option<FunctionDetails<'input>> {
         match self {
             DebugInfo::Dwarf(dwarf) => dwarf.get_function_details(offset, hash),
+            DebugInfo::Snapshot(snapshot) => snapshot.get_function_details(offset),
         }
     }
 
-    fn get_cfi(&self, address: Address, size: Size) -> Vec<Cfi> {
+    fn get_cfi(&self, machine: Architecture, address: Address, size: Size) -> Vec<Cfi> {
         match self {
-            DebugInfo::Dwarf(dwarf) => dwarf.get_cfi(address, size),
+            DebugInfo::Dwarf(dwarf) => dwarf.get_cfi(machine, address, size),
+            // Snapshots don't contain call frame information.
+            DebugInfo::Snapshot(_) => Vec::new(),
         }
     }
 
     fn get_register_name(&self, machine: Architecture, register: Register) -> Option<&'static str> {
         match self {
             DebugInfo::Dwarf(dwarf) => dwarf.get_register_name(machine, register),
+            DebugInfo::Snapshot(_) => dwarf::register_name(machine, register),
         }
     }
 }
@@ -90,14 +105,325 @@ impl StringCache {
 
 pub use object::target_lexicon::Architecture;
 
+// `object` only recognizes a few ELF machine types, so check for others here.
+fn elf_architecture(data: &[u8]) -> Option<Architecture> {
+    const EM_PPC: u16 = 20;
+    const EM_PPC64: u16 = 21;
+    const EM_RISCV: u16 = 243;
+
+    if !data.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let is_64 = *data.get(4)? == 2;
+    let is_big_endian = *data.get(5)? == 2;
+    let machine = [*data.get(18)?, *data.get(19)?];
+    let machine = if is_big_endian {
+        u16::from_be_bytes(machine)
+    } else {
+        u16::from_le_bytes(machine)
+    };
+    match machine {
+        EM_PPC => Some(Architecture::Powerpc),
+        EM_PPC64 if is_big_endian => Some(Architecture::Powerpc64),
+        EM_PPC64 => Some(Architecture::Powerpc64le),
+        EM_RISCV if is_64 => Some(Architecture::Riscv64),
+        EM_RISCV => Some(Architecture::Riscv32),
+        _ => None,
+    }
+}
+
+/// Options that control how a file is parsed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    /// Assign non-overlapping addresses to the sections of relocatable objects.
+    ///
+    /// All sections in relocatable objects have an address of 0, so code in
+    /// different sections can't be told apart by address. When this is set, each
+    /// loadable section is instead given an address starting from 0x1000, and the
+    /// addresses of symbols, relocations, functions and variables are adjusted to
+    /// match. These addresses do not appear in the file itself.
+    pub section_addresses: bool,
+}
+
+/// The address of each loadable section in a file.
+///
+/// Sections are only assigned addresses if `ParseOptions::section_addresses`
+/// is set and the file is a relocatable object. These start at a non-zero
+/// address because an address of 0 is treated as unknown.
+#[derive(Debug, Default)]
+pub(crate) struct SectionAddresses {
+    addresses: HashMap<usize, u64>,
+}
+
+impl SectionAddresses {
+    fn new<'input, 'file, Object>(object: &'file Object, options: &ParseOptions) -> Self
+    where
+        Object: object::Object<'input, 'file>,
+    {
+        let mut addresses = HashMap::default();
+        if options.section_addresses && object.segments().next().is_none() {
+            let mut address = 0x1000;
+            for section in object.sections() {
+                match section.kind() {
+                    object::SectionKind::Text
+                    | object::SectionKind::Data
+                    | object::SectionKind::ReadOnlyData
+                    | object::SectionKind::ReadOnlyString
+                    | object::SectionKind::UninitializedData
+                    | object::SectionKind::Tls
+                    | object::SectionKind::UninitializedTls => {}
+                    _ => continue,
+                }
+                let align = cmp::max(section.align(), 1);
+                address = (address + align - 1) / align * align;
+                addresses.insert(section.index().0, address);
+                address += section.size();
+            }
+        }
+        SectionAddresses { addresses }
+    }
+
+    /// The address of the given section.
+    pub(crate) fn section<'input, Section>(&self, section: &Section) -> u64
+    where
+        Section: ObjectSection<'input>,
+    {
+        match self.addresses.get(&section.index().0) {
+            Some(address) => *address,
+            None => section.address(),
+        }
+    }
+
+    /// The address of the given symbol.
+    pub(crate) fn symbol(&self, symbol: &object::Symbol) -> u64 {
+        let section = symbol
+            .section_index()
+            .and_then(|index| self.addresses.get(&index.0));
+        match section {
+            Some(address) => address.wrapping_add(symbol.address()),
+            None => symbol.address(),
+        }
+    }
+}
+
+fn parse_symbol<'input>(
+    symbol: &object::Symbol<'input>,
+    addresses: &SectionAddresses,
+) -> Option<Symbol<'input>> {
+    let address = addresses.symbol(symbol);
+    if address == 0 {
+        return None;
+    }
+
+    let size = symbol.size();
+    if size == 0 {
+        return None;
+    }
+
+    // TODO: handle SymbolKind::File
+    let kind = match symbol.kind() {
+        object::SymbolKind::Text => SymbolKind::Function,
+        object::SymbolKind::Data | object::SymbolKind::Unknown => SymbolKind::Variable,
+        _ => return None,
+    };
+
+    let name = symbol.name();
+
+    Some(Symbol {
+        name,
+        kind,
+        address,
+        size,
+    })
+}
+
+fn parse_mapping_symbol<'input, 'file, Object>(
+    object: &'file Object,
+    symbol: &object::Symbol,
+    addresses: &SectionAddresses,
+) -> Option<MappingSymbol>
+where
+    Object: object::Object<'input, 'file>,
+{
+    let name = symbol.name()?;
+    let kind = match name.get(..2) {
+        Some("$a") => MappingKind::Arm,
+        Some("$t") => MappingKind::Thumb,
+        Some("$d") => MappingKind::Data,
+        _ => return None,
+    };
+    // The name may have a suffix, such as `$t.1`.
+    if name.len() > 2 && !name[2..].starts_with('.') {
+        return None;
+    }
+    // Sections in relocatable objects may all have an address of 0, so the
+    // sequences must be kept separate for each section.
+    let section_index = symbol.section_index()?;
+    let section = object.section_by_index(section_index)?;
+    let section_address = addresses.section(&section);
+    Some(MappingSymbol {
+        address: addresses.symbol(symbol),
+        section: section_index.0,
+        section_range: Range {
+            begin: section_address,
+            end: section_address + section.size(),
+        },
+        kind,
+    })
+}
+
+fn is_debug_section(name: &str) -> bool {
+    name.starts_with(".debug_") || name.starts_with(".zdebug_") || name.starts_with("__debug_")
+}
+
+fn add_relocations<'input, 'file, Object>(
+    relocations: &mut Vec<Relocation<'input>>,
+    file: &'file Object,
+    section: &Object::Section,
+    addresses: &SectionAddresses,
+) where
+    Object: object::Object<'input, 'file>,
+{
+    let data = section.data();
+    for (offset, relocation) in section.relocations() {
+        let kind = match relocation.kind() {
+            object::RelocationKind::Absolute => RelocationKind::Absolute,
+            object::RelocationKind::Relative | object::RelocationKind::PltRelative => {
+                RelocationKind::Relative
+            }
+            _ => continue,
+        };
+        let (symbol, target) = match relocation.target() {
+            object::RelocationTarget::Symbol(index) => match file.symbol_by_index(index) {
+                // Section symbols don't have a useful name.
+                Some(symbol) => (
+                    symbol.name().filter(|name| {
+                        !name.is_empty() && symbol.kind() != object::SymbolKind::Section
+                    }),
+                    addresses.symbol(&symbol),
+                ),
+                None => continue,
+            },
+            object::RelocationTarget::Section(index) => match file.section_by_index(index) {
+                Some(section) => (None, addresses.section(&section)),
+                None => continue,
+            },
+        };
+        let size = relocation.size();
+        let mut addend = relocation.addend();
+        if relocation.has_implicit_addend() {
+            let bytes = match data
+                .get(offset as usize..)
+                .and_then(|x| x.get(..size as usize / 8))
+            {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+            let implicit = match (size, file.is_little_endian()) {
+                (32, true) => i64::from(i32::from_le_bytes(bytes.try_into().unwrap())),
+                (32, false) => i64::from(i32::from_be_bytes(bytes.try_into().unwrap())),
+                (64, true) => i64::from_le_bytes(bytes.try_into().unwrap()),
+                (64, false) => i64::from_be_bytes(bytes.try_into().unwrap()),
+                _ => continue,
+            };
+            addend = addend.wrapping_add(implicit);
+        }
+        relocations.push(Relocation {
+            address: addresses.section(section).wrapping_add(offset),
+            size,
+            kind,
+            symbol,
+            target,
+            addend,
+        });
+    }
+}
+
+// The object crate only returns the relocations for sections that are
+// the target of a relocation section, which doesn't include most dynamic
+// relocations, so parse the ELF relocation entries directly.
+fn add_dynamic_relocations<'input>(
+    relocations: &mut Vec<Relocation<'input>>,
+    data: &[u8],
+    is_rela: bool,
+    is_64: bool,
+    little_endian: bool,
+    symbols: &HashMap<usize, (Option<&'input str>, u64)>,
+    segments: &[Segment<'input>],
+) {
+    let word = if is_64 { 8 } else { 4 };
+    let read = |bytes: &[u8]| read_word(bytes, little_endian).unwrap_or(0);
+    let entry_size = if is_rela { 3 * word } else { 2 * word };
+    for entry in data.chunks_exact(entry_size) {
+        let address = read(&entry[..word]);
+        let info = read(&entry[word..][..word]);
+        let symbol = if is_64 { info >> 32 } else { info >> 8 } as usize;
+        let addend = if is_rela {
+            let addend = read(&entry[2 * word..][..word]);
+            if is_64 {
+                addend as i64
+            } else {
+                i64::from(addend as u32 as i32)
+            }
+        } else if symbol == 0 {
+            // Relative relocations store the addend in the relocated value.
+            segments
+                .iter()
+                .filter(|segment| address >= segment.address)
+                .filter_map(|segment| {
+                    let offset = (address - segment.address) as usize;
+                    segment.bytes.get(offset..)?.get(..word)
+                })
+                .next()
+                .and_then(|bytes| read_word(bytes, little_endian))
+                .unwrap_or(0) as i64
+        } else {
+            0
+        };
+        let (symbol, target) = if symbol == 0 {
+            (None, 0)
+        } else {
+            match symbols.get(&symbol) {
+                Some(symbol) => *symbol,
+                None => continue,
+            }
+        };
+        relocations.push(Relocation {
+            address,
+            size: word as u8 * 8,
+            kind: RelocationKind::Absolute,
+            symbol,
+            target,
+            addend,
+        });
+    }
+}
+
+fn read_word(bytes: &[u8], little_endian: bool) -> Option<u64> {
+    match (bytes.len(), little_endian) {
+        (4, true) => Some(u64::from(u32::from_le_bytes(bytes.try_into().unwrap()))),
+        (4, false) => Some(u64::from(u32::from_be_bytes(bytes.try_into().unwrap()))),
+        (8, true) => Some(u64::from_le_bytes(bytes.try_into().unwrap())),
+        (8, false) => Some(u64::from_be_bytes(bytes.try_into().unwrap())),
+        _ => None,
+    }
+}
+
 /// The parsed debuginfo for a single file.
 pub struct File<'input> {
     pub(crate) path: &'input str,
     pub(crate) machine: Architecture,
+    pub(crate) little_endian: bool,
     pub(crate) segments: Vec<Segment<'input>>,
     pub(crate) sections: Vec<Section<'input>>,
     pub(crate) symbols: Vec<Symbol<'input>>,
+    pub(crate) dynamic_symbols: Vec<Symbol<'input>>,
+    pub(crate) mapping_symbols: Vec<MappingSymbol>,
+    pub(crate) relocations: Vec<Relocation<'input>>,
+    pub(crate) dynamic_relocations: Vec<Relocation<'input>>,
+    pub(crate) entry: u64,
     pub(crate) units: Vec<Unit<'input>>,
+    pub(crate) calls: Vec<Call<'input>>,
     debug_info: DebugInfo<'input, gimli::RunTimeEndian>,
 }
 
@@ -121,7 +447,7 @@ impl<'input> File<'input> {
     }
 
     pub(crate) fn get_cfi(&self, address: Address, size: Size) -> Vec<Cfi> {
-        self.debug_info.get_cfi(address, size)
+        self.debug_info.get_cfi(self.machine, address, size)
     }
 
     pub(crate) fn get_register_name(&self, register: Register) -> Option<&'static str> {
@@ -130,9 +456,23 @@ impl<'input> File<'input> {
 
     /// Parse the file with the given path.
     ///
+    /// The path may be either an object file or a snapshot that was written by
+    /// `File::write_snapshot`.
+    ///
     /// `cb` is a callback function that is called with the parsed File.
     /// It requires a callback so that memory management is simplified.
     pub fn parse<Cb>(path: &str, cb: Cb) -> Result<()>
+    where
+        Cb: FnOnce(&File) -> Result<()>,
+    {
+        File::parse_with_options(path, &ParseOptions::default(), cb)
+    }
+
+    /// Parse the file with the given path and options.
+    ///
+    /// This is the same as `File::parse`, except that `options` controls how
+    /// object files are parsed. Snapshots are not affected by the options.
+    pub fn parse_with_options<Cb>(path: &str, options: &ParseOptions, cb: Cb) -> Result<()>
     where
         Cb: FnOnce(&File) -> Result<()>,
     {
@@ -150,10 +490,19 @@ impl<'input> File<'input> {
             }
         };
 
+        if snapshot::is_snapshot(&*map) {
+            return snapshot::parse(&*map, path, cb);
+        }
+
         let object = object::File::parse(&*map)?;
 
+        let machine = match object.architecture() {
+            Architecture::Unknown => elf_architecture(&*map).unwrap_or(Architecture::Unknown),
+            machine => machine,
+        };
+
         if object.has_debug_symbols() {
-            File::parse_object(&object, &object, path, cb)
+            File::parse_object(&object, &object, machine, options, path, cb)
         } else {
             let debug_path = match moria::locate_debug_symbols(&object, path) {
                 Ok(debug_path) => debug_path,
@@ -177,7 +526,7 @@ impl<'input> File<'input> {
             };
 
             let debug_object = object::File::parse(&*map)?;
-            File::parse_object(&object, &debug_object, path, cb)
+            File::parse_object(&object, &debug_object, machine, options, path, cb)
         }
         /*
         let input = &*map;
@@ -192,13 +541,16 @@ impl<'input> File<'input> {
     fn parse_object<Cb>(
         object: &object::File,
         debug_object: &object::File,
+        machine: Architecture,
+        options: &ParseOptions,
         path: &str,
         cb: Cb,
     ) -> Result<()>
     where
         Cb: FnOnce(&File) -> Result<()>,
     {
-        let machine = object.architecture();
+        let little_endian = object.is_little_endian();
+        let addresses = SectionAddresses::new(object, options);
         let mut segments = Vec::new();
         for segment in object.segments() {
             segments.push(Segment {
@@ -206,16 +558,24 @@ impl<'input> File<'input> {
                 bytes: segment.data(),
             });
         }
+        if segments.is_empty() {
+            // Relocatable objects don't have segments, so use the sections instead.
+            for section in object.sections() {
+                if addresses.addresses.contains_key(&section.index().0) {
+                    segments.push(Segment {
+                        address: addresses.section(&section),
+                        bytes: section.data(),
+                    });
+                }
+            }
+        }
 
         let mut sections = Vec::new();
         for section in object.sections() {
             let name = section.name().map(|x| Cow::Owned(x.to_string()));
             let segment = section.segment_name().map(|x| Cow::Owned(x.to_string()));
-            let address = if section.address() != 0 {
-                Some(section.address())
-            } else {
-                None
-            };
+            let address = addresses.section(&section);
+            let address = if address != 0 { Some(address) } else { None };
             let size = section.size();
             if size != 0 {
                 sections.push(Section {
@@ -230,58 +590,108 @@ impl<'input> File<'input> {
         // TODO: symbols from debug_object too?
         let mut symbols = Vec::new();
         for (_, symbol) in object.symbols() {
-            // TODO: handle relocatable objects
-            let address = symbol.address();
-            if address == 0 {
-                continue;
+            if let Some(symbol) = parse_symbol(&symbol, &addresses) {
+                symbols.push(symbol);
             }
+        }
+
+        let mut dynamic_symbols = Vec::new();
+        for (_, symbol) in object.dynamic_symbols() {
+            if let Some(symbol) = parse_symbol(&symbol, &addresses) {
+                dynamic_symbols.push(symbol);
+            }
+        }
 
-            let size = symbol.size();
-            if size == 0 {
+        let mut mapping_symbols = Vec::new();
+        if let Architecture::Arm(_) = machine {
+            for (_, symbol) in object.symbols() {
+                if let Some(symbol) = parse_mapping_symbol(object, &symbol, &addresses) {
+                    mapping_symbols.push(symbol);
+                }
+            }
+        }
+
+        let mut relocations = Vec::new();
+        for section in object.sections() {
+            if section.name().map(is_debug_section) != Some(false) {
                 continue;
             }
+            add_relocations(&mut relocations, object, &section, &addresses);
+        }
 
-            // TODO: handle SymbolKind::File
-            let kind = match symbol.kind() {
-                object::SymbolKind::Text => SymbolKind::Function,
-                object::SymbolKind::Data | object::SymbolKind::Unknown => SymbolKind::Variable,
+        let mut dynamic_symbols_by_index = HashMap::default();
+        for (index, symbol) in object.dynamic_symbols() {
+            dynamic_symbols_by_index.insert(index.0, (symbol.name(), symbol.address()));
+        }
+        let mut dynamic_relocations = Vec::new();
+        for section in object.sections() {
+            let is_rela = match section.name() {
+                Some(".rela.dyn") | Some(".rela.plt") => true,
+                Some(".rel.dyn") | Some(".rel.plt") => false,
                 _ => continue,
             };
-
-            let name = symbol.name();
-
-            symbols.push(Symbol {
-                name,
-                kind,
-                address,
-                size,
-            });
+            add_dynamic_relocations(
+                &mut dynamic_relocations,
+                &section.data(),
+                is_rela,
+                object.is_64(),
+                little_endian,
+                &dynamic_symbols_by_index,
+                &segments,
+            );
         }
 
+        let entry = object.entry();
+
         let endian = if debug_object.is_little_endian() {
             gimli::RunTimeEndian::Little
         } else {
             gimli::RunTimeEndian::Big
         };
 
+        // The section indices of a separate debug file may differ.
+        let debug_addresses = if std::ptr::eq(object, debug_object) {
+            addresses
+        } else {
+            SectionAddresses::default()
+        };
+
         let strings = &StringCache::new();
-        dwarf::parse(endian, debug_object, strings, |units, debug_info| {
-            let mut file = File {
-                path,
-                machine,
-                segments,
-                sections,
-                symbols,
-                units,
-                debug_info,
-            };
-            file.normalize();
-            cb(&file)
-        })
+        dwarf::parse(
+            endian,
+            debug_object,
+            &debug_addresses,
+            strings,
+            |units, debug_info| {
+                let mut file = File {
+                    path,
+                    machine,
+                    little_endian,
+                    segments,
+                    sections,
+                    symbols,
+                    dynamic_symbols,
+                    mapping_symbols,
+                    relocations,
+                    dynamic_relocations,
+                    entry,
+                    units,
+                    calls: Vec::new(),
+                    debug_info,
+                };
+                file.normalize();
+                cb(&file)
+            },
+        )
     }
 
     fn normalize(&mut self) {
         self.symbols.sort_by(|a, b| a.address.cmp(&b.address));
+        self.mapping_symbols
+            .sort_by(|a, b| (a.section, a.address).cmp(&(b.section, b.address)));
+        self.relocations.sort_by(|a, b| a.address.cmp(&b.address));
+        self.dynamic_relocations
+            .sort_by(|a, b| a.address.cmp(&b.address));
         let mut used_symbols = vec![false; self.symbols.len()];
 
         // Set symbol names on functions/variables.
@@ -335,11 +745,19 @@ impl<'input> File<'input> {
                     });
                 }
                 SymbolKind::Function => {
+                    let mut ranges = Vec::new();
+                    if symbol.size != 0 {
+                        ranges.push(Range {
+                            begin: symbol.address,
+                            end: symbol.address + symbol.size,
+                        });
+                    }
                     unit.functions.push(Function {
                         name: symbol.name,
                         linkage_name: symbol.name,
                         address: Address::new(symbol.address),
                         size: Size::new(symbol.size),
+                        ranges,
                         ..Default::default()
                     });
                 }
@@ -403,6 +821,12 @@ impl<'input> File<'input> {
         self.machine
     }
 
+    /// Return true if the file is little endian.
+    #[inline]
+    pub fn is_little_endian(&self) -> bool {
+        self.little_endian
+    }
+
     /// A list of segments in the file.
     #[inline]
     pub fn segments(&self) -> &[Segment<'input>] {
@@ -415,12 +839,73 @@ impl<'input> File<'input> {
         &self.sections
     }
 
+    /// A list of symbols in the file.
+    #[inline]
+    pub fn symbols(&self) -> &[Symbol<'input>] {
+        &self.symbols
+    }
+
+    /// A list of defined symbols in the dynamic symbol table.
+    ///
+    /// These are the symbols exported by a shared library or executable.
+    #[inline]
+    pub fn dynamic_symbols(&self) -> &[Symbol<'input>] {
+        &self.dynamic_symbols
+    }
+
+    /// A list of the ARM mapping symbols in the file, sorted by section and address.
+    ///
+    /// These mark the start of ARM code, Thumb code, or data within a section.
+    #[inline]
+    pub fn mapping_symbols(&self) -> &[MappingSymbol] {
+        &self.mapping_symbols
+    }
+
+    /// A list of relocations for the loadable sections in the file, sorted by address.
+    #[inline]
+    pub fn relocations(&self) -> &[Relocation<'input>] {
+        &self.relocations
+    }
+
+    /// A list of the dynamic relocations in the file, sorted by address.
+    ///
+    /// These are applied by the dynamic linker, and include the relocations
+    /// for PLT and GOT entries. The target address is 0 for undefined symbols.
+    #[inline]
+    pub fn dynamic_relocations(&self) -> &[Relocation<'input>] {
+        &self.dynamic_relocations
+    }
+
+    /// The address of the entry point, or 0 if there is none.
+    #[inline]
+    pub fn entry(&self) -> u64 {
+        self.entry
+    }
+
     /// A list of compilation units in the file.
     #[inline]
     pub fn units(&self) -> &[Unit<'input>] {
         &self.units
     }
 
+    /// The calls that were recorded when writing a snapshot.
+    ///
+    /// This is empty unless the file was parsed from a snapshot.
+    #[inline]
+    pub fn calls(&self) -> &[Call<'input>] {
+        &self.calls
+    }
+
+    /// Return true if the file was parsed from a snapshot.
+    ///
+    /// Snapshots don't contain any code, line information or CFI.
+    pub fn is_snapshot(&self) -> bool {
+        match self.debug_info {
+            DebugInfo::Snapshot(_) => true,
+            _ => false,
+        }
+    }
+
     /// A list of address ranges covered by the compilation units.
     ///
     /// This includes both `Unit::ranges` and `Unit::unknown_ranges`.
@@ -472,6 +957,56 @@ impl<'input> File<'input> {
         }
         size
     }
+
+    /// Find the source location of an address.
+    ///
+    /// This includes the chain of inlined functions that contain the address.
+    pub fn find_location<'a>(
+        &'a self,
+        hash: &'a FileHash<'input>,
+        address: u64,
+    ) -> Option<AddressLocation<'a, 'input>> {
+        let function = hash.find_function(address);
+        for unit in &self.units {
+            let line = unit.find_line(address);
+            let function = function
+                .filter(|(function_unit, _)| ptr::eq(*function_unit, unit))
+                .map(|(_, function)| function);
+            if line.is_none() && function.is_none() {
+                continue;
+            }
+
+            let mut source = line.map(|line| line.source.clone()).unwrap_or_default();
+            let mut frames = Vec::new();
+            if let Some(function) = function {
+                let details = function.details(hash);
+                let mut inlined_functions = details.inlined_functions();
+                let mut chain = Vec::new();
+                while let Some(inlined_function) = inlined_functions
+                    .iter()
+                    .find(|x| x.ranges().iter().any(|range| range.contains(address)))
+                {
+                    chain.push(inlined_function);
+                    inlined_functions = inlined_function.inlined_functions();
+                }
+                for inlined_function in chain.into_iter().rev() {
+                    frames.push(InlineFrame {
+                        function: inlined_function.abstract_origin(hash),
+                        source,
+                    });
+                    source = inlined_function.call_source().clone();
+                }
+            }
+            frames.push(InlineFrame { function, source });
+
+            return Some(AddressLocation {
+                address,
+                unit,
+                frames,
+            });
+        }
+        None
+    }
 }
 
 /// An index of functions and types within a file.
@@ -484,6 +1019,10 @@ pub struct FileHash<'input> {
     pub functions_by_offset: HashMap<FunctionOffset, &'input Function<'input>>,
     /// All types by offset.
     pub types: HashMap<TypeOffset, &'input Type<'input>>,
+    // The address ranges of all functions, sorted by address.
+    pub(crate) functions_by_range: Vec<(Range, &'input Unit<'input>, &'input Function<'input>)>,
+    // All variables with a known address range, sorted by address.
+    pub(crate) variables_by_address: Vec<(Range, &'input Variable<'input>)>,
     // The type corresponding to `TypeOffset::none()`.
     pub(crate) void: Type<'input>,
 }
@@ -491,12 +1030,99 @@ pub struct FileHash<'input> {
 impl<'input> FileHash<'input> {
     /// Create a new `FileHash` for the given `File`.
     pub fn new(file: &'input File<'input>) -> Self {
-        FileHash {
+        let mut hash = FileHash {
             file,
             functions_by_address: FileHash::functions_by_address(file),
             functions_by_offset: FileHash::functions_by_offset(file),
+            functions_by_range: FileHash::functions_by_range(file),
             types: FileHash::types(file),
+            variables_by_address: Vec::new(),
             void: Type::void(),
+        };
+        // Variable sizes may depend on types, so this must be done last.
+        hash.variables_by_address = hash.variables_by_address();
+        hash
+    }
+
+    /// Returns a list of variables with a known address range, sorted by address.
+    fn variables_by_address(&self) -> Vec<(Range, &'input Variable<'input>)> {
+        let file = self.file;
+        let mut variables = Vec::new();
+        for unit in &file.units {
+            for variable in &unit.variables {
+                if let Some(range) = variable.range(self) {
+                    variables.push((range, variable));
+                }
+            }
+        }
+        variables.sort_by_key(|x| x.0);
+        variables
+    }
+
+    /// Find the variable containing the given address.
+    ///
+    /// Returns the variable and the offset of the address within it.
+    pub fn find_variable(&self, address: u64) -> Option<(&'input Variable<'input>, u64)> {
+        let index = match self
+            .variables_by_address
+            .binary_search_by_key(&address, |x| x.0.begin)
+        {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let (range, variable) = self.variables_by_address[index];
+        if range.contains(address) {
+            Some((variable, address - range.begin))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a list of the address ranges of all functions, sorted by address.
+    ///
+    /// This includes all of the ranges of functions with non-contiguous code.
+    fn functions_by_range<'a>(
+        file: &'a File<'input>,
+    ) -> Vec<(Range, &'a Unit<'input>, &'a Function<'input>)> {
+        let mut functions = Vec::new();
+        for unit in &file.units {
+            for function in &unit.functions {
+                // Not all formats record the ranges of a function.
+                let ranges = match (function.ranges(), function.range()) {
+                    ([], Some(range)) => vec![range],
+                    (ranges, _) => ranges.to_vec(),
+                };
+                for range in ranges {
+                    if range.begin < range.end {
+                        functions.push((range, unit, function));
+                    }
+                }
+            }
+        }
+        functions.sort_by_key(|x| x.0);
+        functions
+    }
+
+    /// Find the function containing the given address, and the unit that
+    /// contains the function.
+    pub fn find_function(
+        &self,
+        address: u64,
+    ) -> Option<(&'input Unit<'input>, &'input Function<'input>)> {
+        let index = match self
+            .functions_by_range
+            .binary_search_by_key(&address, |x| x.0.begin)
+        {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let (range, unit, function) = self.functions_by_range[index];
+        if range.contains(address) {
+            Some((unit, function))
+        } else {
+            None
         }
     }
 
@@ -584,7 +1210,7 @@ impl<'input> Section<'input> {
 }
 
 /// A symbol kind.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SymbolKind {
     /// The symbol is a variable.
     Variable,
@@ -629,3 +1255,126 @@ impl<'input> Symbol<'input> {
         self.size
     }
 }
+
+/// The contents that follow a mapping symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingKind {
+    /// ARM instructions.
+    Arm,
+    /// Thumb instructions.
+    Thumb,
+    /// Data, such as a literal pool.
+    Data,
+}
+
+/// A mapping symbol.
+///
+/// ARM ELF files use these symbols to mark the start of a sequence of ARM
+/// instructions, Thumb instructions, or data within a section.
+#[derive(Debug, Clone, Copy)]
+pub struct MappingSymbol {
+    pub(crate) address: u64,
+    pub(crate) section: usize,
+    pub(crate) section_range: Range,
+    pub(crate) kind: MappingKind,
+}
+
+impl MappingSymbol {
+    /// The address of the start of the sequence.
+    ///
+    /// This may be 0 for the start of a section in a relocatable object.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The index of the section containing the sequence.
+    #[inline]
+    pub fn section_index(&self) -> usize {
+        self.section
+    }
+
+    /// The address range of the section containing the sequence.
+    ///
+    /// The sequence ends at the next mapping symbol in the same section, or
+    /// at the end of the section.
+    #[inline]
+    pub fn section_range(&self) -> Range {
+        self.section_range
+    }
+
+    /// The contents of the sequence.
+    #[inline]
+    pub fn kind(&self) -> MappingKind {
+        self.kind
+    }
+}
+
+/// A relocation kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
+    /// The relocated value is the target address plus the addend.
+    Absolute,
+    /// The relocated value is the target address plus the addend, minus the
+    /// address of the relocation.
+    Relative,
+}
+
+/// A relocation of a value within a loadable section.
+#[derive(Debug, Clone)]
+pub struct Relocation<'input> {
+    pub(crate) address: u64,
+    pub(crate) size: u8,
+    pub(crate) kind: RelocationKind,
+    pub(crate) symbol: Option<&'input str>,
+    pub(crate) target: u64,
+    pub(crate) addend: i64,
+}
+
+impl<'input> Relocation<'input> {
+    /// The address of the value that is relocated.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The size in bits of the value that is relocated.
+    #[inline]
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// The relocation kind.
+    #[inline]
+    pub fn kind(&self) -> RelocationKind {
+        self.kind
+    }
+
+    /// The name of the target symbol, if the target is a symbol.
+    #[inline]
+    pub fn symbol(&self) -> Option<&'input str> {
+        self.symbol
+    }
+
+    /// The address of the target symbol or section.
+    #[inline]
+    pub fn target(&self) -> u64 {
+        self.target
+    }
+
+    /// The addend.
+    ///
+    /// This includes any implicit addend that was stored in the relocated value.
+    #[inline]
+    pub fn addend(&self) -> i64 {
+        self.addend
+    }
+
+    /// The address that the relocated value refers to.
+    ///
+    /// This is the target address plus the addend.
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.target.wrapping_add(self.addend as u64)
+    }
+}
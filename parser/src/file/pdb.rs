@@ -392,6 +392,9 @@ fn parse_procedure<'input>(
                 parameters,
                 return_type,
                 byte_size: None,
+                // TODO: variadic functions
+                variadic: false,
+                prototyped: true,
             }),
         },
     );
@@ -451,6 +454,9 @@ fn parse_member_function<'input>(
                 parameters,
                 return_type,
                 byte_size: None,
+                // TODO: variadic functions
+                variadic: false,
+                prototyped: true,
             }),
         },
     );
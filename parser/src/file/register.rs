@@ -0,0 +1,134 @@
+//! Register names and conventions for architectures that gimli doesn't know about.
+//!
+//! The register numbers are from the DWARF supplement of each psABI.
+
+use object::target_lexicon::Architecture;
+
+use crate::location::Register;
+
+const AARCH64_X: [&str; 32] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp",
+];
+
+const AARCH64_V: [&str; 32] = [
+    "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "v10", "v11", "v12", "v13", "v14",
+    "v15", "v16", "v17", "v18", "v19", "v20", "v21", "v22", "v23", "v24", "v25", "v26", "v27",
+    "v28", "v29", "v30", "v31",
+];
+
+const RISCV_X: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+const RISCV_F: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+const MIPS_R: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp",
+    "ra",
+];
+
+const MIPS_F: [&str; 32] = [
+    "f0", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12", "f13", "f14",
+    "f15", "f16", "f17", "f18", "f19", "f20", "f21", "f22", "f23", "f24", "f25", "f26", "f27",
+    "f28", "f29", "f30", "f31",
+];
+
+const PPC_R: [&str; 32] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "r13", "r14",
+    "r15", "r16", "r17", "r18", "r19", "r20", "r21", "r22", "r23", "r24", "r25", "r26", "r27",
+    "r28", "r29", "r30", "r31",
+];
+
+const PPC_F: [&str; 32] = [
+    "f0", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12", "f13", "f14",
+    "f15", "f16", "f17", "f18", "f19", "f20", "f21", "f22", "f23", "f24", "f25", "f26", "f27",
+    "f28", "f29", "f30", "f31",
+];
+
+const PPC_CR: [&str; 8] = ["cr0", "cr1", "cr2", "cr3", "cr4", "cr5", "cr6", "cr7"];
+
+const PPC_VR: [&str; 32] = [
+    "vr0", "vr1", "vr2", "vr3", "vr4", "vr5", "vr6", "vr7", "vr8", "vr9", "vr10", "vr11", "vr12",
+    "vr13", "vr14", "vr15", "vr16", "vr17", "vr18", "vr19", "vr20", "vr21", "vr22", "vr23", "vr24",
+    "vr25", "vr26", "vr27", "vr28", "vr29", "vr30", "vr31",
+];
+
+fn lookup(names: &[&'static str], base: u16, register: Register) -> Option<&'static str> {
+    register
+        .0
+        .checked_sub(base)
+        .and_then(|index| names.get(index as usize))
+        .cloned()
+}
+
+pub(crate) fn aarch64_name(register: Register) -> Option<&'static str> {
+    match register.0 {
+        0..=31 => lookup(&AARCH64_X, 0, register),
+        32 => Some("pc"),
+        33 => Some("elr_mode"),
+        34 => Some("ra_sign_state"),
+        64..=95 => lookup(&AARCH64_V, 64, register),
+        _ => None,
+    }
+}
+
+pub(crate) fn riscv_name(register: Register) -> Option<&'static str> {
+    match register.0 {
+        0..=31 => lookup(&RISCV_X, 0, register),
+        32..=63 => lookup(&RISCV_F, 32, register),
+        _ => None,
+    }
+}
+
+pub(crate) fn mips_name(register: Register) -> Option<&'static str> {
+    match register.0 {
+        0..=31 => lookup(&MIPS_R, 0, register),
+        32..=63 => lookup(&MIPS_F, 32, register),
+        64 => Some("hi"),
+        65 => Some("lo"),
+        _ => None,
+    }
+}
+
+pub(crate) fn powerpc_name(register: Register) -> Option<&'static str> {
+    match register.0 {
+        0..=31 => lookup(&PPC_R, 0, register),
+        32..=63 => lookup(&PPC_F, 32, register),
+        64 => Some("mq"),
+        65 => Some("lr"),
+        66 => Some("ctr"),
+        68..=75 => lookup(&PPC_CR, 68, register),
+        76 => Some("xer"),
+        77..=108 => lookup(&PPC_VR, 77, register),
+        _ => None,
+    }
+}
+
+/// The register that holds the return address on entry to a function.
+///
+/// This is the default return column for CIEs on the architecture.
+pub(crate) fn return_address(machine: Architecture) -> Option<Register> {
+    let register = match machine {
+        Architecture::Arm(_) => 14,
+        Architecture::Aarch64(_) => 30,
+        Architecture::I386 => 8,
+        Architecture::X86_64 => 16,
+        Architecture::Mips
+        | Architecture::Mipsel
+        | Architecture::Mips64
+        | Architecture::Mips64el => 31,
+        Architecture::Powerpc | Architecture::Powerpc64 | Architecture::Powerpc64le => 65,
+        Architecture::Riscv32 | Architecture::Riscv64 => 1,
+        _ => return None,
+    };
+    Some(Register(register))
+}
@@ -0,0 +1,1237 @@
+// A snapshot contains the parsed debuginfo of a file, but not the contents of any
+// sections. Integers are encoded as LEB128, and strings are length prefixed so
+// that they can be borrowed from the snapshot data when it is parsed.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::io;
+use std::rc::Rc;
+use std::str;
+
+use fnv::FnvHashMap as HashMap;
+use gimli;
+
+use crate::file::{Architecture, DebugInfo, File, FileHash, Section};
+use crate::function::{
+    Function, FunctionDetails, FunctionOffset, InlinedFunction, Parameter, ParameterOffset,
+};
+use crate::location::{Location, Piece, Register};
+use crate::namespace::{Namespace, NamespaceKind};
+use crate::range::{Range, RangeList};
+use crate::source::Source;
+use crate::types::{
+    ArrayType, BaseType, BaseTypeEncoding, Endianity, EnumerationType, Enumerator, FunctionType,
+    Inherit, Member, MemberOffset, ParameterType, PointerToMemberType, StructType, SubrangeType,
+    Type, TypeDef, TypeKind, TypeModifier, TypeModifierKind, TypeOffset, UnionType,
+    UnspecifiedType, Variant, VariantPart,
+};
+use crate::unit::Unit;
+use crate::variable::{LocalVariable, Variable, VariableOffset};
+use crate::{Address, Result, Size};
+
+const MAGIC: &[u8] = b"ddbug-snapshot\0";
+const VERSION: u64 = 1;
+const INVALID: &str = "invalid snapshot";
+
+/// A call instruction that was recorded in a snapshot.
+///
+/// Snapshots don't contain any code, so calls are found when the snapshot
+/// is written.
+#[derive(Debug, Clone)]
+pub struct Call<'input> {
+    /// The address of the call instruction.
+    pub from: u64,
+    /// The address of the called function.
+    pub to: u64,
+    /// The imported symbol, for calls through a PLT stub or GOT entry.
+    pub import: Option<&'input str>,
+}
+
+pub(crate) fn is_snapshot(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub(crate) struct SnapshotDebugInfo<'input> {
+    data: &'input [u8],
+    // Types that aren't in any unit, such as types declared within functions.
+    types: HashMap<TypeOffset, Type<'input>>,
+    enumerators: HashMap<TypeOffset, Vec<Enumerator<'input>>>,
+    // The position of the details of each function, which are parsed when needed.
+    details: HashMap<FunctionOffset, usize>,
+}
+
+impl<'input> SnapshotDebugInfo<'input> {
+    pub(crate) fn get_type(&self, offset: TypeOffset) -> Option<Type<'input>> {
+        self.types.get(&offset).cloned()
+    }
+
+    pub(crate) fn get_enumerators(&self, offset: TypeOffset) -> Vec<Enumerator<'input>> {
+        self.enumerators.get(&offset).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn get_function_details(
+        &self,
+        offset: FunctionOffset,
+    ) -> Option<FunctionDetails<'input>> {
+        let position = *self.details.get(&offset)?;
+        let mut reader = Reader {
+            data: self.data,
+            position,
+        };
+        reader.function_details().ok()
+    }
+}
+
+pub(crate) fn parse<Cb>(data: &[u8], path: &str, cb: Cb) -> Result<()>
+where
+    Cb: FnOnce(&File) -> Result<()>,
+{
+    let mut r = Reader {
+        data,
+        position: MAGIC.len(),
+    };
+    let version = r.uleb()?;
+    if version != VERSION {
+        return Err(format!("unsupported snapshot version {}", version).into());
+    }
+    let machine = r.str()?.parse().unwrap_or(Architecture::Unknown);
+    let little_endian = r.bool()?;
+    let entry = r.uleb()?;
+    let sections = r.vec(Reader::section)?;
+    let mut details = HashMap::default();
+    let units = r.vec(|r| r.unit(&mut details))?;
+    let mut types = HashMap::default();
+    for ty in r.vec(Reader::ty)? {
+        types.insert(ty.offset, ty);
+    }
+    let mut enumerators = HashMap::default();
+    for _ in 0..r.usize()? {
+        let offset = r.type_offset()?;
+        enumerators.insert(offset, r.vec(Reader::enumerator)?);
+    }
+    let calls = r.vec(Reader::call)?;
+
+    let debug_info = SnapshotDebugInfo {
+        data,
+        types,
+        enumerators,
+        details,
+    };
+    let file = File {
+        path,
+        machine,
+        little_endian,
+        segments: Vec::new(),
+        sections,
+        symbols: Vec::new(),
+        dynamic_symbols: Vec::new(),
+        mapping_symbols: Vec::new(),
+        relocations: Vec::new(),
+        dynamic_relocations: Vec::new(),
+        entry,
+        units,
+        calls,
+        debug_info: DebugInfo::Snapshot(&debug_info),
+    };
+    cb(&file)
+}
+
+impl<'input> File<'input> {
+    /// Write a snapshot of the debuginfo for this file.
+    ///
+    /// `hash` must have been created for this file. `calls` are the calls made by
+    /// the functions in the file, since the snapshot will not contain any code.
+    /// The snapshot can be parsed by `File::parse`.
+    pub fn write_snapshot(
+        &self,
+        hash: &FileHash<'input>,
+        calls: &[Call],
+        w: &mut dyn io::Write,
+    ) -> Result<()> {
+        let mut out = Writer::default();
+        out.data.extend_from_slice(MAGIC);
+        out.uleb(VERSION);
+        out.str(&self.machine.to_string());
+        out.bool(self.little_endian);
+        out.uleb(self.entry);
+        out.vec(&self.sections, Writer::section);
+        let mut refs = Vec::new();
+        out.vec(&self.units, |out, unit| out.unit(hash, unit, &mut refs));
+
+        // Types that are only referenced from within functions may not be in any unit.
+        let mut types = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(offset) = refs.pop() {
+            if offset.is_none() || hash.types.contains_key(&offset) || !seen.insert(offset) {
+                continue;
+            }
+            if let Some(ty) = self.get_type(offset) {
+                type_refs(&ty, &mut refs);
+                types.push(ty);
+            }
+        }
+        out.vec(&types, Writer::ty);
+
+        let mut enumerators = Vec::new();
+        let unit_types = self.units.iter().flat_map(|unit| unit.types.iter());
+        for ty in unit_types.chain(types.iter()) {
+            if let TypeKind::Enumeration(ref val) = ty.kind {
+                enumerators.push((val.offset, self.get_enumerators(val.offset)));
+            }
+        }
+        out.vec(&enumerators, |out, (offset, enumerators)| {
+            out.type_offset(*offset);
+            out.vec(enumerators, Writer::enumerator);
+        });
+        out.vec(calls, Writer::call);
+
+        w.write_all(&out.data)?;
+        Ok(())
+    }
+}
+
+fn type_refs(ty: &Type, refs: &mut Vec<TypeOffset>) {
+    match ty.kind {
+        TypeKind::Void | TypeKind::Base(..) | TypeKind::Unspecified(..) => {}
+        TypeKind::Def(ref val) => refs.push(val.ty),
+        TypeKind::Struct(ref val) => {
+            refs.extend(val.members.iter().map(|member| member.ty));
+            for variant_part in &val.variant_parts {
+                for variant in &variant_part.variants {
+                    refs.extend(variant.members.iter().map(|member| member.ty));
+                }
+            }
+            refs.extend(val.inherits.iter().map(|inherit| inherit.ty));
+        }
+        TypeKind::Union(ref val) => refs.extend(val.members.iter().map(|member| member.ty)),
+        TypeKind::Enumeration(ref val) => refs.push(val.ty),
+        TypeKind::Array(ref val) => refs.push(val.ty),
+        TypeKind::Function(ref val) => {
+            refs.extend(val.parameters.iter().map(|parameter| parameter.ty));
+            refs.push(val.return_type);
+        }
+        TypeKind::PointerToMember(ref val) => {
+            refs.push(val.ty);
+            refs.push(val.containing_ty);
+        }
+        TypeKind::Modifier(ref val) => refs.push(val.ty),
+        TypeKind::Subrange(ref val) => refs.push(val.ty),
+    }
+}
+
+fn details_refs(
+    parameters: &[Parameter],
+    variables: &[LocalVariable],
+    inlined_functions: &[InlinedFunction],
+    refs: &mut Vec<TypeOffset>,
+) {
+    refs.extend(parameters.iter().map(|parameter| parameter.ty));
+    refs.extend(variables.iter().map(|variable| variable.ty));
+    for inlined_function in inlined_functions {
+        details_refs(
+            &inlined_function.parameters,
+            &inlined_function.variables,
+            &inlined_function.inlined_functions,
+            refs,
+        );
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, val: u8) {
+        self.data.push(val);
+    }
+
+    fn bool(&mut self, val: bool) {
+        self.u8(val as u8);
+    }
+
+    fn uleb(&mut self, mut val: u64) {
+        loop {
+            let byte = (val & 0x7f) as u8;
+            val >>= 7;
+            if val == 0 {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn sleb(&mut self, mut val: i64) {
+        loop {
+            let byte = (val & 0x7f) as u8;
+            val >>= 7;
+            if (val == 0 && byte & 0x40 == 0) || (val == -1 && byte & 0x40 != 0) {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn usize(&mut self, val: usize) {
+        self.uleb(val as u64);
+    }
+
+    fn str(&mut self, val: &str) {
+        self.usize(val.len());
+        self.data.extend_from_slice(val.as_bytes());
+    }
+
+    fn opt_str(&mut self, val: Option<&str>) {
+        self.bool(val.is_some());
+        if let Some(val) = val {
+            self.str(val);
+        }
+    }
+
+    fn opt_uleb(&mut self, val: Option<u64>) {
+        self.bool(val.is_some());
+        if let Some(val) = val {
+            self.uleb(val);
+        }
+    }
+
+    fn opt_sleb(&mut self, val: Option<i64>) {
+        self.bool(val.is_some());
+        if let Some(val) = val {
+            self.sleb(val);
+        }
+    }
+
+    fn opt_usize(&mut self, val: Option<usize>) {
+        self.opt_uleb(val.map(|val| val as u64));
+    }
+
+    fn address(&mut self, val: Address) {
+        self.opt_uleb(val.get());
+    }
+
+    fn size(&mut self, val: Size) {
+        self.opt_uleb(val.get());
+    }
+
+    fn type_offset(&mut self, val: TypeOffset) {
+        self.opt_usize(val.get());
+    }
+
+    fn vec<T, F>(&mut self, vals: &[T], mut f: F)
+    where
+        F: FnMut(&mut Self, &T),
+    {
+        self.usize(vals.len());
+        for val in vals {
+            f(self, val);
+        }
+    }
+
+    fn section(&mut self, section: &Section) {
+        self.opt_str(section.name());
+        self.opt_str(section.segment());
+        self.opt_uleb(section.address);
+        self.uleb(section.size);
+    }
+
+    fn call(&mut self, call: &Call) {
+        self.uleb(call.from);
+        self.uleb(call.to);
+        self.opt_str(call.import);
+    }
+
+    fn range(&mut self, range: &Range) {
+        self.uleb(range.begin);
+        self.uleb(range.end);
+    }
+
+    fn source(&mut self, source: &Source) {
+        self.opt_str(source.directory);
+        self.opt_str(source.file);
+        self.uleb(u64::from(source.line));
+        self.uleb(u64::from(source.column));
+    }
+
+    fn namespace(&mut self, namespace: Option<&Namespace>) {
+        self.bool(namespace.is_some());
+        if let Some(namespace) = namespace {
+            self.namespace(namespace.parent());
+            self.opt_str(namespace.name);
+            self.namespace_kind(namespace.kind);
+        }
+    }
+
+    fn namespace_kind(&mut self, kind: NamespaceKind) {
+        self.u8(match kind {
+            NamespaceKind::Namespace => 0,
+            NamespaceKind::Function => 1,
+            NamespaceKind::Type => 2,
+        });
+    }
+
+    fn unit<'input>(
+        &mut self,
+        hash: &FileHash<'input>,
+        unit: &Unit<'input>,
+        refs: &mut Vec<TypeOffset>,
+    ) {
+        self.opt_str(unit.dir());
+        self.opt_str(unit.name());
+        self.opt_uleb(unit.language.map(|language| u64::from(language.0)));
+        self.opt_uleb(unit.address_size);
+        self.opt_uleb(unit.low_pc);
+        self.vec(unit.ranges.list(), Writer::range);
+        for ty in &unit.types {
+            type_refs(ty, refs);
+        }
+        self.vec(&unit.types, Writer::ty);
+        self.vec(&unit.functions, |out, function| {
+            out.function(hash, function, refs)
+        });
+        self.vec(&unit.variables, |out, variable| {
+            refs.push(variable.ty);
+            out.variable(variable);
+        });
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        self.type_offset(ty.offset);
+        match ty.kind {
+            TypeKind::Void => self.u8(0),
+            TypeKind::Base(ref val) => {
+                self.u8(1);
+                self.opt_str(val.name);
+                self.size(val.byte_size);
+                self.encoding(val.encoding);
+                self.endianity(val.endianity);
+            }
+            TypeKind::Def(ref val) => {
+                self.u8(2);
+                self.namespace(val.namespace());
+                self.opt_str(val.name);
+                self.type_offset(val.ty);
+                self.source(&val.source);
+            }
+            TypeKind::Struct(ref val) => {
+                self.u8(3);
+                self.namespace(val.namespace());
+                self.opt_str(val.name);
+                self.source(&val.source);
+                self.size(val.byte_size);
+                self.bool(val.declaration);
+                self.vec(&val.members, Writer::member);
+                self.vec(&val.variant_parts, Writer::variant_part);
+                self.vec(&val.inherits, Writer::inherit);
+            }
+            TypeKind::Union(ref val) => {
+                self.u8(4);
+                self.namespace(val.namespace());
+                self.opt_str(val.name);
+                self.source(&val.source);
+                self.size(val.byte_size);
+                self.bool(val.declaration);
+                self.vec(&val.members, Writer::member);
+            }
+            TypeKind::Enumeration(ref val) => {
+                self.u8(5);
+                self.type_offset(val.offset);
+                self.namespace(val.namespace());
+                self.opt_str(val.name);
+                self.source(&val.source);
+                self.bool(val.declaration);
+                self.type_offset(val.ty);
+                self.size(val.byte_size);
+            }
+            TypeKind::Array(ref val) => {
+                self.u8(6);
+                self.type_offset(val.ty);
+                self.size(val.count);
+                self.size(val.byte_size);
+            }
+            TypeKind::Function(ref val) => {
+                self.u8(7);
+                self.vec(&val.parameters, Writer::parameter_type);
+                self.type_offset(val.return_type);
+                self.size(val.byte_size);
+                self.bool(val.variadic);
+                self.bool(val.prototyped);
+            }
+            TypeKind::Unspecified(ref val) => {
+                self.u8(8);
+                self.namespace(val.namespace());
+                self.opt_str(val.name);
+            }
+            TypeKind::PointerToMember(ref val) => {
+                self.u8(9);
+                self.type_offset(val.ty);
+                self.type_offset(val.containing_ty);
+                self.size(val.byte_size);
+                self.opt_uleb(val.address_size);
+            }
+            TypeKind::Modifier(ref val) => {
+                self.u8(10);
+                self.modifier_kind(val.kind);
+                self.type_offset(val.ty);
+                self.opt_str(val.name);
+                self.size(val.byte_size);
+                self.opt_uleb(val.address_size);
+            }
+            TypeKind::Subrange(ref val) => {
+                self.u8(11);
+                self.opt_str(val.name);
+                self.type_offset(val.ty);
+                self.opt_uleb(val.lower);
+                self.opt_uleb(val.upper);
+                self.size(val.byte_size);
+            }
+        }
+    }
+
+    fn encoding(&mut self, encoding: BaseTypeEncoding) {
+        self.u8(match encoding {
+            BaseTypeEncoding::Other => 0,
+            BaseTypeEncoding::Boolean => 1,
+            BaseTypeEncoding::Address => 2,
+            BaseTypeEncoding::Signed => 3,
+            BaseTypeEncoding::SignedChar => 4,
+            BaseTypeEncoding::Unsigned => 5,
+            BaseTypeEncoding::UnsignedChar => 6,
+            BaseTypeEncoding::Float => 7,
+        });
+    }
+
+    fn endianity(&mut self, endianity: Endianity) {
+        self.u8(match endianity {
+            Endianity::Default => 0,
+            Endianity::Big => 1,
+            Endianity::Little => 2,
+        });
+    }
+
+    fn modifier_kind(&mut self, kind: TypeModifierKind) {
+        self.u8(match kind {
+            TypeModifierKind::Pointer => 0,
+            TypeModifierKind::Reference => 1,
+            TypeModifierKind::Const => 2,
+            TypeModifierKind::Packed => 3,
+            TypeModifierKind::Volatile => 4,
+            TypeModifierKind::Restrict => 5,
+            TypeModifierKind::Shared => 6,
+            TypeModifierKind::RvalueReference => 7,
+            TypeModifierKind::Atomic => 8,
+            TypeModifierKind::Other => 9,
+        });
+    }
+
+    fn member(&mut self, member: &Member) {
+        self.opt_usize(member.offset.get());
+        self.opt_str(member.name);
+        self.type_offset(member.ty);
+        self.uleb(member.bit_offset);
+        self.size(member.bit_size);
+    }
+
+    fn variant_part(&mut self, variant_part: &VariantPart) {
+        self.opt_usize(variant_part.discr.get());
+        self.vec(&variant_part.variants, Writer::variant);
+    }
+
+    fn variant(&mut self, variant: &Variant) {
+        self.opt_usize(variant.discr.get());
+        self.opt_uleb(variant.discr_value);
+        self.opt_str(variant.name);
+        self.vec(&variant.members, Writer::member);
+    }
+
+    fn inherit(&mut self, inherit: &Inherit) {
+        self.type_offset(inherit.ty);
+        self.uleb(inherit.bit_offset);
+    }
+
+    fn enumerator(&mut self, enumerator: &Enumerator) {
+        self.opt_str(enumerator.name);
+        self.opt_sleb(enumerator.value);
+    }
+
+    fn parameter_type(&mut self, parameter: &ParameterType) {
+        self.opt_usize(parameter.offset.get());
+        self.opt_str(parameter.name);
+        self.type_offset(parameter.ty);
+    }
+
+    fn function<'input>(
+        &mut self,
+        hash: &FileHash<'input>,
+        function: &Function<'input>,
+        refs: &mut Vec<TypeOffset>,
+    ) {
+        self.opt_usize(function.offset.get());
+        self.namespace(function.namespace());
+        self.opt_str(function.name);
+        self.opt_str(function.linkage_name);
+        self.opt_str(function.symbol_name);
+        self.source(&function.source);
+        self.address(function.address);
+        self.size(function.size);
+        self.vec(&function.ranges, Writer::range);
+        self.bool(function.inline);
+        self.bool(function.declaration);
+        self.vec(&function.parameters, Writer::parameter_type);
+        self.type_offset(function.return_type);
+        self.bool(function.frame_base.is_some());
+        if let Some(ref location) = function.frame_base {
+            self.location(location);
+        }
+        refs.push(function.return_type);
+        refs.extend(function.parameters.iter().map(|parameter| parameter.ty));
+
+        // Details are length prefixed so that they can be skipped until needed.
+        let details = function.details(hash);
+        details_refs(
+            &details.parameters,
+            &details.variables,
+            &details.inlined_functions,
+            refs,
+        );
+        let mut out = Writer::default();
+        out.function_details(&details);
+        self.usize(out.data.len());
+        self.data.extend_from_slice(&out.data);
+    }
+
+    fn function_details(&mut self, details: &FunctionDetails) {
+        self.vec(&details.parameters, Writer::parameter);
+        self.vec(&details.variables, Writer::local_variable);
+        self.vec(&details.inlined_functions, Writer::inlined_function);
+    }
+
+    fn parameter(&mut self, parameter: &Parameter) {
+        self.opt_usize(parameter.offset.get());
+        self.opt_str(parameter.name);
+        self.type_offset(parameter.ty);
+        self.vec(&parameter.locations, Writer::piece);
+    }
+
+    fn local_variable(&mut self, variable: &LocalVariable) {
+        self.opt_usize(variable.offset.get());
+        self.opt_str(variable.name);
+        self.type_offset(variable.ty);
+        self.source(&variable.source);
+        self.address(variable.address);
+        self.size(variable.size);
+        self.vec(&variable.locations, Writer::piece);
+    }
+
+    fn inlined_function(&mut self, function: &InlinedFunction) {
+        self.opt_usize(function.abstract_origin.get());
+        self.size(function.size);
+        self.vec(&function.ranges, Writer::range);
+        self.vec(&function.parameters, Writer::parameter);
+        self.vec(&function.variables, Writer::local_variable);
+        self.vec(&function.inlined_functions, Writer::inlined_function);
+        self.source(&function.call_source);
+    }
+
+    fn variable(&mut self, variable: &Variable) {
+        self.opt_usize(variable.offset.get());
+        self.namespace(variable.namespace());
+        self.opt_str(variable.name);
+        self.opt_str(variable.linkage_name);
+        self.opt_str(variable.symbol_name);
+        self.type_offset(variable.ty);
+        self.source(&variable.source);
+        self.address(variable.address);
+        self.size(variable.size);
+        self.bool(variable.declaration);
+    }
+
+    fn piece(&mut self, &(ref range, ref piece): &(Range, Piece)) {
+        self.range(range);
+        self.uleb(piece.bit_offset);
+        self.size(piece.bit_size);
+        self.location(&piece.location);
+        self.uleb(piece.location_offset);
+        self.bool(piece.is_value);
+    }
+
+    fn location(&mut self, location: &Location) {
+        match *location {
+            Location::Empty => self.u8(0),
+            Location::Literal { value } => {
+                self.u8(1);
+                self.uleb(value);
+            }
+            Location::Register { register } => {
+                self.u8(2);
+                self.uleb(u64::from(register.0));
+            }
+            Location::RegisterOffset { register, offset } => {
+                self.u8(3);
+                self.uleb(u64::from(register.0));
+                self.sleb(offset);
+            }
+            Location::FrameOffset { offset } => {
+                self.u8(4);
+                self.sleb(offset);
+            }
+            Location::CfaOffset { offset } => {
+                self.u8(5);
+                self.sleb(offset);
+            }
+            Location::Address { address } => {
+                self.u8(6);
+                self.address(address);
+            }
+            Location::TlsOffset { offset } => {
+                self.u8(7);
+                self.uleb(offset);
+            }
+            Location::Other => self.u8(8),
+        }
+    }
+}
+
+struct Reader<'input> {
+    data: &'input [u8],
+    position: usize,
+}
+
+impl<'input> Reader<'input> {
+    fn bytes(&mut self, len: usize) -> Result<&'input [u8]> {
+        let data = self.data;
+        let end = self.position.checked_add(len).ok_or(INVALID)?;
+        let bytes = data.get(self.position..end).ok_or(INVALID)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn uleb(&mut self) -> Result<u64> {
+        let mut val = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= 64 {
+                return Err(INVALID.into());
+            }
+            val |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(val);
+            }
+            shift += 7;
+        }
+    }
+
+    fn sleb(&mut self) -> Result<i64> {
+        let mut val = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= 64 {
+                return Err(INVALID.into());
+            }
+            val |= i64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    val |= !0 << shift;
+                }
+                return Ok(val);
+            }
+        }
+    }
+
+    fn usize(&mut self) -> Result<usize> {
+        Ok(self.uleb()? as usize)
+    }
+
+    fn str(&mut self) -> Result<&'input str> {
+        let len = self.usize()?;
+        let bytes = self.bytes(len)?;
+        let s = str::from_utf8(bytes).ok().ok_or(INVALID)?;
+        Ok(s)
+    }
+
+    fn opt_str(&mut self) -> Result<Option<&'input str>> {
+        if self.bool()? {
+            Ok(Some(self.str()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn opt_uleb(&mut self) -> Result<Option<u64>> {
+        if self.bool()? {
+            Ok(Some(self.uleb()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn opt_sleb(&mut self) -> Result<Option<i64>> {
+        if self.bool()? {
+            Ok(Some(self.sleb()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn opt_usize(&mut self) -> Result<Option<usize>> {
+        Ok(self.opt_uleb()?.map(|val| val as usize))
+    }
+
+    fn address(&mut self) -> Result<Address> {
+        Ok(match self.opt_uleb()? {
+            Some(address) => Address::new(address),
+            None => Address::none(),
+        })
+    }
+
+    fn size(&mut self) -> Result<Size> {
+        Ok(self.opt_uleb()?.into())
+    }
+
+    fn type_offset(&mut self) -> Result<TypeOffset> {
+        Ok(match self.opt_usize()? {
+            Some(offset) => TypeOffset::new(offset),
+            None => TypeOffset::none(),
+        })
+    }
+
+    fn function_offset(&mut self) -> Result<FunctionOffset> {
+        Ok(match self.opt_usize()? {
+            Some(offset) => FunctionOffset::new(offset),
+            None => FunctionOffset::none(),
+        })
+    }
+
+    fn variable_offset(&mut self) -> Result<VariableOffset> {
+        Ok(match self.opt_usize()? {
+            Some(offset) => VariableOffset::new(offset),
+            None => VariableOffset::none(),
+        })
+    }
+
+    fn parameter_offset(&mut self) -> Result<ParameterOffset> {
+        Ok(match self.opt_usize()? {
+            Some(offset) => ParameterOffset::new(offset),
+            None => ParameterOffset::none(),
+        })
+    }
+
+    fn member_offset(&mut self) -> Result<MemberOffset> {
+        Ok(match self.opt_usize()? {
+            Some(offset) => MemberOffset::new(offset),
+            None => MemberOffset::none(),
+        })
+    }
+
+    fn vec<T, F>(&mut self, mut f: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> Result<T>,
+    {
+        let len = self.usize()?;
+        let mut vals = Vec::new();
+        for _ in 0..len {
+            vals.push(f(self)?);
+        }
+        Ok(vals)
+    }
+
+    fn section(&mut self) -> Result<Section<'input>> {
+        Ok(Section {
+            name: self.opt_str()?.map(Cow::Borrowed),
+            segment: self.opt_str()?.map(Cow::Borrowed),
+            address: self.opt_uleb()?,
+            size: self.uleb()?,
+        })
+    }
+
+    fn call(&mut self) -> Result<Call<'input>> {
+        Ok(Call {
+            from: self.uleb()?,
+            to: self.uleb()?,
+            import: self.opt_str()?,
+        })
+    }
+
+    fn range(&mut self) -> Result<Range> {
+        Ok(Range {
+            begin: self.uleb()?,
+            end: self.uleb()?,
+        })
+    }
+
+    fn source(&mut self) -> Result<Source<'input>> {
+        Ok(Source {
+            directory: self.opt_str()?,
+            file: self.opt_str()?,
+            line: self.uleb()? as u32,
+            column: self.uleb()? as u32,
+        })
+    }
+
+    fn namespace(&mut self) -> Result<Option<Rc<Namespace<'input>>>> {
+        if !self.bool()? {
+            return Ok(None);
+        }
+        let parent = self.namespace()?;
+        let name = self.opt_str()?;
+        let kind = match self.u8()? {
+            0 => NamespaceKind::Namespace,
+            1 => NamespaceKind::Function,
+            2 => NamespaceKind::Type,
+            _ => return Err(INVALID.into()),
+        };
+        Ok(Some(Namespace::new(&parent, name, kind)))
+    }
+
+    fn unit(&mut self, details: &mut HashMap<FunctionOffset, usize>) -> Result<Unit<'input>> {
+        let dir = self.opt_str()?.map(Cow::Borrowed);
+        let name = self.opt_str()?.map(Cow::Borrowed);
+        let language = self
+            .opt_uleb()?
+            .map(|language| gimli::DwLang(language as u16));
+        let address_size = self.opt_uleb()?;
+        let low_pc = self.opt_uleb()?;
+        let mut ranges = RangeList::default();
+        for range in self.vec(Reader::range)? {
+            ranges.push(range);
+        }
+        let types = self.vec(Reader::ty)?;
+        let functions = self.vec(|r| r.function(details))?;
+        let variables = self.vec(Reader::variable)?;
+        Ok(Unit {
+            dir,
+            name,
+            language,
+            address_size,
+            low_pc,
+            ranges,
+            lines: Vec::new(),
+            types,
+            functions,
+            variables,
+        })
+    }
+
+    fn ty(&mut self) -> Result<Type<'input>> {
+        let offset = self.type_offset()?;
+        let kind = match self.u8()? {
+            0 => TypeKind::Void,
+            1 => TypeKind::Base(BaseType {
+                name: self.opt_str()?,
+                byte_size: self.size()?,
+                encoding: self.encoding()?,
+                endianity: self.endianity()?,
+            }),
+            2 => TypeKind::Def(TypeDef {
+                namespace: self.namespace()?,
+                name: self.opt_str()?,
+                ty: self.type_offset()?,
+                source: self.source()?,
+            }),
+            3 => TypeKind::Struct(StructType {
+                namespace: self.namespace()?,
+                name: self.opt_str()?,
+                source: self.source()?,
+                byte_size: self.size()?,
+                declaration: self.bool()?,
+                members: self.vec(Reader::member)?,
+                variant_parts: self.vec(Reader::variant_part)?,
+                inherits: self.vec(Reader::inherit)?,
+            }),
+            4 => TypeKind::Union(UnionType {
+                namespace: self.namespace()?,
+                name: self.opt_str()?,
+                source: self.source()?,
+                byte_size: self.size()?,
+                declaration: self.bool()?,
+                members: self.vec(Reader::member)?,
+            }),
+            5 => TypeKind::Enumeration(EnumerationType {
+                offset: self.type_offset()?,
+                namespace: self.namespace()?,
+                name: self.opt_str()?,
+                source: self.source()?,
+                declaration: self.bool()?,
+                ty: self.type_offset()?,
+                byte_size: self.size()?,
+            }),
+            6 => TypeKind::Array(ArrayType {
+                ty: self.type_offset()?,
+                count: self.size()?,
+                byte_size: self.size()?,
+                ..Default::default()
+            }),
+            7 => TypeKind::Function(FunctionType {
+                parameters: self.vec(Reader::parameter_type)?,
+                return_type: self.type_offset()?,
+                byte_size: self.size()?,
+                variadic: self.bool()?,
+                prototyped: self.bool()?,
+            }),
+            8 => TypeKind::Unspecified(UnspecifiedType {
+                namespace: self.namespace()?,
+                name: self.opt_str()?,
+            }),
+            9 => TypeKind::PointerToMember(PointerToMemberType {
+                ty: self.type_offset()?,
+                containing_ty: self.type_offset()?,
+                byte_size: self.size()?,
+                address_size: self.opt_uleb()?,
+            }),
+            10 => TypeKind::Modifier(TypeModifier {
+                kind: self.modifier_kind()?,
+                ty: self.type_offset()?,
+                name: self.opt_str()?,
+                byte_size: self.size()?,
+                address_size: self.opt_uleb()?,
+            }),
+            11 => TypeKind::Subrange(SubrangeType {
+                name: self.opt_str()?,
+                ty: self.type_offset()?,
+                lower: self.opt_uleb()?,
+                upper: self.opt_uleb()?,
+                byte_size: self.size()?,
+            }),
+            _ => return Err(INVALID.into()),
+        };
+        Ok(Type {
+            id: Cell::new(0),
+            offset,
+            kind,
+        })
+    }
+
+    fn encoding(&mut self) -> Result<BaseTypeEncoding> {
+        Ok(match self.u8()? {
+            0 => BaseTypeEncoding::Other,
+            1 => BaseTypeEncoding::Boolean,
+            2 => BaseTypeEncoding::Address,
+            3 => BaseTypeEncoding::Signed,
+            4 => BaseTypeEncoding::SignedChar,
+            5 => BaseTypeEncoding::Unsigned,
+            6 => BaseTypeEncoding::UnsignedChar,
+            7 => BaseTypeEncoding::Float,
+            _ => return Err(INVALID.into()),
+        })
+    }
+
+    fn endianity(&mut self) -> Result<Endianity> {
+        Ok(match self.u8()? {
+            0 => Endianity::Default,
+            1 => Endianity::Big,
+            2 => Endianity::Little,
+            _ => return Err(INVALID.into()),
+        })
+    }
+
+    fn modifier_kind(&mut self) -> Result<TypeModifierKind> {
+        Ok(match self.u8()? {
+            0 => TypeModifierKind::Pointer,
+            1 => TypeModifierKind::Reference,
+            2 => TypeModifierKind::Const,
+            3 => TypeModifierKind::Packed,
+            4 => TypeModifierKind::Volatile,
+            5 => TypeModifierKind::Restrict,
+            6 => TypeModifierKind::Shared,
+            7 => TypeModifierKind::RvalueReference,
+            8 => TypeModifierKind::Atomic,
+            9 => TypeModifierKind::Other,
+            _ => return Err(INVALID.into()),
+        })
+    }
+
+    fn member(&mut self) -> Result<Member<'input>> {
+        Ok(Member {
+            offset: self.member_offset()?,
+            name: self.opt_str()?,
+            ty: self.type_offset()?,
+            bit_offset: self.uleb()?,
+            bit_size: self.size()?,
+        })
+    }
+
+    fn variant_part(&mut self) -> Result<VariantPart<'input>> {
+        Ok(VariantPart {
+            discr: self.member_offset()?,
+            variants: self.vec(Reader::variant)?,
+        })
+    }
+
+    fn variant(&mut self) -> Result<Variant<'input>> {
+        Ok(Variant {
+            discr: self.member_offset()?,
+            discr_value: self.opt_uleb()?,
+            name: self.opt_str()?,
+            members: self.vec(Reader::member)?,
+        })
+    }
+
+    fn inherit(&mut self) -> Result<Inherit> {
+        Ok(Inherit {
+            ty: self.type_offset()?,
+            bit_offset: self.uleb()?,
+        })
+    }
+
+    fn enumerator(&mut self) -> Result<Enumerator<'input>> {
+        Ok(Enumerator {
+            name: self.opt_str()?,
+            value: self.opt_sleb()?,
+        })
+    }
+
+    fn parameter_type(&mut self) -> Result<ParameterType<'input>> {
+        Ok(ParameterType {
+            offset: self.parameter_offset()?,
+            name: self.opt_str()?,
+            ty: self.type_offset()?,
+        })
+    }
+
+    fn function(
+        &mut self,
+        details: &mut HashMap<FunctionOffset, usize>,
+    ) -> Result<Function<'input>> {
+        let function = Function {
+            offset: self.function_offset()?,
+            namespace: self.namespace()?,
+            name: self.opt_str()?,
+            linkage_name: self.opt_str()?,
+            symbol_name: self.opt_str()?,
+            source: self.source()?,
+            address: self.address()?,
+            size: self.size()?,
+            ranges: self.vec(Reader::range)?,
+            inline: self.bool()?,
+            declaration: self.bool()?,
+            parameters: self.vec(Reader::parameter_type)?,
+            return_type: self.type_offset()?,
+            frame_base: if self.bool()? {
+                Some(self.location()?)
+            } else {
+                None
+            },
+            ..Default::default()
+        };
+        let len = self.usize()?;
+        if function.offset.is_some() {
+            details.insert(function.offset, self.position);
+        }
+        self.bytes(len)?;
+        Ok(function)
+    }
+
+    fn function_details(&mut self) -> Result<FunctionDetails<'input>> {
+        Ok(FunctionDetails {
+            parameters: self.vec(Reader::parameter)?,
+            variables: self.vec(Reader::local_variable)?,
+            inlined_functions: self.vec(Reader::inlined_function)?,
+        })
+    }
+
+    fn parameter(&mut self) -> Result<Parameter<'input>> {
+        Ok(Parameter {
+            offset: self.parameter_offset()?,
+            name: self.opt_str()?,
+            ty: self.type_offset()?,
+            locations: self.vec(Reader::piece)?,
+        })
+    }
+
+    fn local_variable(&mut self) -> Result<LocalVariable<'input>> {
+        Ok(LocalVariable {
+            offset: self.variable_offset()?,
+            name: self.opt_str()?,
+            ty: self.type_offset()?,
+            source: self.source()?,
+            address: self.address()?,
+            size: self.size()?,
+            locations: self.vec(Reader::piece)?,
+        })
+    }
+
+    fn inlined_function(&mut self) -> Result<InlinedFunction<'input>> {
+        Ok(InlinedFunction {
+            abstract_origin: self.function_offset()?,
+            size: self.size()?,
+            ranges: self.vec(Reader::range)?,
+            parameters: self.vec(Reader::parameter)?,
+            variables: self.vec(Reader::local_variable)?,
+            inlined_functions: self.vec(Reader::inlined_function)?,
+            call_source: self.source()?,
+        })
+    }
+
+    fn variable(&mut self) -> Result<Variable<'input>> {
+        Ok(Variable {
+            offset: self.variable_offset()?,
+            namespace: self.namespace()?,
+            name: self.opt_str()?,
+            linkage_name: self.opt_str()?,
+            symbol_name: self.opt_str()?,
+            ty: self.type_offset()?,
+            source: self.source()?,
+            address: self.address()?,
+            size: self.size()?,
+            declaration: self.bool()?,
+            ..Default::default()
+        })
+    }
+
+    fn piece(&mut self) -> Result<(Range, Piece)> {
+        let range = self.range()?;
+        let piece = Piece {
+            bit_offset: self.uleb()?,
+            bit_size: self.size()?,
+            location: self.location()?,
+            location_offset: self.uleb()?,
+            is_value: self.bool()?,
+        };
+        Ok((range, piece))
+    }
+
+    fn location(&mut self) -> Result<Location> {
+        Ok(match self.u8()? {
+            0 => Location::Empty,
+            1 => Location::Literal {
+                value: self.uleb()?,
+            },
+            2 => Location::Register {
+                register: Register(self.uleb()? as u16),
+            },
+            3 => Location::RegisterOffset {
+                register: Register(self.uleb()? as u16),
+                offset: self.sleb()?,
+            },
+            4 => Location::FrameOffset {
+                offset: self.sleb()?,
+            },
+            5 => Location::CfaOffset {
+                offset: self.sleb()?,
+            },
+            6 => Location::Address {
+                address: self.address()?,
+            },
+            7 => Location::TlsOffset {
+                offset: self.uleb()?,
+            },
+            8 => Location::Other,
+            _ => return Err(INVALID.into()),
+        })
+    }
+}
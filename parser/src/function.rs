@@ -6,7 +6,7 @@ use std::usize;
 
 use crate::cfi::Cfi;
 use crate::file::FileHash;
-use crate::location::{self, FrameLocation, Piece, Register};
+use crate::location::{self, FrameLocation, Location, Piece, Register};
 use crate::namespace::Namespace;
 use crate::range::Range;
 use crate::source::Source;
@@ -71,10 +71,12 @@ pub struct Function<'input> {
     pub(crate) source: Source<'input>,
     pub(crate) address: Address,
     pub(crate) size: Size,
+    pub(crate) ranges: Vec<Range>,
     pub(crate) inline: bool,
     pub(crate) declaration: bool,
     pub(crate) parameters: Vec<ParameterType<'input>>,
     pub(crate) return_type: TypeOffset,
+    pub(crate) frame_base: Option<Location>,
 }
 
 /// Extra function details.
@@ -98,6 +100,12 @@ impl<'input> Function<'input> {
         hash.functions_by_offset.get(&offset).cloned()
     }
 
+    /// The debuginfo offset of this function.
+    #[inline]
+    pub fn offset(&self) -> FunctionOffset {
+        self.offset
+    }
+
     /// The user defined id for this function.
     #[inline]
     pub fn id(&self) -> usize {
@@ -167,6 +175,15 @@ impl<'input> Function<'input> {
         }
     }
 
+    /// The address ranges of the function.
+    ///
+    /// This includes parts of the function that are not contiguous with its
+    /// address, such as cold code that has been moved to another section.
+    #[inline]
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
     /// Return true if this is an inlined function.
     #[inline]
     pub fn is_inline(&self) -> bool {
@@ -193,6 +210,15 @@ impl<'input> Function<'input> {
         Type::from_offset(hash, self.return_type)
     }
 
+    /// The location of the frame base.
+    ///
+    /// The frame base is used to evaluate the `FrameOffset` locations of parameters
+    /// and local variables. The frame base is the address of this location.
+    #[inline]
+    pub fn frame_base(&self) -> Option<Location> {
+        self.frame_base
+    }
+
     /// Extra function details.
     pub fn details(&self, hash: &FileHash<'input>) -> FunctionDetails<'input> {
         hash.file.get_function_details(self.offset, hash)
@@ -253,6 +279,15 @@ impl ParameterOffset {
     pub(crate) fn none() -> ParameterOffset {
         ParameterOffset(usize::MAX)
     }
+
+    #[inline]
+    pub(crate) fn get(self) -> Option<usize> {
+        if self == Self::none() {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
 }
 
 impl Default for ParameterOffset {
@@ -298,6 +333,13 @@ impl<'input> Parameter<'input> {
         self.ty(hash).and_then(|v| v.byte_size(hash))
     }
 
+    /// The locations of the pieces of this parameter, and the address ranges for which
+    /// they are valid.
+    #[inline]
+    pub fn locations(&self) -> &[(Range, Piece)] {
+        &self.locations
+    }
+
     /// The registers in which this parameter is stored.
     pub fn registers<'a>(&'a self) -> impl Iterator<Item = (Range, Register)> + 'a {
         location::registers(&self.locations)
@@ -349,6 +391,7 @@ impl<'input> Parameter<'input> {
 pub struct InlinedFunction<'input> {
     pub(crate) abstract_origin: FunctionOffset,
     pub(crate) size: Size,
+    pub(crate) ranges: Vec<Range>,
     pub(crate) parameters: Vec<Parameter<'input>>,
     pub(crate) variables: Vec<LocalVariable<'input>>,
     pub(crate) inlined_functions: Vec<InlinedFunction<'input>>,
@@ -368,6 +411,12 @@ impl<'input> InlinedFunction<'input> {
         self.size.get()
     }
 
+    /// The address ranges of the inlined function.
+    #[inline]
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
     /// The source information for call location.
     #[inline]
     pub fn call_source(&self) -> &Source<'input> {
@@ -15,6 +15,20 @@
 //!     Ok(())
 //! });
 //! ```
+//!
+//! ## Serialization
+//!
+//! The optional `serde` feature implements `serde::Serialize` for the data model.
+//! Units, types, functions and variables refer to other types by offset, so they must
+//! be wrapped in a `Serializable` together with a `FileHash` that is used to resolve
+//! the names of the referenced items:
+//!
+//! ```rust,ignore
+//! let hash = ddbug_parser::FileHash::new(file);
+//! for unit in file.units() {
+//!     serde_json::to_writer(&mut out, &ddbug_parser::Serializable::new(&hash, unit))?;
+//! }
+//! ```
 #![deny(missing_docs)]
 // Enable some rust 2018 idioms.
 #![warn(bare_trait_objects)]
@@ -31,9 +45,12 @@ extern crate log;
 mod cfi;
 mod file;
 mod function;
+mod line;
 mod location;
 mod namespace;
 mod range;
+#[cfg(feature = "serde")]
+mod serialize;
 mod source;
 mod types;
 mod unit;
@@ -42,9 +59,12 @@ mod variable;
 pub use crate::cfi::*;
 pub use crate::file::*;
 pub use crate::function::*;
+pub use crate::line::*;
 pub use crate::location::*;
 pub use crate::namespace::*;
 pub use crate::range::*;
+#[cfg(feature = "serde")]
+pub use crate::serialize::*;
 pub use crate::source::*;
 pub use crate::types::*;
 pub use crate::unit::*;
@@ -0,0 +1,90 @@
+use crate::function::Function;
+use crate::range::Range;
+use crate::source::Source;
+use crate::unit::Unit;
+
+/// A row in the line number table of a unit.
+///
+/// Each row covers the range of addresses up to the start of the next row
+/// in the same sequence.
+#[derive(Debug, Default, Clone)]
+pub struct Line<'input> {
+    pub(crate) range: Range,
+    pub(crate) source: Source<'input>,
+}
+
+impl<'input> Line<'input> {
+    /// The address range covered by this row.
+    #[inline]
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    /// The source location for the addresses in this row.
+    #[inline]
+    pub fn source(&self) -> &Source<'input> {
+        &self.source
+    }
+}
+
+/// A frame in the chain of inlined functions that contain an address.
+#[derive(Debug)]
+pub struct InlineFrame<'a, 'input> {
+    pub(crate) function: Option<&'a Function<'input>>,
+    pub(crate) source: Source<'input>,
+}
+
+impl<'a, 'input> InlineFrame<'a, 'input> {
+    /// The function for this frame.
+    ///
+    /// For inlined functions, this is the abstract origin.
+    #[inline]
+    pub fn function(&self) -> Option<&'a Function<'input>> {
+        self.function
+    }
+
+    /// The source location within the function for this frame.
+    ///
+    /// For the innermost frame, this is determined from the line number table.
+    /// For other frames, this is the call location of the next inner frame.
+    #[inline]
+    pub fn source(&self) -> &Source<'input> {
+        &self.source
+    }
+}
+
+/// The source location of an address.
+#[derive(Debug)]
+pub struct AddressLocation<'a, 'input> {
+    pub(crate) address: u64,
+    pub(crate) unit: &'a Unit<'input>,
+    pub(crate) frames: Vec<InlineFrame<'a, 'input>>,
+}
+
+impl<'a, 'input> AddressLocation<'a, 'input> {
+    /// The address that was looked up.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The unit containing the address.
+    #[inline]
+    pub fn unit(&self) -> &'a Unit<'input> {
+        self.unit
+    }
+
+    /// The source location from the line number table.
+    pub fn source(&self) -> Option<&Source<'input>> {
+        self.frames.first().map(InlineFrame::source)
+    }
+
+    /// The frames containing the address.
+    ///
+    /// The innermost inlined function is first, and the concrete function
+    /// is last.
+    #[inline]
+    pub fn frames(&self) -> &[InlineFrame<'a, 'input>] {
+        &self.frames
+    }
+}
@@ -3,6 +3,7 @@ use crate::{Address, Range, Size};
 
 /// A register number.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Register(pub u16);
 
 impl Register {
@@ -24,7 +25,8 @@ pub struct FrameLocation {
 /// A piece of a value.
 // TODO: include the address ranges for which this piece is valid
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) struct Piece {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Piece {
     /// The offset of the piece within the containing object.
     pub bit_offset: u64,
     /// The size of the piece. If none, then the piece is the complete value.
@@ -40,7 +42,8 @@ pub(crate) struct Piece {
 
 /// A value location.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) enum Location {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Location {
     /// The value has been optimized away.
     Empty,
     /// A literal address or value.
@@ -72,7 +75,7 @@ pub(crate) enum Location {
     },
     /// The value is stored in memory at an address. This address may need relocation.
     Address {
-        /// The offset.
+        /// The address.
         address: Address,
     },
     /// The value is stored in memory at an offset within TLS.
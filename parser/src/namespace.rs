@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 /// A namespace kind.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum NamespaceKind {
     /// An explicit namespace.
     Namespace,
@@ -98,6 +99,30 @@ impl<'input> Namespace<'input> {
         }
     }
 
+    /// Return `name` prefixed by the names of `namespace` and its parents.
+    ///
+    /// Names are separated by `::`, and function namespaces are followed by `()`.
+    /// Anonymous namespaces are named `<anon>`.
+    pub fn qualified_name(namespace: Option<&Namespace>, name: &str) -> String {
+        fn push(namespace: &Namespace, s: &mut String) {
+            if let Some(parent) = namespace.parent() {
+                push(parent, s);
+            }
+            s.push_str(namespace.name().unwrap_or("<anon>"));
+            if namespace.kind() == NamespaceKind::Function {
+                s.push_str("()");
+            }
+            s.push_str("::");
+        }
+
+        let mut s = String::new();
+        if let Some(namespace) = namespace {
+            push(namespace, &mut s);
+        }
+        s.push_str(name);
+        s
+    }
+
     /// Return true if this namespace is within the given namespace.
     ///
     /// `namespace` is a slice of names, starting with the root namespace name.
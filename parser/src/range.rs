@@ -2,6 +2,7 @@ use std::mem;
 
 /// An address range.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Range {
     /// The beginning of the address range (inclusive).
     pub begin: u64,
@@ -0,0 +1,638 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::file::FileHash;
+use crate::function::{Function, FunctionDetails, FunctionOffset, InlinedFunction, Parameter};
+use crate::namespace::Namespace;
+use crate::types::{
+    ArrayType, EnumerationType, FunctionType, Inherit, Member, ParameterType, PointerToMemberType,
+    StructType, SubrangeType, Type, TypeDef, TypeKind, TypeModifier, TypeOffset, UnionType,
+    UnspecifiedType, Variant, VariantPart,
+};
+use crate::unit::Unit;
+use crate::variable::{LocalVariable, Variable, VariableOffset};
+use crate::{Address, Size};
+
+/// A value that uses a `FileHash` to resolve references during serialization.
+///
+/// References to types and functions are serialized with both the offset and the
+/// name of the referenced item. Offsets are unique within a file, so they can be
+/// used to find the referenced type or function in the serialized units.
+pub struct Serializable<'a, 'input, T: ?Sized> {
+    hash: &'a FileHash<'input>,
+    value: &'a T,
+}
+
+impl<'a, 'input, T: ?Sized> Serializable<'a, 'input, T> {
+    /// Wrap a value so that it can be serialized.
+    pub fn new(hash: &'a FileHash<'input>, value: &'a T) -> Self {
+        Serializable { hash, value }
+    }
+}
+
+impl<'a, 'input, T> Serialize for Serializable<'a, 'input, [T]>
+where
+    Serializable<'a, 'input, T>: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let values = self.value;
+        serializer.collect_seq(values.iter().map(|value| Serializable::new(hash, value)))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl Serialize for Size {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl Serialize for TypeOffset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl Serialize for FunctionOffset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl Serialize for VariableOffset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'input> Serialize for Namespace<'input> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Namespace", 3)?;
+        state.serialize_field("parent", &self.parent())?;
+        state.serialize_field("name", &self.name())?;
+        state.serialize_field("kind", &self.kind())?;
+        state.end()
+    }
+}
+
+fn qualified_name(namespace: Option<&Namespace>, name: Option<&str>) -> Option<String> {
+    name.map(|name| Namespace::qualified_name(namespace, name))
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match *ty.kind() {
+        TypeKind::Void => Some("void".to_string()),
+        TypeKind::Base(ref val) => val.name().map(String::from),
+        TypeKind::Def(ref val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Struct(ref val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Union(ref val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Enumeration(ref val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Unspecified(ref val) => qualified_name(val.namespace(), val.name()),
+        TypeKind::Modifier(ref val) => val.name().map(String::from),
+        TypeKind::Subrange(ref val) => val.name().map(String::from),
+        TypeKind::Array(..) | TypeKind::Function(..) | TypeKind::PointerToMember(..) => None,
+    }
+}
+
+/// A reference to a type, serialized as its offset and name.
+struct TypeRef<'a, 'input> {
+    hash: &'a FileHash<'input>,
+    offset: TypeOffset,
+}
+
+impl<'a, 'input> Serialize for TypeRef<'a, 'input> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = Type::from_offset(self.hash, self.offset).and_then(|ty| type_name(&ty));
+        let mut state = serializer.serialize_struct("TypeRef", 2)?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("name", &name)?;
+        state.end()
+    }
+}
+
+/// A reference to a function, serialized as its offset and name.
+struct FunctionRef<'a, 'input> {
+    hash: &'a FileHash<'input>,
+    offset: FunctionOffset,
+}
+
+impl<'a, 'input> Serialize for FunctionRef<'a, 'input> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = self
+            .hash
+            .functions_by_offset
+            .get(&self.offset)
+            .and_then(|function| qualified_name(function.namespace(), function.name()));
+        let mut state = serializer.serialize_struct("FunctionRef", 2)?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("name", &name)?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Unit<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let unit = self.value;
+        let language = unit
+            .language()
+            .and_then(|language| language.static_string());
+        let mut state = serializer.serialize_struct("Unit", 8)?;
+        state.serialize_field("dir", &unit.dir())?;
+        state.serialize_field("name", &unit.name())?;
+        state.serialize_field("language", &language)?;
+        state.serialize_field("address", &unit.address())?;
+        state.serialize_field("ranges", unit.ranges(hash).list())?;
+        state.serialize_field("types", &Serializable::new(hash, unit.types()))?;
+        state.serialize_field("functions", &Serializable::new(hash, unit.functions()))?;
+        state.serialize_field("variables", &Serializable::new(hash, unit.variables()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Type<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Type", 2)?;
+        state.serialize_field("offset", &self.value.offset())?;
+        state.serialize_field("kind", &Serializable::new(self.hash, self.value.kind()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, TypeKind<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let name = "TypeKind";
+        match *self.value {
+            TypeKind::Void => serializer.serialize_unit_variant(name, 0, "Void"),
+            TypeKind::Base(ref val) => serializer.serialize_newtype_variant(name, 1, "Base", val),
+            TypeKind::Def(ref val) => {
+                serializer.serialize_newtype_variant(name, 2, "Def", &Serializable::new(hash, val))
+            }
+            TypeKind::Struct(ref val) => serializer.serialize_newtype_variant(
+                name,
+                3,
+                "Struct",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Union(ref val) => serializer.serialize_newtype_variant(
+                name,
+                4,
+                "Union",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Enumeration(ref val) => serializer.serialize_newtype_variant(
+                name,
+                5,
+                "Enumeration",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Array(ref val) => serializer.serialize_newtype_variant(
+                name,
+                6,
+                "Array",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Function(ref val) => serializer.serialize_newtype_variant(
+                name,
+                7,
+                "Function",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Unspecified(ref val) => serializer.serialize_newtype_variant(
+                name,
+                8,
+                "Unspecified",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::PointerToMember(ref val) => serializer.serialize_newtype_variant(
+                name,
+                9,
+                "PointerToMember",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Modifier(ref val) => serializer.serialize_newtype_variant(
+                name,
+                10,
+                "Modifier",
+                &Serializable::new(hash, val),
+            ),
+            TypeKind::Subrange(ref val) => serializer.serialize_newtype_variant(
+                name,
+                11,
+                "Subrange",
+                &Serializable::new(hash, val),
+            ),
+        }
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, TypeDef<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("TypeDef", 4)?;
+        state.serialize_field("namespace", &ty.namespace())?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field("source", ty.source())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, StructType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("StructType", 8)?;
+        state.serialize_field("namespace", &ty.namespace())?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field("source", ty.source())?;
+        state.serialize_field("byte_size", &ty.byte_size())?;
+        state.serialize_field("declaration", &ty.is_declaration())?;
+        state.serialize_field("members", &Serializable::new(hash, ty.members()))?;
+        state.serialize_field(
+            "variant_parts",
+            &Serializable::new(hash, ty.variant_parts()),
+        )?;
+        state.serialize_field("inherits", &Serializable::new(hash, ty.inherits()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, UnionType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("UnionType", 6)?;
+        state.serialize_field("namespace", &ty.namespace())?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field("source", ty.source())?;
+        state.serialize_field("byte_size", &ty.byte_size())?;
+        state.serialize_field("declaration", &ty.is_declaration())?;
+        state.serialize_field("members", &Serializable::new(hash, ty.members()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, VariantPart<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let part = self.value;
+        let mut state = serializer.serialize_struct("VariantPart", 2)?;
+        state.serialize_field("bit_offset", &part.bit_offset())?;
+        state.serialize_field("variants", &Serializable::new(hash, part.variants()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Variant<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let variant = self.value;
+        let mut state = serializer.serialize_struct("Variant", 3)?;
+        state.serialize_field("name", &variant.name())?;
+        state.serialize_field("discriminant_value", &variant.discriminant_value())?;
+        state.serialize_field("members", &Serializable::new(hash, variant.members()))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Member<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let member = self.value;
+        let mut state = serializer.serialize_struct("Member", 4)?;
+        state.serialize_field("name", &member.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: member.type_offset(),
+            },
+        )?;
+        state.serialize_field("bit_offset", &member.bit_offset())?;
+        state.serialize_field("bit_size", &member.bit_size(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Inherit> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let inherit = self.value;
+        let mut state = serializer.serialize_struct("Inherit", 2)?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: inherit.type_offset(),
+            },
+        )?;
+        state.serialize_field("bit_offset", &inherit.bit_offset())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, EnumerationType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("EnumerationType", 7)?;
+        state.serialize_field("namespace", &ty.namespace())?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field("source", ty.source())?;
+        state.serialize_field("declaration", &ty.is_declaration())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field("byte_size", &ty.byte_size(hash))?;
+        state.serialize_field("enumerators", &ty.enumerators(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, ArrayType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("ArrayType", 3)?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field("count", &ty.count(hash))?;
+        state.serialize_field("byte_size", &ty.byte_size(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, FunctionType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("FunctionType", 5)?;
+        state.serialize_field("parameters", &Serializable::new(hash, ty.parameters()))?;
+        state.serialize_field(
+            "return_type",
+            &TypeRef {
+                hash,
+                offset: ty.return_type,
+            },
+        )?;
+        state.serialize_field("byte_size", &ty.byte_size())?;
+        state.serialize_field("variadic", &ty.is_variadic())?;
+        state.serialize_field("prototyped", &ty.is_prototyped())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, ParameterType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let parameter = self.value;
+        let mut state = serializer.serialize_struct("ParameterType", 2)?;
+        state.serialize_field("name", &parameter.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: parameter.ty,
+            },
+        )?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, UnspecifiedType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("UnspecifiedType", 2)?;
+        state.serialize_field("namespace", &ty.namespace())?;
+        state.serialize_field("name", &ty.name())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, PointerToMemberType> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("PointerToMemberType", 3)?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field(
+            "containing_type",
+            &TypeRef {
+                hash,
+                offset: ty.containing_ty,
+            },
+        )?;
+        state.serialize_field("byte_size", &ty.byte_size(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, TypeModifier<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("TypeModifier", 4)?;
+        state.serialize_field("kind", &ty.kind())?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field("byte_size", &ty.byte_size(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, SubrangeType<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let ty = self.value;
+        let mut state = serializer.serialize_struct("SubrangeType", 5)?;
+        state.serialize_field("name", &ty.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: ty.ty,
+            },
+        )?;
+        state.serialize_field("lower", &ty.lower())?;
+        state.serialize_field("upper", &ty.upper())?;
+        state.serialize_field("byte_size", &ty.byte_size(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Function<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let function = self.value;
+        let details = function.details(hash);
+        let mut state = serializer.serialize_struct("Function", 15)?;
+        state.serialize_field("offset", &function.offset())?;
+        state.serialize_field("namespace", &function.namespace())?;
+        state.serialize_field("name", &function.name())?;
+        state.serialize_field("linkage_name", &function.linkage_name())?;
+        state.serialize_field("symbol_name", &function.symbol_name())?;
+        state.serialize_field("source", function.source())?;
+        state.serialize_field("address", &function.address())?;
+        state.serialize_field("size", &function.size())?;
+        state.serialize_field("ranges", function.ranges())?;
+        state.serialize_field("inline", &function.is_inline())?;
+        state.serialize_field("declaration", &function.is_declaration())?;
+        state.serialize_field(
+            "parameters",
+            &Serializable::new(hash, function.parameters()),
+        )?;
+        state.serialize_field(
+            "return_type",
+            &TypeRef {
+                hash,
+                offset: function.return_type,
+            },
+        )?;
+        state.serialize_field("details", &Serializable::new(hash, &details))?;
+        state.serialize_field("cfi", &function.cfi(hash))?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, FunctionDetails<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let details = self.value;
+        let mut state = serializer.serialize_struct("FunctionDetails", 3)?;
+        state.serialize_field("parameters", &Serializable::new(hash, details.parameters()))?;
+        state.serialize_field("variables", &Serializable::new(hash, details.variables()))?;
+        state.serialize_field(
+            "inlined_functions",
+            &Serializable::new(hash, details.inlined_functions()),
+        )?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Parameter<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let parameter = self.value;
+        let mut state = serializer.serialize_struct("Parameter", 4)?;
+        state.serialize_field("name", &parameter.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: parameter.type_offset(),
+            },
+        )?;
+        state.serialize_field("byte_size", &parameter.byte_size(hash))?;
+        state.serialize_field("locations", parameter.locations())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, LocalVariable<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let variable = self.value;
+        let mut state = serializer.serialize_struct("LocalVariable", 6)?;
+        state.serialize_field("name", &variable.name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: variable.type_offset(),
+            },
+        )?;
+        state.serialize_field("source", variable.source())?;
+        state.serialize_field("address", &variable.address())?;
+        state.serialize_field("byte_size", &variable.byte_size(hash))?;
+        state.serialize_field("locations", variable.locations())?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, InlinedFunction<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let function = self.value;
+        let mut state = serializer.serialize_struct("InlinedFunction", 7)?;
+        state.serialize_field(
+            "abstract_origin",
+            &FunctionRef {
+                hash,
+                offset: function.abstract_origin,
+            },
+        )?;
+        state.serialize_field("size", &function.size())?;
+        state.serialize_field("ranges", function.ranges())?;
+        state.serialize_field("call_source", function.call_source())?;
+        state.serialize_field(
+            "parameters",
+            &Serializable::new(hash, function.parameters()),
+        )?;
+        state.serialize_field("variables", &Serializable::new(hash, function.variables()))?;
+        state.serialize_field(
+            "inlined_functions",
+            &Serializable::new(hash, function.inlined_functions()),
+        )?;
+        state.end()
+    }
+}
+
+impl<'a, 'input> Serialize for Serializable<'a, 'input, Variable<'input>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash = self.hash;
+        let variable = self.value;
+        let mut state = serializer.serialize_struct("Variable", 10)?;
+        state.serialize_field("offset", &variable.offset())?;
+        state.serialize_field("namespace", &variable.namespace())?;
+        state.serialize_field("name", &variable.name())?;
+        state.serialize_field("linkage_name", &variable.linkage_name())?;
+        state.serialize_field("symbol_name", &variable.symbol_name())?;
+        state.serialize_field(
+            "type",
+            &TypeRef {
+                hash,
+                offset: variable.ty,
+            },
+        )?;
+        state.serialize_field("source", variable.source())?;
+        state.serialize_field("address", &variable.address())?;
+        state.serialize_field("byte_size", &variable.byte_size(hash))?;
+        state.serialize_field("declaration", &variable.is_declaration())?;
+        state.end()
+    }
+}
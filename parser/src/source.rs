@@ -2,6 +2,7 @@ use crate::unit::Unit;
 
 /// A source location.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Source<'input> {
     pub(crate) directory: Option<&'input str>,
     pub(crate) file: Option<&'input str>,
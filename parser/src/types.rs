@@ -311,6 +311,7 @@ pub struct TypeModifier<'input> {
 
 /// The kind of a type modifier.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeModifierKind {
     /// The resulting type is a pointer to the type being modified.
     Pointer,
@@ -439,6 +440,7 @@ impl<'input> TypeModifier<'input> {
 
 /// The endianity of an object.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Endianity {
     /// Default endianity encoding.
     Default,
@@ -456,6 +458,7 @@ impl Default for Endianity {
 
 /// The encoding of a base type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BaseTypeEncoding {
     /// Unsupported or unspecified encoding.
     Other,
@@ -483,6 +486,7 @@ impl Default for BaseTypeEncoding {
 
 /// A base type.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BaseType<'input> {
     pub(crate) name: Option<&'input str>,
     pub(crate) byte_size: Size,
@@ -910,6 +914,15 @@ impl MemberOffset {
     pub(crate) fn none() -> MemberOffset {
         MemberOffset(usize::MAX)
     }
+
+    #[inline]
+    pub(crate) fn get(self) -> Option<usize> {
+        if self == Self::none() {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
 }
 
 impl Default for MemberOffset {
@@ -1171,6 +1184,7 @@ impl<'input> EnumerationType<'input> {
 
 /// A member of an enumeration.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Enumerator<'input> {
     pub(crate) name: Option<&'input str>,
     pub(crate) value: Option<i64>,
@@ -1340,6 +1354,8 @@ pub struct FunctionType<'input> {
     pub(crate) parameters: Vec<ParameterType<'input>>,
     pub(crate) return_type: TypeOffset,
     pub(crate) byte_size: Size,
+    pub(crate) variadic: bool,
+    pub(crate) prototyped: bool,
 }
 
 impl<'input> FunctionType<'input> {
@@ -1361,10 +1377,26 @@ impl<'input> FunctionType<'input> {
         self.byte_size.get()
     }
 
+    /// Return true if the function accepts a variable number of arguments
+    /// after its parameters.
+    #[inline]
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
+    /// Return true if the function type specifies its parameters.
+    ///
+    /// This is false for C function types declared without a prototype,
+    /// such as `int (*)()`.
+    #[inline]
+    pub fn is_prototyped(&self) -> bool {
+        self.prototyped
+    }
+
     /// Compare the identifying information of two types.
     ///
-    /// Function types are considered equal if they have the same parameter types and
-    /// return types. Parameter names are ignored.
+    /// Function types are considered equal if they have the same parameter types,
+    /// variadic-ness and return types. Parameter names are ignored.
     ///
     /// This can be used to sort, and to determine if two types refer to the same definition
     /// (even if there are differences in the definitions).
@@ -1386,6 +1418,11 @@ impl<'input> FunctionType<'input> {
             return ord;
         }
 
+        let ord = a.variadic.cmp(&b.variadic);
+        if ord != cmp::Ordering::Equal {
+            return ord;
+        }
+
         match (a.return_type(hash_a), b.return_type(hash_b)) {
             (Some(ref ty_a), Some(ref ty_b)) => {
                 let ord = Type::cmp_id(hash_a, ty_a, hash_b, ty_b);
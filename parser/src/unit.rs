@@ -5,6 +5,7 @@ use gimli;
 
 use crate::file::FileHash;
 use crate::function::Function;
+use crate::line::Line;
 use crate::range::RangeList;
 use crate::types::Type;
 use crate::variable::Variable;
@@ -18,6 +19,7 @@ pub struct Unit<'input> {
     pub(crate) address_size: Option<u64>,
     pub(crate) low_pc: Option<u64>,
     pub(crate) ranges: RangeList,
+    pub(crate) lines: Vec<Line<'input>>,
     pub(crate) types: Vec<Type<'input>>,
     pub(crate) functions: Vec<Function<'input>>,
     pub(crate) variables: Vec<Variable<'input>>,
@@ -124,4 +126,25 @@ impl<'input> Unit<'input> {
     pub fn variables(&self) -> &[Variable<'input>] {
         &self.variables
     }
+
+    /// The rows of the line number table, sorted by address.
+    #[inline]
+    pub fn lines(&self) -> &[Line<'input>] {
+        &self.lines
+    }
+
+    /// Find the row of the line number table that contains the address.
+    pub fn find_line(&self, address: u64) -> Option<&Line<'input>> {
+        let index = match self.lines.binary_search_by(|x| x.range.begin.cmp(&address)) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let line = &self.lines[index];
+        if line.range.contains(address) {
+            Some(line)
+        } else {
+            None
+        }
+    }
 }
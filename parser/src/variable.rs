@@ -15,7 +15,7 @@ use crate::{Address, Size};
 /// The debuginfo offset of a variable.
 ///
 /// This is unique for all variables in a file.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VariableOffset(usize);
 
 impl VariableOffset {
@@ -29,6 +29,15 @@ impl VariableOffset {
     pub(crate) fn none() -> VariableOffset {
         VariableOffset(usize::MAX)
     }
+
+    #[inline]
+    pub(crate) fn get(self) -> Option<usize> {
+        if self == Self::none() {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
 }
 
 impl Default for VariableOffset {
@@ -67,6 +76,12 @@ impl<'input> Variable<'input> {
         self.id.set(id)
     }
 
+    /// The debuginfo offset of this variable.
+    #[inline]
+    pub fn offset(&self) -> VariableOffset {
+        self.offset
+    }
+
     /// The namespace of the variable.
     pub fn namespace(&self) -> Option<&Namespace> {
         self.namespace.as_ref().map(|x| &**x)
@@ -218,6 +233,13 @@ impl<'input> LocalVariable<'input> {
         }
     }
 
+    /// The locations of the pieces of this variable, and the address ranges for which
+    /// they are valid.
+    #[inline]
+    pub fn locations(&self) -> &[(Range, Piece)] {
+        &self.locations
+    }
+
     /// The registers in which this variable is stored.
     pub fn registers<'a>(&'a self) -> impl Iterator<Item = (Range, Register)> + 'a {
         location::registers(&self.locations)
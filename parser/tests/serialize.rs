@@ -0,0 +1,38 @@
+#![cfg(feature = "serde")]
+
+use ddbug_parser::{File, FileHash, Serializable};
+
+#[test]
+fn unit() {
+    File::parse("../main/tests/bin/diff1", |file| {
+        let hash = FileHash::new(file);
+        let unit = file
+            .units()
+            .iter()
+            .find(|unit| unit.name() == Some("src/diff.c"))
+            .unwrap();
+        let value = serde_json::to_value(&Serializable::new(&hash, unit)).unwrap();
+        assert_eq!(value["name"], "src/diff.c");
+
+        let functions = value["functions"].as_array().unwrap();
+        let main = functions
+            .iter()
+            .find(|function| function["name"] == "main")
+            .unwrap();
+        assert_eq!(main["address"], 0x4004d6);
+        assert_eq!(main["size"], 11);
+        assert_eq!(main["ranges"][0]["begin"], 0x4004d6);
+
+        let types = value["types"].as_array().unwrap();
+        let ty = types
+            .iter()
+            .map(|ty| &ty["kind"]["Struct"])
+            .find(|ty| ty["name"] == "struct_diff_member")
+            .unwrap();
+        assert_eq!(ty["byte_size"], 1);
+        assert_eq!(ty["members"][0]["name"], "a");
+        assert_eq!(ty["members"][0]["bit_size"], 8);
+        Ok(())
+    })
+    .unwrap();
+}